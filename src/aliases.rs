@@ -0,0 +1,193 @@
+//! The `workspace` and `move_to_workspace` commands.
+//!
+//! Both are available as standalone binaries and from `ws` when it is invoked under their names
+//! (e.g., through a symlink), so that all tools share the mapping, dry-run, and error handling.
+
+use crate::{
+    error::{Error, ErrorFormat, Fallible},
+    initialize_workspace, placement, quote, tree, Connection, WorkspaceCli,
+};
+use clap::Parser;
+use swayipc::Node;
+
+/// Runs the `workspace` command with the process arguments and exits on errors.
+pub fn workspace_main() {
+    let cli = SwitchCli::parse();
+    let result = if cli.prepare {
+        prepare_workspace(&cli.workspace)
+    } else {
+        switch_workspace(&cli.workspace)
+    };
+    if let Err(err) = result {
+        ErrorFormat::resolve(cli.workspace.errors).exit(&err);
+    }
+}
+
+/// Runs the `move_to_workspace` command with the process arguments and exits on errors.
+pub fn move_to_workspace_main() {
+    let cli = MoveCli::parse();
+    if let Err(err) = move_to_workspace(&cli) {
+        ErrorFormat::resolve(cli.workspace.errors).exit(&err);
+    }
+}
+
+/// Switches to the workspace given by `cli`, opening it on its output if it does not exist yet.
+pub fn switch_workspace(cli: &WorkspaceCli) -> Fallible<()> {
+    let (mut sway, workspace, output, workspace_exists) = initialize_workspace(cli)?;
+
+    // If the workspace we want to go to already exists then we can just go there.
+    // Create or switch to the desired workspace.
+    sway.run(format!("workspace {}", workspace.selector()))?;
+    // The only problem is that if the workspace does not exist yet, it will be created on the same output that is currently focused.
+    // If the output where the workspace should be created is given, then we just move the workspace to this output.
+    // The new workspace is empty, so it cannot be matched by criteria, but it has the focus.
+    if let (false, Some(output)) = (workspace_exists, output) {
+        sway.run(format!("move workspace to output '{output}'"))?;
+    }
+
+    Ok(())
+}
+
+/// Opens the workspace given by `cli` on its output without focusing it.
+///
+/// The workspace is created on its output and the focused workspace is focused again. As sway
+/// destroys empty workspaces which are not visible, a new workspace is only opened (and shown) if
+/// its output is not the focused one. Nothing is assigned to the output, so sway places the
+/// workspace as usual when it is created again later.
+pub fn prepare_workspace(cli: &WorkspaceCli) -> Fallible<()> {
+    let (mut sway, workspace, output, workspace_exists) = initialize_workspace(cli)?;
+    let Some(output) = output else {
+        return Ok(());
+    };
+    if workspace_exists {
+        return Ok(());
+    }
+
+    let focused = sway
+        .get_workspaces()?
+        .into_iter()
+        .find(|ws| ws.focused)
+        .filter(|focused| focused.output != output);
+    let Some(focused) = focused else {
+        return Ok(());
+    };
+    // sway creates a new workspace on the focused output.
+    sway.run(format!(
+        "focus output {}; workspace --no-auto-back-and-forth {}; \
+         workspace --no-auto-back-and-forth {}",
+        quote(&output),
+        workspace.selector(),
+        quote(&focused.name)
+    ))
+}
+
+/// The command line interface of `workspace`.
+#[derive(clap::Parser, Debug)]
+#[command(author, version, long_about = None)]
+#[command(about = "Switches to a workspace, opening it on its mapped output")]
+pub struct SwitchCli {
+    #[command(flatten)]
+    pub workspace: WorkspaceCli,
+
+    /// Open the workspace on its output without focusing it.
+    #[arg(long)]
+    pub prepare: bool,
+}
+
+/// The command line interface of `move_to_workspace`.
+#[derive(clap::Parser, Debug)]
+#[command(author, version, long_about = None)]
+#[command(about = "Moves a window to a workspace, opening it on its mapped output")]
+pub struct MoveCli {
+    #[command(flatten)]
+    pub workspace: WorkspaceCli,
+
+    /// Focus the workspace after moving the window there.
+    #[arg(short, long)]
+    pub follow: bool,
+
+    /// Open a new workspace on its output without changing the visible workspace on any output.
+    #[arg(short, long, conflicts_with = "follow")]
+    pub silent: bool,
+
+    /// Move the window with this container id instead of the focused one.
+    #[arg(long, group = "window")]
+    pub con_id: Option<i64>,
+
+    /// Move the first window with this (Wayland) app id instead of the focused one.
+    #[arg(long, group = "window")]
+    pub app_id: Option<String>,
+
+    /// Move the first window of this process instead of the focused one.
+    #[arg(long, group = "window")]
+    pub pid: Option<i32>,
+
+    /// Move the window with this mark instead of the focused one.
+    #[arg(long, group = "window")]
+    pub mark: Option<String>,
+}
+
+impl MoveCli {
+    /// Returns a description of the selected window, `None` if the focused window is moved.
+    fn window(&self) -> Option<String> {
+        match (self.con_id, &self.app_id, self.pid, &self.mark) {
+            (Some(id), ..) => Some(format!("con_id {id}")),
+            (_, Some(app_id), ..) => Some(format!("app_id '{app_id}'")),
+            (.., Some(pid), _) => Some(format!("pid {pid}")),
+            (.., Some(mark)) => Some(format!("mark '{mark}'")),
+            _ => None,
+        }
+    }
+
+    /// Returns whether `node` is the selected window.
+    fn is_window(&self, node: &Node) -> bool {
+        self.con_id.is_none_or(|id| node.id == id)
+            && self
+                .app_id
+                .as_ref()
+                .is_none_or(|app_id| node.app_id.as_ref() == Some(app_id))
+            && self.pid.is_none_or(|pid| node.pid == Some(pid))
+            && self
+                .mark
+                .as_ref()
+                .is_none_or(|mark| node.marks.contains(mark))
+    }
+}
+
+/// Moves a window to the workspace given by `cli`, opening the workspace on its output if it does
+/// not exist yet.
+pub fn move_to_workspace(cli: &MoveCli) -> Fallible<()> {
+    let (mut sway, workspace, output, _) = initialize_workspace(&cli.workspace)?;
+
+    // Move the selected or the currently focused window to the provided workspace.
+    let criteria = match cli.window() {
+        Some(description) => find_window(cli, &description, &mut sway)?,
+        None => String::new(),
+    };
+    if cli.silent {
+        return placement::move_window_silently(
+            &mut sway,
+            &criteria,
+            &workspace,
+            output.as_deref(),
+        );
+    }
+    placement::move_window(
+        &mut sway,
+        &criteria,
+        &workspace,
+        output.as_deref(),
+        cli.follow,
+    )
+}
+
+/// Locates the window selected by `cli` (and described by `description`) in the tree and returns
+/// the criteria selecting it.
+fn find_window(cli: &MoveCli, description: &str, sway: &mut Connection) -> Fallible<String> {
+    let tree = sway.get_tree()?;
+    tree::windows(&tree)
+        .into_iter()
+        .find(|location| cli.is_window(location.node))
+        .map(|location| format!("[con_id={}] ", location.node.id))
+        .ok_or_else(|| Error::WindowNotFound(description.to_owned()))
+}
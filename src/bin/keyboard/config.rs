@@ -0,0 +1,72 @@
+//! The configuration file of the keyboard tool.
+//!
+//! By default, `$XDG_CONFIG_HOME/swaytools/keyboard.toml` is read if it exists. It may contain
+//! the formatting options, the include and exclude lists, and icons (see [`crate::icons`]):
+//!
+//! ```toml
+//! format-single = "{flag} {number}/{count}"
+//! tooltip-separator = "\n"
+//! include = ["*Keychron*"]
+//!
+//! [icons]
+//! "us(intl)" = "🌐"
+//! ```
+//!
+//! Options given on the command line override the values of the file.
+
+use crate::output::{OutputFormat, ShortCase};
+use serde::Deserialize;
+use std::{collections::HashMap, fs, io, path::PathBuf};
+use swaytools::{env, error::Fallible};
+
+/// The name of the default configuration file in the swaytools configuration directory.
+const FILE_NAME: &str = "keyboard.toml";
+
+/// The contents of the configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub include: Vec<String>,
+    pub include_file: Option<String>,
+    pub exclude: Vec<String>,
+    pub exclude_file: Option<String>,
+    pub format: Option<String>,
+    pub format_single: Option<String>,
+    pub format_separator: Option<String>,
+    pub format_empty: Option<String>,
+    pub tooltip: Option<String>,
+    pub tooltip_single: Option<String>,
+    pub tooltip_separator: Option<String>,
+    pub tooltip_empty: Option<String>,
+    pub flag_placeholder: Option<String>,
+    pub hide_default: Option<String>,
+    pub lock_interval: Option<u64>,
+    pub debounce: Option<u64>,
+    pub group_identical: bool,
+    pub notify: bool,
+    pub short_case: Option<ShortCase>,
+    pub short_variant: bool,
+    pub output_format: Option<OutputFormat>,
+    /// Additional top-level fields of the waybar output by name, overridden by `--field`.
+    pub fields: HashMap<String, String>,
+    /// Icons by layout, overridden by the entries of the `--icons` file.
+    pub icons: HashMap<String, String>,
+}
+
+impl Config {
+    /// Loads the configuration from `path` or, if not given, from the default file.
+    ///
+    /// A missing default file yields the empty configuration, whereas a missing explicit file is
+    /// an error.
+    pub fn load(path: Option<&str>) -> Fallible<Config> {
+        let (path, explicit) = match path {
+            Some(path) => (PathBuf::from(path), true),
+            None => (env::config_file(FILE_NAME), false),
+        };
+        match fs::read_to_string(&path) {
+            Ok(content) => Ok(toml::from_str(&content)?),
+            Err(err) if !explicit && err.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! The selection of keyboards by the include and exclude lists.
+//!
+//! Entries are glob patterns (an identifier without wildcards matches only itself) or regular
+//! expressions enclosed in slashes like `/^1:1:AT_/`. They are matched against both the
+//! identifier and the human-readable name of a keyboard.
+//!
+//! The patterns are kept (and not resolved to the keyboards present at startup) and checked for
+//! every input event, so keyboards plugged in later are picked up by their `added` event.
+
+use swayipc::Input;
+use swaytools::{error::Fallible, pattern::Pattern};
+
+/// Selects keyboards which match (or do not match) any of a list of patterns.
+pub struct KeyboardFilter {
+    patterns: Vec<Pattern>,
+    include: bool,
+}
+
+impl KeyboardFilter {
+    /// Creates a filter selecting the keyboards matching any of the `entries` if `include` is
+    /// set, or the keyboards matching none of them otherwise.
+    pub fn new(entries: &[String], include: bool) -> Fallible<KeyboardFilter> {
+        Ok(KeyboardFilter {
+            patterns: entries
+                .iter()
+                .map(|entry| Pattern::parse(entry))
+                .collect::<Fallible<_>>()?,
+            include,
+        })
+    }
+
+    /// Returns whether `input` is a selected keyboard.
+    pub fn matches(&self, input: &Input) -> bool {
+        input.input_type == "keyboard"
+            && self
+                .patterns
+                .iter()
+                .any(|pattern| pattern.matches(&input.identifier) || pattern.matches(&input.name))
+                == self.include
+    }
+}
@@ -0,0 +1,74 @@
+//! User-defined icons for layouts which replace the automatically generated flag.
+//!
+//! The icon file is a TOML (`*.toml`) or JSON table mapping layouts to arbitrary strings:
+//!
+//! ```toml
+//! "us(intl)" = "🌐"
+//! "de" = "DE"
+//! "fr*" = "🥖"
+//! ```
+//!
+//! Layouts are written as `name` or `name(variant)` and may contain glob patterns. Exact entries
+//! win over patterns, and longer patterns win over shorter ones. The same table may also be given
+//! as `[icons]` in the configuration file.
+
+use glob::Pattern;
+use std::{collections::HashMap, fs, path::Path};
+use swaytools::error::{Error, Fallible};
+
+/// The icons for layouts.
+#[derive(Debug, Default)]
+pub struct Icons {
+    exact: HashMap<String, String>,
+    /// Patterns ordered from the most to the least specific.
+    patterns: Vec<(Pattern, String)>,
+}
+
+impl Icons {
+    /// Reads the table of icons from the TOML or JSON file at `path`.
+    pub fn read(path: &str) -> Fallible<HashMap<String, String>> {
+        let content = fs::read_to_string(path)?;
+        Ok(
+            if Path::new(path).extension().is_some_and(|ext| ext == "toml") {
+                toml::from_str(&content)?
+            } else {
+                serde_json::from_str(&content)?
+            },
+        )
+    }
+
+    /// Creates the icons from a table mapping layouts (or patterns) to icons.
+    pub fn new(entries: HashMap<String, String>) -> Fallible<Icons> {
+        let mut icons = Icons::default();
+        for (layout, icon) in entries {
+            if layout.contains(['*', '?', '[']) {
+                let pattern = Pattern::new(&layout)
+                    .map_err(|err| Error::Invalid("pattern", layout.to_owned(), err.to_string()))?;
+                icons.patterns.push((pattern, icon));
+            } else {
+                icons.exact.insert(layout, icon);
+            }
+        }
+        icons
+            .patterns
+            .sort_by_key(|(pattern, _)| std::cmp::Reverse(pattern.as_str().len()));
+        Ok(icons)
+    }
+
+    /// Returns the icon for the layout `name` with the optional `variant`.
+    pub fn get(&self, name: &str, variant: Option<&str>) -> Option<&str> {
+        let layout = match variant {
+            Some(variant) if !variant.is_empty() => format!("{name}({variant})"),
+            _ => name.to_owned(),
+        };
+        self.exact
+            .get(&layout)
+            .or_else(|| {
+                self.patterns
+                    .iter()
+                    .find(|(pattern, _)| pattern.matches(&layout))
+                    .map(|(_, icon)| icon)
+            })
+            .map(String::as_str)
+    }
+}
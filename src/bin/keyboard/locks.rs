@@ -0,0 +1,66 @@
+//! The Caps Lock and Num Lock state of keyboards as shown by their LEDs in sysfs.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use swayipc::Input;
+
+/// The directory containing all input devices.
+const SYSFS_INPUT: &str = "/sys/class/input";
+
+/// The lock state of a keyboard.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Locks {
+    pub caps: bool,
+    pub num: bool,
+}
+
+impl Locks {
+    /// Reads the lock state from the LEDs of the sysfs `device`.
+    pub fn read(device: &Path) -> Locks {
+        Locks {
+            caps: led_is_on(device, "capslock"),
+            num: led_is_on(device, "numlock"),
+        }
+    }
+}
+
+/// Returns the sysfs directory (like `/sys/class/input/input3`) of the given keyboard.
+///
+/// Devices are matched by name and, if sway reports them, vendor and product id.
+pub fn find_device(input: &Input) -> Option<PathBuf> {
+    let vendor = input.vendor.map(|vendor| format!("{vendor:04x}"));
+    let product = input.product.map(|product| format!("{product:04x}"));
+    fs::read_dir(SYSFS_INPUT)
+        .ok()?
+        .map_while(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("input"))
+        })
+        .find(|path| {
+            read_trimmed(&path.join("name")).as_ref() == Some(&input.name)
+                && (vendor.is_none() || read_trimmed(&path.join("id/vendor")) == vendor)
+                && (product.is_none() || read_trimmed(&path.join("id/product")) == product)
+        })
+}
+
+/// Returns whether the LED `name` (like `capslock`) of the sysfs `device` is on.
+fn led_is_on(device: &Path, name: &str) -> bool {
+    let suffix = format!("::{name}");
+    let Ok(entries) = fs::read_dir(device) else {
+        return false;
+    };
+    entries.map_while(Result::ok).any(|entry| {
+        entry.file_name().to_string_lossy().ends_with(&suffix)
+            && read_trimmed(&entry.path().join("brightness")).is_some_and(|value| value != "0")
+    })
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|content| content.trim().to_owned())
+}
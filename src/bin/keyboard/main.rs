@@ -0,0 +1,1076 @@
+mod config;
+mod filter;
+mod icons;
+mod locks;
+mod notification;
+mod output;
+mod registry;
+mod rules;
+mod settings;
+#[cfg(feature = "x11")]
+mod x11;
+
+use clap::{
+    parser::ValueSource, ArgGroup, ArgMatches, CommandFactory, FromArgMatches, Parser, ValueHint,
+};
+use config::Config;
+use filter::KeyboardFilter;
+use icons::Icons;
+use itertools::Itertools;
+use locks::Locks;
+use notification::Notifier;
+use notify::RecommendedWatcher;
+use output::{OutputFormat, Printer, ShortCase};
+use registry::{Layout, Registry};
+use rules::Rules;
+use serde_json::Value;
+use settings::{XkbConfig, XkbSettings};
+use signal_hook::{
+    consts::{SIGUSR1, SIGUSR2},
+    iterator::Signals,
+};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::PathBuf,
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::{Duration, Instant},
+};
+use swayipc::{Event, EventType, Input, InputChange, InputEvent, WindowChange};
+use swaytools::{
+    connection, env,
+    error::{self, ErrorFormat, Fallible},
+    events::{EventStream, StreamEvent},
+    tracker::StateTracker,
+    Connection,
+};
+
+/// sway keyboard information reporting for status bars.
+///
+/// This tool prints the keyboard situation whenever it changes. Sending `SIGUSR1` queries the
+/// keyboards and prints them again, `SIGUSR2` switches the matched keyboards to their next layout.
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+#[clap(group(ArgGroup::new("in").args(["include", "include_file"]).multiple(true).conflicts_with("ex")))]
+#[clap(group(ArgGroup::new("ex").args(["exclude", "exclude_file"]).multiple(true)))]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Commands>,
+
+    /// The TOML configuration file whose values are used for all options not given on the command
+    /// line [default: $XDG_CONFIG_HOME/swaytools/keyboard.toml]
+    #[arg(short, long, value_hint = ValueHint::FilePath)]
+    config: Option<String>,
+
+    /// Keyboard identifier (e.g., '1:1:AT_Translated_Set_2_keyboard') or name to be included;
+    /// may be a glob or a regex enclosed in slashes
+    #[arg(short, long)]
+    include: Vec<String>,
+
+    /// A config file containing the keyboard identifiers to be included
+    #[arg(short = 'n', long, value_hint = ValueHint::FilePath)]
+    include_file: Option<String>,
+
+    /// Keyboard identifier (e.g., '1:1:AT_Translated_Set_2_keyboard') or name to be excluded;
+    /// may be a glob or a regex enclosed in slashes; all keyboards are shown if no keyboards are
+    /// included or excluded
+    #[arg(short, long)]
+    exclude: Vec<String>,
+
+    /// A config file containing the keyboard identifiers to be excluded
+    #[arg(short = 'x', long, value_hint = ValueHint::FilePath)]
+    exclude_file: Option<String>,
+
+    /// The output string formatting
+    #[arg(short, long, default_value = "{result}")]
+    format: String,
+
+    /// The output string formatting for a single keyboard
+    #[arg(short = 's', long, default_value = "{flag}")]
+    format_single: String,
+
+    /// The output string separator for multiple keyboards
+    #[arg(short = 'p', long, default_value = "")]
+    format_separator: String,
+
+    /// The output string if no keyboard matches (by default `format` with an empty result)
+    #[arg(long)]
+    format_empty: Option<String>,
+
+    /// The tooltip string formatting for a single keyboard
+    #[arg(short, long, default_value = "<b>Keyboards</b>\n{result}")]
+    tooltip: String,
+
+    /// The tooltip string formatting for a single keyboard
+    #[arg(short = 'o', long, default_value = "{keyboard}: {description}")]
+    tooltip_single: String,
+
+    /// The tooltip string separator for multiple keyboards
+    #[arg(short = 'r', long, default_value = "\n")]
+    tooltip_separator: String,
+
+    /// The tooltip string if no keyboard matches (by default `tooltip` with an empty result)
+    #[arg(long)]
+    tooltip_empty: Option<String>,
+
+    /// The protocol of the status bar the output is meant for
+    #[arg(long, value_enum, default_value_t)]
+    output_format: OutputFormat,
+
+    /// Also write the output in FORMAT to FILE on every change, e.g., `plain:/tmp/keyboard`
+    #[arg(long = "write", value_name = "FORMAT:FILE", value_parser = output::parse_sink)]
+    sinks: Vec<(OutputFormat, PathBuf)>,
+
+    /// Run a shell command on every change with the output in `$SWAYTOOLS_KEYBOARD_TEXT` (plain)
+    /// and `$SWAYTOOLS_KEYBOARD_JSON` (waybar)
+    #[arg(long, value_name = "COMMAND")]
+    exec: Option<String>,
+
+    /// Show the blocks of another status generator speaking the swaybar protocol on stdin after
+    /// the keyboard block, with the swaybar output format,
+    /// e.g., `i3status | keyboard --output-format swaybar --merge`
+    #[arg(long)]
+    merge: bool,
+
+    /// A TOML or JSON file mapping layouts (`name` or `name(variant)`, may contain globs) to the
+    /// icons used as `flag` instead of the regional indicator symbols
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    icons: Option<String>,
+
+    /// An additional top-level field of the waybar output as NAME=TEMPLATE, overriding the
+    /// defaults `class={names}`, `alt={name}`, and `percentage={percentage}`; an empty TEMPLATE
+    /// removes the field
+    #[arg(long = "field", value_name = "NAME=TEMPLATE")]
+    fields: Vec<String>,
+
+    /// The case of the `short` layout code
+    #[arg(long, value_enum, default_value_t)]
+    short_case: ShortCase,
+
+    /// Append the layout variant to the `short` layout code, e.g., `RU (phonetic)`
+    #[arg(long)]
+    short_variant: bool,
+
+    /// Print an empty text if all matched keyboards are on LAYOUT (xkb name, `name(variant)`, or
+    /// description), so that the module only shows up for other layouts
+    #[arg(long, value_name = "LAYOUT")]
+    hide_default: Option<String>,
+
+    /// The `flag` of layouts which belong to no (known) country
+    #[arg(long, value_name = "TEXT", default_value = "")]
+    flag_placeholder: String,
+
+    /// Show keyboards with the same active layout only once in the output (the tooltip still
+    /// lists all of them); the number of keyboards is available as `keyboards` since `count` is
+    /// the number of layouts
+    #[arg(long)]
+    group_identical: bool,
+
+    /// Show a desktop notification whenever the layout of a matched keyboard changes
+    #[arg(long)]
+    notify: bool,
+
+    /// Wait MS milliseconds for further changes before printing, so that bursts of changes (like
+    /// plugging in a USB hub) are printed only once
+    #[arg(long, value_name = "MS", default_value_t = 50)]
+    debounce: u64,
+
+    /// Print the keyboard situation once and exit instead of waiting for changes
+    #[arg(short = '1', long)]
+    once: bool,
+
+    /// Check the Caps Lock and Num Lock state (available as `caps` and `num`) every MS
+    /// milliseconds instead of only when a layout changes
+    #[arg(short = 'l', long, value_name = "MS")]
+    lock_interval: Option<u64>,
+
+    /// Only show the layout switching commands instead of executing them
+    /// [env: SWAYTOOLS_DRY_RUN]
+    #[arg(long)]
+    dry_run: bool,
+
+    /// How errors are reported [env: SWAYTOOLS_ERRORS]
+    #[arg(long, value_enum)]
+    errors: Option<ErrorFormat>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Commands {
+    /// List the available (matched) keyboards with their identifiers, names, and layouts
+    List {
+        /// Print a JSON array instead of a human-readable list
+        #[arg(short, long)]
+        json: bool,
+    },
+    /// Print the complete state of the matched keyboards (layouts, active index, lock state, and
+    /// icons) as JSON
+    Status,
+    /// Switch the matched keyboards to their next layout
+    NextLayout,
+    /// Switch the matched keyboards to their previous layout
+    PrevLayout,
+    /// Switch the matched keyboards to the given layout; keyboards without it are left alone
+    #[command(group(ArgGroup::new("target").args(["layout", "index"]).required(true)))]
+    SetLayout {
+        /// The layout as xkb name (e.g., 'de'), `name(variant)`, or description
+        layout: Option<String>,
+        /// The (0-based) index of the layout among the configured layouts of each keyboard
+        #[arg(short, long)]
+        index: Option<usize>,
+    },
+    /// Read click events of waybar or swaybar from stdin and switch the layout of the matched
+    /// keyboards: left click or scrolling down selects the next, right click or scrolling up the
+    /// previous layout
+    Click,
+    /// Remember the layout of the matched keyboards for every window and restore it whenever the
+    /// window is focused again
+    PerWindow {
+        /// The layout index to switch to when a window is focused for the first time (by default
+        /// the layout is kept)
+        #[arg(short, long)]
+        default: Option<i32>,
+        /// A TOML file with rules selecting the layout by the app id or class of the focused
+        /// window
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        rules: Option<String>,
+        /// Whether the matching rule or the remembered layout of a window wins
+        #[arg(long, value_enum, default_value_t)]
+        prefer: Precedence,
+    },
+}
+
+/// Whether rules or the remembered layout of a window win if both exist.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Precedence {
+    /// The remembered layout, rules only select the layout of newly focused windows
+    #[default]
+    Memory,
+    /// The rules, the remembered layout is only restored for windows without a rule
+    Rules,
+}
+
+fn main() {
+    let matches = Cli::command().get_matches();
+    let mut cli = match Cli::from_arg_matches(&matches) {
+        Ok(cli) => cli,
+        Err(err) => err.exit(),
+    };
+    let errors = ErrorFormat::resolve(cli.errors);
+    let icons = match Config::load(cli.config.as_deref())
+        .and_then(|config| apply_config(&mut cli, &matches, config))
+    {
+        Ok(icons) => icons,
+        Err(err) => errors.exit(&err),
+    };
+    // Get a list of all interface identifiers that should be matched and whether the match should be inclusive or exclusive
+    let filter = match get_include_exclude(&cli) {
+        Ok(filter) => filter,
+        Err(err) => errors.exit(&err),
+    };
+
+    let command = cli.command.take();
+    let mut printer = match Printer::new(&cli, icons) {
+        Ok(printer) => printer,
+        Err(err) => errors.exit(&err),
+    };
+
+    // Without sway, show the layout of the X server instead
+    #[cfg(feature = "x11")]
+    if command.is_none() && x11::is_preferred() {
+        if let Err(err) = monitor_x11(&cli, &mut printer) {
+            errors.exit(&err);
+        }
+        return;
+    }
+
+    let dry_run = env::dry_run(cli.dry_run);
+    let mut sway = match Connection::new(dry_run) {
+        Ok(sway) => sway,
+        Err(err) => errors.exit(&err),
+    };
+
+    // Actions only switch layouts and exit
+    if let Some(command) = command {
+        let result = match command {
+            Commands::List { json } => list_keyboards(&filter, json, &mut sway),
+            Commands::Status => print_status(&filter, &printer, &mut sway),
+            Commands::NextLayout => switch_layout(&filter, "next", &mut sway),
+            Commands::PrevLayout => switch_layout(&filter, "prev", &mut sway),
+            Commands::SetLayout { layout, index } => set_layout(
+                &filter,
+                &Registry::load(),
+                layout.as_deref(),
+                index,
+                &mut sway,
+            ),
+            Commands::Click => handle_clicks(&filter, &mut sway),
+            Commands::PerWindow {
+                default,
+                rules,
+                prefer,
+            } => rules
+                .as_deref()
+                .map(Rules::load)
+                .transpose()
+                .and_then(|rules| {
+                    remember_per_window(&filter, default, rules.as_ref(), prefer, &mut sway)
+                }),
+        };
+        if let Err(err) = result {
+            errors.exit(&err);
+        }
+        return;
+    }
+
+    // Load all layouts for all keyboards present and matching
+    let mut registry = Registry::load();
+    let mut layouts = initialize_layouts(&filter, &registry, &mut sway);
+
+    // Before entering the event loop, print out the keyboard situation
+    printer.print(&layouts);
+    if cli.once {
+        return;
+    }
+
+    // Subscribe to all input events
+    let events = match EventStream::subscribe(&[EventType::Input]) {
+        Ok(events) => events,
+        Err(err) => errors.exit(&err.into()),
+    };
+
+    let (updates, _watcher) = spawn_updates(
+        events,
+        cli.lock_interval.map(Duration::from_millis),
+        cli.merge,
+    );
+    let debounce = Duration::from_millis(cli.debounce);
+    let mut notifier = cli.notify.then(Notifier::default);
+    while let Ok(update) = updates.recv() {
+        // Coalesce bursts of updates (like plugging in a hub) to print at most once per interval
+        let mut batch = vec![update];
+        let deadline = Instant::now() + debounce;
+        while let Ok(update) =
+            updates.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        {
+            batch.push(update);
+        }
+        let before: HashMap<String, String> = layouts
+            .iter()
+            .map(|(identifier, keyboard)| {
+                (
+                    identifier.to_owned(),
+                    keyboard.layout.description.to_owned(),
+                )
+            })
+            .collect();
+        if apply_updates(
+            batch,
+            &filter,
+            &mut registry,
+            &mut layouts,
+            &mut printer,
+            dry_run,
+        ) {
+            printer.print(&layouts);
+        }
+        if let Some(notifier) = &mut notifier {
+            for (identifier, keyboard) in &layouts {
+                if before
+                    .get(identifier)
+                    .is_some_and(|description| *description != keyboard.layout.description)
+                {
+                    notifier.notify(keyboard, &printer.flag(&keyboard.layout));
+                }
+            }
+        }
+    }
+}
+
+/// Applies a `batch` of updates to the keyboard situation and returns whether it has to be
+/// printed again.
+///
+/// Only the last input event of every keyboard is applied.
+fn apply_updates(
+    batch: Vec<Update>,
+    filter: &KeyboardFilter,
+    registry: &mut Registry,
+    layouts: &mut HashMap<String, Keyboard>,
+    printer: &mut Printer,
+    dry_run: bool,
+) -> bool {
+    let mut changed = false;
+    let mut inputs: HashMap<String, InputEvent> = HashMap::new();
+    for update in batch {
+        let event = match update {
+            Update::Event(event) => event,
+            // Layouts may have been added or renamed
+            Update::RegistryChanged | Update::Refresh => {
+                if let Update::RegistryChanged = update {
+                    *registry = Registry::load();
+                }
+                if let Ok(mut sway) = Connection::new(dry_run) {
+                    *layouts = initialize_layouts(filter, registry, &mut sway);
+                    inputs.clear();
+                }
+                changed = true;
+                continue;
+            }
+            Update::Blocks(blocks) => {
+                printer.set_blocks(blocks);
+                changed = true;
+                continue;
+            }
+            // The resulting input events update the keyboard situation
+            Update::Cycle => {
+                if let Err(err) = Connection::new(dry_run)
+                    .and_then(|mut sway| switch_layout(filter, "next", &mut sway))
+                {
+                    eprintln!("Cannot switch layouts: {err}");
+                }
+                continue;
+            }
+            // Only print the keyboard situation again if a lock state changed
+            Update::CheckLocks => {
+                for keyboard in layouts.values_mut() {
+                    changed |= keyboard.update_locks();
+                }
+                continue;
+            }
+        };
+        match event {
+            // Only look at input events (other events should never appear here, anyway)
+            StreamEvent::Event(event) => {
+                if let Event::Input(ev) = *event {
+                    // Ignore events that are not keyboard events or don't match our criteria
+                    if filter.matches(&ev.input) {
+                        inputs.insert(ev.input.identifier.to_owned(), *ev);
+                    }
+                }
+            }
+            // Sway may have been restarted, so the keyboards have to be queried again
+            StreamEvent::Reconnected => {
+                if let Ok(mut sway) = Connection::new(dry_run) {
+                    *layouts = initialize_layouts(filter, registry, &mut sway);
+                    inputs.clear();
+                }
+                changed = true;
+            }
+        }
+    }
+
+    // The settings may have changed with a reload of the config
+    let xkb = if inputs.is_empty() {
+        XkbConfig::default()
+    } else {
+        connection::connect()
+            .map(|mut sway| XkbConfig::load(&mut sway))
+            .unwrap_or_default()
+    };
+    for (identifier, ev) in inputs {
+        match ev.change {
+            // If a keyboard was removed, remove the corresponding entry from our mapping
+            swayipc::InputChange::Removed => {
+                layouts.remove(&identifier);
+            }
+            // If a keyboard was added or a layout changed, store the (new) layout in our mapping
+            swayipc::InputChange::Added
+            | swayipc::InputChange::XkbKeymap
+            | swayipc::InputChange::XkbLayout => {
+                if let Some(keyboard) = Keyboard::new(&ev.input, registry, &xkb) {
+                    layouts.insert(identifier, keyboard);
+                }
+            }
+            // Ignore all other events
+            _ => continue,
+        };
+        changed = true;
+    }
+    changed
+}
+
+/// Prints the layout of the core keyboard of the X server whenever it changes.
+#[cfg(feature = "x11")]
+fn monitor_x11(cli: &Cli, printer: &mut Printer) -> Fallible<()> {
+    let registry = Registry::load();
+    let keyboard = x11::CoreKeyboard::connect()?;
+    loop {
+        let (layout_names, active_index) = keyboard.layouts()?;
+        let mut layouts = HashMap::new();
+        if let Some(layout) = layout_names
+            .get(active_index as usize)
+            .map(|description| registry.resolve(description))
+        {
+            layouts.insert(
+                x11::KEYBOARD_NAME.to_owned(),
+                Keyboard::with_device(x11::KEYBOARD_NAME, layout, layout_names, active_index, None),
+            );
+        }
+        printer.print(&layouts);
+        if cli.once {
+            return Ok(());
+        }
+        keyboard.wait_for_change()?;
+    }
+}
+
+/// A reason to update the keyboard situation.
+enum Update {
+    Event(StreamEvent),
+    /// The lock state should be checked.
+    CheckLocks,
+    /// The files of the xkb registry changed.
+    RegistryChanged,
+    /// The keyboards should be queried and printed again (`SIGUSR1`).
+    Refresh,
+    /// The matched keyboards should switch to their next layout (`SIGUSR2`).
+    Cycle,
+    /// Another status generator printed new blocks.
+    Blocks(Vec<Value>),
+}
+
+/// Forwards all `events`, changes of the xkb registry, `SIGUSR1` and `SIGUSR2`, the blocks read
+/// from stdin if `merge` is set, and, if `lock_interval` is given, a request to check the lock
+/// state every `lock_interval` to the returned receiver.
+///
+/// Registry changes are only forwarded as long as the returned watcher is alive.
+fn spawn_updates(
+    events: EventStream,
+    lock_interval: Option<Duration>,
+    merge: bool,
+) -> (Receiver<Update>, Option<RecommendedWatcher>) {
+    let (sender, receiver) = mpsc::channel();
+    let watcher = {
+        let sender = sender.clone();
+        registry::watch(move || {
+            sender.send(Update::RegistryChanged).ok();
+        })
+        .ok()
+    };
+    if let Ok(mut signals) = Signals::new([SIGUSR1, SIGUSR2]) {
+        let sender = sender.clone();
+        thread::spawn(move || {
+            for signal in signals.forever() {
+                let update = if signal == SIGUSR1 {
+                    Update::Refresh
+                } else {
+                    Update::Cycle
+                };
+                if sender.send(update).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    if merge {
+        let sender = sender.clone();
+        thread::spawn(move || {
+            for line in io::stdin().lines().map_while(Result::ok) {
+                if let Some(blocks) = output::parse_blocks(&line) {
+                    if sender.send(Update::Blocks(blocks)).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+    }
+    if let Some(lock_interval) = lock_interval {
+        let sender = sender.clone();
+        thread::spawn(move || loop {
+            thread::sleep(lock_interval);
+            if sender.send(Update::CheckLocks).is_err() {
+                break;
+            }
+        });
+    }
+    thread::spawn(move || {
+        for event in events {
+            if sender.send(Update::Event(event)).is_err() {
+                break;
+            }
+        }
+    });
+    (receiver, watcher)
+}
+
+/// Uses the values of `config` for all options which are not given on the command line and
+/// returns the icons of the configuration and the `--icons` file.
+fn apply_config(cli: &mut Cli, matches: &ArgMatches, config: Config) -> Fallible<Icons> {
+    let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+    // The lists are only taken from the file if none are given, mixing them makes no sense
+    if !["include", "include_file", "exclude", "exclude_file"]
+        .into_iter()
+        .any(from_cli)
+    {
+        cli.include = config.include;
+        cli.include_file = config.include_file;
+        cli.exclude = config.exclude;
+        cli.exclude_file = config.exclude_file;
+    }
+    for (id, field, value) in [
+        ("format", &mut cli.format, config.format),
+        (
+            "format_single",
+            &mut cli.format_single,
+            config.format_single,
+        ),
+        (
+            "format_separator",
+            &mut cli.format_separator,
+            config.format_separator,
+        ),
+        ("tooltip", &mut cli.tooltip, config.tooltip),
+        (
+            "tooltip_single",
+            &mut cli.tooltip_single,
+            config.tooltip_single,
+        ),
+        (
+            "tooltip_separator",
+            &mut cli.tooltip_separator,
+            config.tooltip_separator,
+        ),
+    ] {
+        if let Some(value) = value.filter(|_| !from_cli(id)) {
+            *field = value;
+        }
+    }
+    cli.lock_interval = cli.lock_interval.or(config.lock_interval);
+    cli.format_empty = cli.format_empty.take().or(config.format_empty);
+    cli.hide_default = cli.hide_default.take().or(config.hide_default);
+    cli.tooltip_empty = cli.tooltip_empty.take().or(config.tooltip_empty);
+    cli.group_identical |= config.group_identical;
+    cli.notify |= config.notify;
+    cli.short_variant |= config.short_variant;
+    if let Some(short_case) = config.short_case.filter(|_| !from_cli("short_case")) {
+        cli.short_case = short_case;
+    }
+    cli.fields = config
+        .fields
+        .into_iter()
+        .map(|(name, template)| format!("{name}={template}"))
+        .chain(std::mem::take(&mut cli.fields))
+        .collect();
+    if let Some(debounce) = config.debounce.filter(|_| !from_cli("debounce")) {
+        cli.debounce = debounce;
+    }
+    if let Some(output_format) = config.output_format.filter(|_| !from_cli("output_format")) {
+        cli.output_format = output_format;
+    }
+
+    let mut icons = config.icons;
+    if let Some(file) = &cli.icons {
+        icons.extend(Icons::read(file)?);
+    }
+    Icons::new(icons)
+}
+
+/// Return the filter selecting the keyboards to be included or not excluded.
+fn get_include_exclude(cli: &Cli) -> Fallible<KeyboardFilter> {
+    let include = !cli.include.is_empty() || cli.include_file.is_some();
+    let list = if include {
+        build_clude_list(&cli.include, &cli.include_file)
+    } else {
+        build_clude_list(&cli.exclude, &cli.exclude_file)
+    };
+
+    KeyboardFilter::new(&list, include)
+}
+
+fn build_clude_list(list: &Vec<String>, opt_file_name: &Option<String>) -> Vec<String> {
+    let mut result = list.to_owned();
+    if let Some(file_name) = opt_file_name {
+        if let Ok(file) = File::open(file_name) {
+            for line in BufReader::new(file).lines().map_while(Result::ok) {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                result.push(line);
+            }
+        }
+    }
+
+    result
+}
+
+/// Prints all matched keyboards with their identifiers, names, and configured layouts.
+fn list_keyboards(filter: &KeyboardFilter, json: bool, sway: &mut Connection) -> Fallible<()> {
+    let keyboards: Vec<Input> = sway
+        .get_inputs()?
+        .into_iter()
+        .filter(|input| filter.matches(input))
+        .collect();
+
+    if json {
+        let keyboards: Vec<Value> = keyboards
+            .iter()
+            .map(|input| {
+                serde_json::json!({
+                    "identifier": input.identifier,
+                    "name": input.name,
+                    "layouts": input.xkb_layout_names,
+                    "active_index": input.xkb_active_layout_index,
+                    "vendor": input.vendor.map(hex_id),
+                    "product": input.product.map(hex_id),
+                    "send_events": send_events(input),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&keyboards)?);
+        return Ok(());
+    }
+
+    for input in keyboards {
+        let layouts = input
+            .xkb_layout_names
+            .iter()
+            .enumerate()
+            .map(|(index, layout)| {
+                if input.xkb_active_layout_index == Some(index as i32) {
+                    format!("{layout} (active)")
+                } else {
+                    layout.to_owned()
+                }
+            })
+            .join(", ");
+        println!("{}", input.identifier);
+        println!("    name:    {}", input.name);
+        println!("    layouts: {layouts}");
+        if let (Some(vendor), Some(product)) = (input.vendor, input.product) {
+            println!("    vendor:  {}", hex_id(vendor));
+            println!("    product: {}", hex_id(product));
+        }
+        if let Some(send_events) = send_events(&input) {
+            println!("    events:  {send_events}");
+        }
+    }
+    Ok(())
+}
+
+/// Prints the state of all matched keyboards as a JSON array.
+fn print_status(filter: &KeyboardFilter, printer: &Printer, sway: &mut Connection) -> Fallible<()> {
+    let registry = Registry::load();
+    let keyboards = initialize_layouts(filter, &registry, sway);
+    let status: Vec<Value> = keyboards
+        .iter()
+        .sorted_by_key(|(identifier, _)| *identifier)
+        .map(|(identifier, keyboard)| {
+            let layouts: Vec<Value> = keyboard
+                .layout_names
+                .iter()
+                .map(|description| {
+                    let layout = registry.resolve(description);
+                    serde_json::json!({
+                        "description": layout.description,
+                        "name": layout.name,
+                        "variant": layout.variant,
+                        "flag": printer.flag(&layout),
+                    })
+                })
+                .collect();
+            serde_json::json!({
+                "identifier": identifier,
+                "name": keyboard.name,
+                "layouts": layouts,
+                "active_index": keyboard.active_index,
+                "caps": keyboard.locks.caps,
+                "num": keyboard.locks.num,
+                "vendor": keyboard.vendor.map(hex_id),
+                "product": keyboard.product.map(hex_id),
+                "send_events": keyboard.send_events,
+                "xkb_options": keyboard.xkb.options,
+                "xkb_model": keyboard.xkb.model,
+                "xkb_rules": keyboard.xkb.rules,
+            })
+        })
+        .collect();
+    println!("{}", serde_json::to_string_pretty(&status)?);
+    Ok(())
+}
+
+/// Formats a vendor or product id like `lsusb` does, e.g., `046d`.
+fn hex_id(id: i32) -> String {
+    format!("{id:04x}")
+}
+
+/// Returns the libinput send-events mode of `input`, e.g., `enabled` or `disabled`.
+fn send_events(input: &Input) -> Option<String> {
+    let mode = input.libinput.as_ref()?.send_events?;
+    match serde_json::to_value(mode) {
+        Ok(Value::String(mode)) => Some(mode),
+        _ => None,
+    }
+}
+
+/// Switches all matched keyboards to `layout`, see [`switch_keyboard_layout`].
+fn switch_layout(filter: &KeyboardFilter, layout: &str, sway: &mut Connection) -> Fallible<()> {
+    for input in sway.get_inputs()? {
+        if !filter.matches(&input) {
+            continue;
+        }
+        switch_keyboard_layout(&input.identifier, layout, sway)?;
+    }
+    Ok(())
+}
+
+/// Switches all matched keyboards to the given `layout` or the layout at `index`.
+///
+/// Fails if no matched keyboard has the layout.
+fn set_layout(
+    filter: &KeyboardFilter,
+    registry: &Registry,
+    layout: Option<&str>,
+    index: Option<usize>,
+    sway: &mut Connection,
+) -> Fallible<()> {
+    let mut found = false;
+    for input in sway.get_inputs()? {
+        if !filter.matches(&input) {
+            continue;
+        }
+        let position = match (layout, index) {
+            (_, Some(index)) => (index < input.xkb_layout_names.len()).then_some(index),
+            // A bare name also selects a variant of it unless the plain layout is configured
+            (Some(layout), None) => input
+                .xkb_layout_names
+                .iter()
+                .position(|description| is_layout(description, layout, registry))
+                .or_else(|| {
+                    input.xkb_layout_names.iter().position(|description| {
+                        registry
+                            .get(description)
+                            .is_some_and(|known| known.name == layout)
+                    })
+                }),
+            (None, None) => None,
+        };
+        if let Some(position) = position {
+            switch_keyboard_layout(&input.identifier, &position.to_string(), sway)?;
+            found = true;
+        }
+    }
+    if found {
+        Ok(())
+    } else {
+        let layout =
+            layout.map_or_else(|| format!("#{}", index.unwrap_or_default()), str::to_owned);
+        Err(error::Error::NotFound(
+            "matched keyboard",
+            format!("has the layout '{layout}'"),
+        ))
+    }
+}
+
+/// Returns whether the layout with the given `description` is `layout`, see [`Layout::is`].
+fn is_layout(description: &str, layout: &str, registry: &Registry) -> bool {
+    description.eq_ignore_ascii_case(layout)
+        || registry
+            .get(description)
+            .is_some_and(|known| known.is(layout))
+}
+
+/// Switches the keyboard with the given `identifier` to `layout`, which is either a layout
+/// index, `next`, or `prev`.
+fn switch_keyboard_layout(identifier: &str, layout: &str, sway: &mut Connection) -> Fallible<()> {
+    sway.run(format!("input \"{identifier}\" xkb_switch_layout {layout}"))
+}
+
+/// Switches the layout of all matched keyboards for every click event read from stdin.
+///
+/// Click events are JSON objects with a `button` field, one per line, optionally wrapped in the
+/// infinite JSON array of the swaybar protocol.
+fn handle_clicks(filter: &KeyboardFilter, sway: &mut Connection) -> Fallible<()> {
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let line = line.trim().trim_start_matches([',', '[']);
+        let Ok(click) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        let direction = match click["button"].as_u64() {
+            Some(1 | 5) => "next",
+            Some(3 | 4) => "prev",
+            _ => continue,
+        };
+        switch_layout(filter, direction, sway)?;
+    }
+    Ok(())
+}
+
+/// Remembers the active layout of all matched keyboards per window and restores it whenever a
+/// window is focused.
+///
+/// Windows focused for the first time are switched to the `default` layout index if given. The
+/// layout of a matching rule wins over the remembered one if `prefer` says so, and over the
+/// `default` in any case.
+fn remember_per_window(
+    filter: &KeyboardFilter,
+    default: Option<i32>,
+    rules: Option<&Rules>,
+    prefer: Precedence,
+    sway: &mut Connection,
+) -> Fallible<()> {
+    let registry = rules.map(|_| Registry::load()).unwrap_or_default();
+    let mut tracker = StateTracker::new(&[EventType::Input])?;
+    // The active layout index of every matched keyboard per window (con_id)
+    let mut memory: HashMap<i64, HashMap<String, i32>> = HashMap::new();
+
+    while let Some(event) = tracker.next_event() {
+        let event = match event {
+            StreamEvent::Event(event) => *event,
+            // Window ids are not kept when sway is restarted
+            StreamEvent::Reconnected => {
+                memory.clear();
+                if let Ok(connection) = Connection::new(sway.dry_run()) {
+                    *sway = connection;
+                }
+                continue;
+            }
+        };
+        let result = match event {
+            Event::Window(ev) if ev.change == WindowChange::Focus => {
+                let rule = rules.and_then(|rules| rules.layout_for(&ev.container));
+                match (memory.get(&ev.container.id), rule, default) {
+                    (Some(_), Some(layout), _) if prefer == Precedence::Rules => {
+                        set_layout(filter, &registry, Some(layout), None, sway)
+                    }
+                    (Some(layouts), _, _) => layouts.iter().try_for_each(|(identifier, index)| {
+                        switch_keyboard_layout(identifier, &index.to_string(), sway)
+                    }),
+                    (None, Some(layout), _) => {
+                        set_layout(filter, &registry, Some(layout), None, sway)
+                    }
+                    (None, None, Some(default)) => {
+                        switch_layout(filter, &default.to_string(), sway)
+                    }
+                    (None, None, None) => Ok(()),
+                }
+            }
+            Event::Window(ev) if ev.change == WindowChange::Close => {
+                memory.remove(&ev.container.id);
+                Ok(())
+            }
+            // Remember layout changes (including our own) for the focused window
+            Event::Input(ev)
+                if matches!(ev.change, InputChange::XkbLayout | InputChange::XkbKeymap)
+                    && filter.matches(&ev.input) =>
+            {
+                if let (Some(window), Some(index)) = (
+                    tracker.focused_window_id(),
+                    ev.input.xkb_active_layout_index,
+                ) {
+                    memory
+                        .entry(window)
+                        .or_default()
+                        .insert(ev.input.identifier, index);
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+        if let Err(err) = result {
+            eprintln!("Cannot restore the keyboard layout: {err}");
+        }
+    }
+    Ok(())
+}
+
+/// A matched keyboard together with its active layout.
+#[derive(Debug)]
+struct Keyboard {
+    /// The human readable name of the keyboard.
+    name: String,
+    layout: Layout,
+    /// The descriptions of all configured layouts.
+    layout_names: Vec<String>,
+    /// The index of the active layout in `layout_names`.
+    active_index: i32,
+    /// The sysfs directory of the keyboard to read the lock state from.
+    device: Option<PathBuf>,
+    locks: Locks,
+    /// The xkb options, model, and rules of the sway config.
+    xkb: XkbSettings,
+    vendor: Option<i32>,
+    product: Option<i32>,
+    /// The libinput send-events mode.
+    send_events: Option<String>,
+}
+
+impl Keyboard {
+    /// Creates the keyboard of `input` whose active layout is identified by its index.
+    ///
+    /// The registry only adds details like the xkb name to the layout, so layouts sharing a
+    /// description or custom layouts unknown to the registry are still told apart. Returns `None`
+    /// if the input has no layouts.
+    fn new(input: &Input, registry: &Registry, xkb: &XkbConfig) -> Option<Keyboard> {
+        let index = input.xkb_active_layout_index.unwrap_or_default();
+        let description = usize::try_from(index)
+            .ok()
+            .and_then(|index| input.xkb_layout_names.get(index))
+            .or(input.xkb_active_layout_name.as_ref())?;
+        Some(Keyboard {
+            xkb: xkb.settings(input),
+            vendor: input.vendor,
+            product: input.product,
+            send_events: send_events(input),
+            ..Keyboard::with_device(
+                &input.name,
+                registry.resolve(description),
+                input.xkb_layout_names.to_owned(),
+                index,
+                locks::find_device(input),
+            )
+        })
+    }
+
+    /// Creates a keyboard whose lock state is read from the sysfs `device`.
+    fn with_device(
+        name: &str,
+        layout: Layout,
+        layout_names: Vec<String>,
+        active_index: i32,
+        device: Option<PathBuf>,
+    ) -> Keyboard {
+        Keyboard {
+            name: name.to_owned(),
+            layout,
+            layout_names,
+            active_index,
+            locks: device.as_deref().map(Locks::read).unwrap_or_default(),
+            device,
+            xkb: XkbSettings::default(),
+            vendor: None,
+            product: None,
+            send_events: None,
+        }
+    }
+
+    /// Reads the lock state again and returns whether it changed.
+    fn update_locks(&mut self) -> bool {
+        let locks = self.device.as_deref().map(Locks::read).unwrap_or_default();
+        let changed = locks != self.locks;
+        self.locks = locks;
+        changed
+    }
+}
+
+fn initialize_layouts(
+    filter: &KeyboardFilter,
+    registry: &Registry,
+    sway: &mut Connection,
+) -> HashMap<String, Keyboard> {
+    let mut layouts = HashMap::new();
+    let xkb = XkbConfig::load(sway);
+
+    for input in sway.get_inputs().unwrap_or_default() {
+        if !filter.matches(&input) {
+            continue;
+        }
+        if let Some(keyboard) = Keyboard::new(&input, registry, &xkb) {
+            layouts.insert(input.identifier.to_owned(), keyboard);
+        }
+    }
+
+    layouts
+}
@@ -0,0 +1,34 @@
+//! Desktop notifications about layout changes for setups without a visible bar.
+
+use crate::Keyboard;
+use notify_rust::Notification;
+
+/// How long a notification is shown in milliseconds.
+const TIMEOUT: i32 = 2000;
+
+/// Sends a notification whenever the active layout of a keyboard changes.
+///
+/// Each notification replaces the previous one instead of piling up.
+#[derive(Debug, Default)]
+pub struct Notifier {
+    id: Option<u32>,
+}
+
+impl Notifier {
+    /// Shows the new layout of `keyboard` with its `flag`.
+    pub fn notify(&mut self, keyboard: &Keyboard, flag: &str) {
+        let mut notification = Notification::new();
+        notification
+            .appname("swaytools")
+            .summary(format!("{flag} {}", keyboard.layout.description).trim())
+            .body(&keyboard.name)
+            .timeout(TIMEOUT);
+        if let Some(id) = self.id {
+            notification.id(id);
+        }
+        match notification.show() {
+            Ok(handle) => self.id = Some(handle.id()),
+            Err(err) => eprintln!("Cannot show notification: {err}"),
+        }
+    }
+}
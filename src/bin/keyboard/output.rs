@@ -0,0 +1,495 @@
+//! Printing the keyboard situation in the protocols of various status bars.
+//!
+//! Waybar gets a JSON object with text and tooltip per line, swaybar the text as a block of the
+//! swaybar protocol (see `swaybar-protocol(7)`), and all other bars only the text as a single
+//! line. Swaybar and i3blocks (with `markup=pango`) understand pango markup like waybar, so
+//! values are escaped for them. Polybar has its own formatting tags instead, so pango tags of the
+//! templates are removed and `%` in values is escaped. Plain text contains no markup at all.
+//!
+//! Besides stdout, the output can be written to files in other protocols and passed to a hook, so
+//! that a single instance serves several consumers.
+
+use crate::{icons::Icons, registry::Layout, Cli, Keyboard};
+use clap::ValueEnum;
+use itertools::Itertools;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::OnceLock,
+    thread,
+};
+use swaytools::error::Fallible;
+use tinytemplate::TinyTemplate;
+
+/// The additional top-level fields of the waybar output and their default templates.
+const DEFAULT_FIELDS: &[(&str, &str)] = &[
+    ("class", "{names}"),
+    ("alt", "{name}"),
+    ("percentage", "{percentage}"),
+];
+
+/// The status bar protocol.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// A JSON object with `text` and `tooltip` per line.
+    #[default]
+    Waybar,
+    /// The text without any markup per line.
+    Plain,
+    /// The swaybar protocol, for using the tool as `status_command`.
+    Swaybar,
+    /// The text per line, for blocks with `interval=persist`.
+    I3blocks,
+    /// The text per line, for modules of `type = custom/script` with `tail = true`.
+    Polybar,
+}
+
+impl OutputFormat {
+    /// Escapes a `value` inserted into the templates.
+    pub fn escape(self, value: &str) -> String {
+        match self {
+            OutputFormat::Waybar | OutputFormat::Plain => value.to_owned(),
+            OutputFormat::Swaybar | OutputFormat::I3blocks => value
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;"),
+            OutputFormat::Polybar => value.replace('%', "%%"),
+        }
+    }
+
+    /// Turns the rendered `text` into the line printed for all bars but waybar.
+    pub fn line(self, text: &str) -> String {
+        let text = match self {
+            OutputFormat::Plain | OutputFormat::Polybar => strip_markup(text),
+            OutputFormat::Waybar | OutputFormat::Swaybar | OutputFormat::I3blocks => {
+                text.to_owned()
+            }
+        };
+        text.replace('\n', " ")
+    }
+}
+
+/// The case of the `short` layout code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShortCase {
+    /// Upper case, e.g., `DE`.
+    #[default]
+    Upper,
+    /// Lower case, e.g., `de`.
+    Lower,
+    /// The case of the xkb registry.
+    Keep,
+}
+
+/// Renders the keyboard situation with the templates and separators of the command line.
+pub struct Printer<'a> {
+    templater: TinyTemplate<'a>,
+    icons: Icons,
+    format_separator: &'a str,
+    tooltip_separator: &'a str,
+    format_empty: Option<&'a str>,
+    tooltip_empty: Option<&'a str>,
+    hide_default: Option<&'a str>,
+    output: OutputFormat,
+    group_identical: bool,
+    /// The flag of layouts without a known country.
+    flag_placeholder: &'a str,
+    short_case: ShortCase,
+    short_variant: bool,
+    /// The names of the additional top-level fields of the waybar output.
+    fields: Vec<&'a str>,
+    /// Additional files the keyboard situation is written to in the given protocol.
+    files: &'a [(OutputFormat, PathBuf)],
+    /// A shell command run after every change.
+    exec: Option<&'a str>,
+    /// The blocks of another status generator shown after the keyboard (swaybar only).
+    blocks: Vec<Value>,
+    /// Whether the header of the swaybar protocol was printed already.
+    started: bool,
+}
+
+impl<'a> Printer<'a> {
+    /// Creates a printer for the templates of `cli`.
+    pub fn new(cli: &'a Cli, icons: Icons) -> Fallible<Printer<'a>> {
+        let mut templater = TinyTemplate::new();
+        templater.set_default_formatter(&tinytemplate::format_unescaped);
+        for (name, template) in [
+            ("format", &cli.format),
+            ("format_single", &cli.format_single),
+            ("tooltip", &cli.tooltip),
+            ("tooltip_single", &cli.tooltip_single),
+        ] {
+            templater.add_template(name, template).map_err(|err| {
+                swaytools::error::Error::Invalid("template", name.to_owned(), err.to_string())
+            })?;
+        }
+
+        // Later fields override earlier ones, empty templates remove them
+        let mut fields: Vec<(&str, &str)> = DEFAULT_FIELDS.to_vec();
+        for field in &cli.fields {
+            let (name, template) = field.split_once('=').ok_or_else(|| {
+                swaytools::error::Error::Invalid(
+                    "template",
+                    field.to_owned(),
+                    "expected NAME=TEMPLATE".to_owned(),
+                )
+            })?;
+            fields.retain(|(other, _)| *other != name);
+            fields.push((name, template));
+        }
+        fields.retain(|(_, template)| !template.is_empty());
+        for (name, template) in &fields {
+            templater.add_template(name, template).map_err(|err| {
+                swaytools::error::Error::Invalid("template", name.to_string(), err.to_string())
+            })?;
+        }
+
+        Ok(Printer {
+            templater,
+            icons,
+            format_separator: &cli.format_separator,
+            tooltip_separator: &cli.tooltip_separator,
+            format_empty: cli.format_empty.as_deref(),
+            tooltip_empty: cli.tooltip_empty.as_deref(),
+            hide_default: cli.hide_default.as_deref(),
+            output: cli.output_format,
+            group_identical: cli.group_identical,
+            flag_placeholder: &cli.flag_placeholder,
+            short_case: cli.short_case,
+            short_variant: cli.short_variant,
+            fields: fields.into_iter().map(|(name, _)| name).collect(),
+            files: &cli.sinks,
+            exec: cli.exec.as_deref(),
+            blocks: Vec::new(),
+            started: false,
+        })
+    }
+
+    /// Returns the icon or flag of `layout`.
+    pub fn flag(&self, layout: &Layout) -> String {
+        self.icons
+            .get(&layout.name, layout.variant.as_deref())
+            .map(str::to_owned)
+            .or_else(|| layout.flag())
+            .unwrap_or_else(|| self.flag_placeholder.to_owned())
+    }
+
+    /// Returns the short code of `layout`, e.g., `DE` or `RU (phonetic)`.
+    fn short(&self, layout: &Layout) -> String {
+        let code = layout
+            .brief
+            .as_deref()
+            .filter(|brief| !brief.is_empty())
+            .unwrap_or(&layout.name);
+        let mut short = match self.short_case {
+            ShortCase::Upper => code.to_uppercase(),
+            ShortCase::Lower => code.to_lowercase(),
+            ShortCase::Keep => code.to_owned(),
+        };
+        if let Some(variant) = layout.variant.as_deref().filter(|_| self.short_variant) {
+            if !variant.is_empty() {
+                short = format!("{short} ({variant})");
+            }
+        }
+        short
+    }
+
+    /// Replaces the blocks of another status generator.
+    pub fn set_blocks(&mut self, blocks: Vec<Value>) {
+        self.blocks = blocks;
+    }
+
+    /// Prints the keyboard situation of `layouts` to stdout and all other sinks.
+    pub fn print(&mut self, layouts: &HashMap<String, Keyboard>) {
+        if self.output == OutputFormat::Swaybar && !self.started {
+            println!("{}", json!({ "version": 1 }));
+            println!("[");
+            self.started = true;
+        }
+        match self.output {
+            OutputFormat::Swaybar => println!("{},", self.render(self.output, layouts)),
+            _ => println!("{}", self.render(self.output, layouts)),
+        }
+
+        for (output, path) in self.files {
+            if let Err(err) = write_file(path, &self.render(*output, layouts)) {
+                eprintln!("Cannot write {}: {err}", path.display());
+            }
+        }
+        if let Some(command) = self.exec {
+            let spawned = Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .env(
+                    "SWAYTOOLS_KEYBOARD_TEXT",
+                    self.render(OutputFormat::Plain, layouts),
+                )
+                .env(
+                    "SWAYTOOLS_KEYBOARD_JSON",
+                    self.render(OutputFormat::Waybar, layouts),
+                )
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .spawn();
+            match spawned {
+                // Reap the hook without blocking the updates
+                Ok(mut child) => {
+                    thread::spawn(move || child.wait());
+                }
+                Err(err) => eprintln!("Cannot run '{command}': {err}"),
+            }
+        }
+    }
+
+    /// Renders the keyboard situation of `layouts` as a line of the `output` protocol.
+    fn render(&self, output: OutputFormat, layouts: &HashMap<String, Keyboard>) -> String {
+        let single_contexts: Vec<SingleContext> = layouts
+            .iter()
+            .sorted_by_key(|x| x.0)
+            .map(|x| SingleContext {
+                keyboard: output.escape(&x.1.name),
+                description: output.escape(&x.1.layout.description),
+                name: output.escape(&x.1.layout.name),
+                variant: output.escape(x.1.layout.variant.as_deref().unwrap_or_default()),
+                brief: output.escape(x.1.layout.brief.as_deref().unwrap_or_default()),
+                short: output.escape(&self.short(&x.1.layout)),
+                flag: output.escape(&self.flag(&x.1.layout)),
+                layouts: x
+                    .1
+                    .layout_names
+                    .iter()
+                    .map(|name| output.escape(name))
+                    .collect(),
+                index: x.1.active_index,
+                number: x.1.active_index + 1,
+                count: x.1.layout_names.len(),
+                caps: x.1.locks.caps,
+                num: x.1.locks.num,
+                keyboards: 1,
+                options: output.escape(x.1.xkb.options.as_deref().unwrap_or_default()),
+                model: output.escape(x.1.xkb.model.as_deref().unwrap_or_default()),
+                rules: output.escape(x.1.xkb.rules.as_deref().unwrap_or_default()),
+                vendor: x.1.vendor.map(crate::hex_id).unwrap_or_default(),
+                product: x.1.product.map(crate::hex_id).unwrap_or_default(),
+                send_events: x.1.send_events.to_owned().unwrap_or_default(),
+            })
+            .collect();
+
+        // Keyboards with the same active layout share an entry, only the tooltip lists them all
+        let mut grouped: Vec<SingleContext> = Vec::new();
+        if self.group_identical {
+            for context in &single_contexts {
+                match grouped
+                    .iter_mut()
+                    .find(|group| group.description == context.description)
+                {
+                    Some(group) => group.keyboards += 1,
+                    None => grouped.push(context.clone()),
+                }
+            }
+        }
+
+        let format_singles = if self.group_identical {
+            &grouped
+        } else {
+            &single_contexts
+        }
+        .iter()
+        .map(|c| self.templater.render("format_single", c))
+        .filter_map(|s| s.ok())
+        .join(self.format_separator);
+
+        let tooltip_singles = single_contexts
+            .iter()
+            .map(|c| self.templater.render("tooltip_single", c))
+            .filter_map(|s| s.ok())
+            .join(self.tooltip_separator);
+
+        // Without any keyboards, the placeholders are shown instead of the templates
+        let empty = single_contexts.is_empty();
+        let hidden = !empty
+            && self.hide_default.is_some_and(|default| {
+                layouts.values().all(|keyboard| keyboard.layout.is(default))
+            });
+        let text = match self.format_empty.filter(|_| empty) {
+            _ if hidden => String::new(),
+            Some(text) => text.to_owned(),
+            None => self
+                .templater
+                .render(
+                    "format",
+                    &ResultContext {
+                        result: format_singles,
+                    },
+                )
+                .unwrap(),
+        };
+        let tooltip = match self.tooltip_empty.filter(|_| empty) {
+            Some(tooltip) => tooltip.to_owned(),
+            None => self
+                .templater
+                .render(
+                    "tooltip",
+                    &ResultContext {
+                        result: tooltip_singles,
+                    },
+                )
+                .unwrap(),
+        };
+
+        match output {
+            OutputFormat::Waybar => {
+                let first = single_contexts.first();
+                let context = FieldContext {
+                    names: single_contexts.iter().map(|c| &c.name).unique().join(" "),
+                    name: first.map(|c| c.name.to_owned()).unwrap_or_default(),
+                    variant: first.map(|c| c.variant.to_owned()).unwrap_or_default(),
+                    description: first.map(|c| c.description.to_owned()).unwrap_or_default(),
+                    percentage: first.map_or(0, |c| c.number as usize * 100 / c.count.max(1)),
+                };
+                let mut fields = Map::new();
+                for name in &self.fields {
+                    let Ok(value) = self.templater.render(name, &context) else {
+                        continue;
+                    };
+                    let value = match *name {
+                        "class" => value.split_whitespace().collect(),
+                        _ => serde_json::from_str::<serde_json::Number>(&value)
+                            .map_or(Value::String(value), Value::Number),
+                    };
+                    fields.insert(name.to_string(), value);
+                }
+                let output = WaybarOutput {
+                    text,
+                    tooltip,
+                    fields,
+                };
+                serde_json::to_string(&output).unwrap()
+            }
+            OutputFormat::Swaybar => {
+                let mut blocks = vec![json!({
+                    "name": "keyboard",
+                    "full_text": output.line(&text),
+                    "markup": "pango",
+                })];
+                blocks.extend(self.blocks.iter().cloned());
+                Value::Array(blocks).to_string()
+            }
+            _ => output.line(&text),
+        }
+    }
+}
+
+/// Replaces the contents of the file at `path` by `line` such that readers never see a partially
+/// written file.
+fn write_file(path: &Path, line: &str) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    fs::write(&temporary, format!("{line}\n"))?;
+    fs::rename(&temporary, path)
+}
+
+/// Parses a file sink `FORMAT:FILE` like `plain:/tmp/keyboard`.
+pub fn parse_sink(sink: &str) -> Result<(OutputFormat, PathBuf), String> {
+    let (format, path) = sink
+        .split_once(':')
+        .ok_or_else(|| "expected FORMAT:FILE".to_owned())?;
+    Ok((OutputFormat::from_str(format, true)?, PathBuf::from(path)))
+}
+
+/// Parses a `line` of the swaybar protocol into its blocks.
+///
+/// Returns `None` for the header, the opening bracket, and malformed lines.
+pub fn parse_blocks(line: &str) -> Option<Vec<Value>> {
+    let line = line.trim().trim_start_matches(',').trim_end_matches(',');
+    match serde_json::from_str(line) {
+        Ok(Value::Array(blocks)) => Some(blocks),
+        _ => None,
+    }
+}
+
+/// Removes all pango tags (like `<b>` or `<span color='red'>`) from `text`.
+fn strip_markup(text: &str) -> String {
+    static TAGS: OnceLock<Regex> = OnceLock::new();
+    TAGS.get_or_init(|| {
+        Regex::new(r"</?(b|big|i|s|small|span|sub|sup|tt|u)(\s[^>]*)?>").expect("valid regex")
+    })
+    .replace_all(text, "")
+    .into_owned()
+}
+
+#[derive(Clone, Serialize)]
+struct SingleContext {
+    keyboard: String,
+    description: String,
+    name: String,
+    variant: String,
+    brief: String,
+    /// The brief (or name) of the layout with the case and variant of `--short-case` and
+    /// `--short-variant`.
+    short: String,
+    flag: String,
+    /// The descriptions of all configured layouts.
+    layouts: Vec<String>,
+    /// The (0-based) index of the active layout.
+    index: i32,
+    /// The (1-based) number of the active layout.
+    number: i32,
+    /// The number of configured layouts.
+    count: usize,
+    /// Whether Caps Lock is on.
+    caps: bool,
+    /// Whether Num Lock is on.
+    num: bool,
+    /// The number of keyboards sharing this entry (see `--group-identical`), not named `count` as
+    /// that is the number of layouts.
+    keyboards: usize,
+    /// The configured xkb options like `grp:alt_shift_toggle,compose:ralt`.
+    options: String,
+    /// The configured xkb model.
+    model: String,
+    /// The configured xkb rules.
+    rules: String,
+    /// The vendor id in hex like `046d`, empty if unknown.
+    vendor: String,
+    /// The product id in hex like `c52b`, empty if unknown.
+    product: String,
+    /// The libinput send-events mode like `enabled` or `disabled`.
+    send_events: String,
+}
+
+/// A line of the waybar output.
+#[derive(Serialize)]
+struct WaybarOutput {
+    text: String,
+    tooltip: String,
+    #[serde(flatten)]
+    fields: Map<String, Value>,
+}
+
+/// The context of the additional top-level fields of the waybar output.
+#[derive(Serialize)]
+struct FieldContext {
+    /// The distinct layout names of all keyboards separated by spaces.
+    names: String,
+    /// The layout name of the first keyboard.
+    name: String,
+    /// The layout variant of the first keyboard.
+    variant: String,
+    /// The layout description of the first keyboard.
+    description: String,
+    /// The position of the active layout among all layouts of the first keyboard in percent.
+    percentage: usize,
+}
+
+#[derive(Serialize)]
+struct ResultContext {
+    result: String,
+}
@@ -0,0 +1,234 @@
+//! The layouts known to the xkb registry.
+//!
+//! Parsing the registry is expensive, so it is parsed once into a [`Registry`] and only parsed
+//! again when the registry files change (see [`watch`]).
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    collections::HashMap,
+    env,
+    ffi::CStr,
+    os::raw::c_char,
+    path::{Path, PathBuf},
+};
+use xkbregistry::{
+    rxkb_context_new, rxkb_context_parse_default_ruleset, rxkb_context_unref,
+    rxkb_iso3166_code_get_code, rxkb_layout, rxkb_layout_first, rxkb_layout_get_brief,
+    rxkb_layout_get_description, rxkb_layout_get_iso3166_first, rxkb_layout_get_name,
+    rxkb_layout_get_variant, rxkb_layout_next, RXKB_CONTEXT_LOAD_EXOTIC_RULES,
+};
+
+/// All ISO 3166-1 alpha-2 country codes.
+const ISO3166: &str = "AD AE AF AG AI AL AM AO AQ AR AS AT AU AW AX AZ BA BB BD BE BF BG BH BI BJ \
+    BL BM BN BO BQ BR BS BT BV BW BY BZ CA CC CD CF CG CH CI CK CL CM CN CO CR CU CV CW CX CY CZ \
+    DE DJ DK DM DO DZ EC EE EG EH ER ES ET FI FJ FK FM FO FR GA GB GD GE GF GG GH GI GL GM GN GP \
+    GQ GR GS GT GU GW GY HK HM HN HR HT HU ID IE IL IM IN IO IQ IR IS IT JE JM JO JP KE KG KH KI \
+    KM KN KP KR KW KY KZ LA LB LC LI LK LR LS LT LU LV LY MA MC MD ME MF MG MH MK ML MM MN MO MP \
+    MQ MR MS MT MU MV MW MX MY MZ NA NC NE NF NG NI NL NO NP NR NU NZ OM PA PE PF PG PH PK PL PM \
+    PN PR PS PT PW PY QA RE RO RS RU RW SA SB SC SD SE SG SH SI SJ SK SL SM SN SO SR SS ST SV SX \
+    SY SZ TC TD TF TG TH TJ TK TL TM TN TO TR TT TV TW TZ UA UG UM US UY UZ VA VC VE VG VI VN VU \
+    WF WS YE YT ZA ZM ZW";
+
+/// Flags of layouts whose name is no country code or whose variant is not tied to the country.
+///
+/// Layouts are written as `name` or `name(variant)`, the latter taking precedence.
+const EXCEPTIONS: &[(&str, &str)] = &[
+    ("uk", "🇬🇧"),
+    ("mao", "🇳🇿"),
+    ("us(intl)", "🌐"),
+    ("us(alt-intl)", "🌐"),
+    ("epo", "🌐"),
+    ("latam", "🌎"),
+];
+
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub description: String,
+    pub name: String,
+    pub variant: Option<String>,
+    pub brief: Option<String>,
+    /// The first country the registry associates with the layout.
+    pub country: Option<String>,
+}
+
+impl Layout {
+    /// Returns whether this is `layout`, given as xkb name (if there is no variant),
+    /// `name(variant)`, or description.
+    pub fn is(&self, layout: &str) -> bool {
+        if self.description.eq_ignore_ascii_case(layout) {
+            return true;
+        }
+        match self
+            .variant
+            .as_deref()
+            .filter(|variant| !variant.is_empty())
+        {
+            Some(variant) => layout == format!("{}({variant})", self.name),
+            None => layout == self.name,
+        }
+    }
+
+    /// Returns the flag of the layout's country as regional indicator symbols.
+    ///
+    /// The country is taken from the known exceptions, the registry, or the layout name (in this
+    /// order). Returns `None` if none of them is a valid ISO 3166 code.
+    pub fn flag(&self) -> Option<String> {
+        let variant = self
+            .variant
+            .as_deref()
+            .filter(|variant| !variant.is_empty());
+        let exception = |key: &str| {
+            EXCEPTIONS
+                .iter()
+                .find(|(layout, _)| *layout == key)
+                .map(|(_, flag)| flag.to_string())
+        };
+        if let Some(flag) = variant
+            .and_then(|variant| exception(&format!("{}({variant})", self.name)))
+            .or_else(|| exception(&self.name))
+        {
+            return Some(flag);
+        }
+        self.country
+            .as_deref()
+            .and_then(country_flag)
+            .or_else(|| country_flag(&self.name))
+    }
+}
+
+/// Returns the flag of the ISO 3166 country `code` (ignoring case).
+fn country_flag(code: &str) -> Option<String> {
+    let code = code.to_ascii_uppercase();
+    if code.len() != 2 || !ISO3166.split_whitespace().any(|valid| valid == code) {
+        return None;
+    }
+    // The regional indicator symbols start at U+1F1E6 for 'A'
+    code.chars()
+        .map(|letter| char::from_u32(0x1f1e6 + (letter as u32 - 'A' as u32)))
+        .collect()
+}
+
+/// All layouts of the xkb registry by their description (which sway reports as layout name).
+#[derive(Debug, Default)]
+pub struct Registry {
+    layouts: HashMap<String, Layout>,
+}
+
+impl Registry {
+    /// Parses the default ruleset of the xkb registry.
+    ///
+    /// Returns an empty registry if it cannot be parsed.
+    pub fn load() -> Registry {
+        let mut layouts = HashMap::new();
+
+        let ctx = unsafe { rxkb_context_new(RXKB_CONTEXT_LOAD_EXOTIC_RULES) };
+        if ctx.is_null() {
+            return Registry { layouts };
+        }
+        if !unsafe { rxkb_context_parse_default_ruleset(ctx) } {
+            unsafe { rxkb_context_unref(ctx) };
+            return Registry { layouts };
+        }
+        let mut layout = unsafe { rxkb_layout_first(ctx) };
+        while !layout.is_null() {
+            if let Some(description) =
+                c_char_ptr_to_string(unsafe { rxkb_layout_get_description(layout) })
+            {
+                // The first layout with a description wins
+                layouts
+                    .entry(description.to_owned())
+                    .or_insert_with(|| Layout {
+                        description,
+                        name: c_char_ptr_to_string(unsafe { rxkb_layout_get_name(layout) })
+                            .unwrap_or_default(),
+                        variant: c_char_ptr_to_string(unsafe { rxkb_layout_get_variant(layout) }),
+                        brief: c_char_ptr_to_string(unsafe { rxkb_layout_get_brief(layout) }),
+                        country: first_country(layout),
+                    });
+            }
+
+            layout = unsafe { rxkb_layout_next(layout) };
+        }
+        unsafe { rxkb_context_unref(ctx) };
+
+        Registry { layouts }
+    }
+
+    /// Returns the layout with the given `description`.
+    pub fn get(&self, description: &str) -> Option<&Layout> {
+        self.layouts.get(description)
+    }
+
+    /// Returns the layout with the given `description` or, if it is unknown (like custom
+    /// layouts), a layout which only has the description (also used as name).
+    pub fn resolve(&self, description: &str) -> Layout {
+        self.get(description).cloned().unwrap_or_else(|| Layout {
+            description: description.to_owned(),
+            name: description.to_owned(),
+            variant: None,
+            brief: None,
+            country: None,
+        })
+    }
+}
+
+/// Returns the first ISO 3166 country code of `layout`.
+fn first_country(layout: *mut rxkb_layout) -> Option<String> {
+    let code = unsafe { rxkb_layout_get_iso3166_first(layout) };
+    if code.is_null() {
+        None
+    } else {
+        c_char_ptr_to_string(unsafe { rxkb_iso3166_code_get_code(code) })
+    }
+}
+
+/// Convert a given char pointer from a C function into an optional String.
+///
+/// Returns the converted string if the pointer is valid and the underlying memory can be interpreted as an utf8 string.
+/// Returns `None` otherwise.
+fn c_char_ptr_to_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        None
+    } else {
+        let c_str = unsafe { CStr::from_ptr(ptr) };
+        c_str.to_str().map(|s| s.to_owned()).ok()
+    }
+}
+
+/// Returns the `rules` directories of all xkb include paths which exist.
+fn rules_dirs() -> Vec<PathBuf> {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    let config = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home.as_ref().map(|home| home.join(".config")));
+    [
+        config.map(|config| config.join("xkb")),
+        home.map(|home| home.join(".xkb")),
+        Some(PathBuf::from("/etc/xkb")),
+        Some(
+            env::var_os("XKB_CONFIG_ROOT")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from("/usr/share/X11/xkb")),
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|dir| dir.join("rules"))
+    .filter(|dir| Path::is_dir(dir))
+    .collect()
+}
+
+/// Calls `on_change` whenever a file of the xkb registry changes.
+///
+/// Changes are only reported as long as the returned watcher is alive.
+pub fn watch<F: Fn() + Send + 'static>(on_change: F) -> notify::Result<RecommendedWatcher> {
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if event.is_ok_and(|event| !event.kind.is_access()) {
+            on_change();
+        }
+    })?;
+    for dir in rules_dirs() {
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+    }
+    Ok(watcher)
+}
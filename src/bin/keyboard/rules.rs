@@ -0,0 +1,82 @@
+//! Rules selecting the keyboard layout by the focused application.
+//!
+//! The rules file is a TOML file with a list of rules, the first matching rule wins:
+//!
+//! ```toml
+//! [[rule]]
+//! app_id = "/^(foot|kitty|Alacritty)$/"
+//! layout = "us"
+//!
+//! [[rule]]
+//! class = "libreoffice-*"
+//! layout = "de"
+//! ```
+//!
+//! `app_id` (Wayland) and `class` (Xwayland) are patterns like the include and exclude entries; a
+//! rule with both matches if either matches. `layout` is an xkb name, `name(variant)`, or
+//! description.
+
+use serde::Deserialize;
+use std::fs;
+use swayipc::Node;
+use swaytools::{error::Fallible, pattern::Pattern};
+
+#[derive(Deserialize)]
+struct RulesFile {
+    #[serde(default, rename = "rule")]
+    rules: Vec<RuleEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleEntry {
+    app_id: Option<String>,
+    class: Option<String>,
+    layout: String,
+}
+
+/// A layout for the windows whose app id or class matches.
+struct Rule {
+    app_id: Option<Pattern>,
+    class: Option<Pattern>,
+    layout: String,
+}
+
+/// The rules of a rules file in their order.
+pub struct Rules(Vec<Rule>);
+
+impl Rules {
+    /// Loads the rules from the TOML file at `path`.
+    pub fn load(path: &str) -> Fallible<Rules> {
+        let file: RulesFile = toml::from_str(&fs::read_to_string(path)?)?;
+        let mut rules = Vec::new();
+        for entry in file.rules {
+            rules.push(Rule {
+                app_id: entry.app_id.as_deref().map(Pattern::parse).transpose()?,
+                class: entry.class.as_deref().map(Pattern::parse).transpose()?,
+                layout: entry.layout,
+            });
+        }
+        Ok(Rules(rules))
+    }
+
+    /// Returns the layout of the first rule matching `window`.
+    pub fn layout_for(&self, window: &Node) -> Option<&str> {
+        let class = window
+            .window_properties
+            .as_ref()
+            .and_then(|properties| properties.class.as_deref());
+        let matches = |pattern: &Option<Pattern>, value: Option<&str>| {
+            pattern
+                .as_ref()
+                .zip(value)
+                .is_some_and(|(pattern, value)| pattern.matches(value))
+        };
+        self.0
+            .iter()
+            .find(|rule| {
+                matches(&rule.app_id, window.app_id.as_deref()) || matches(&rule.class, class)
+            })
+            .map(|rule| rule.layout.as_str())
+    }
+}
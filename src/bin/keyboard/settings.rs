@@ -0,0 +1,96 @@
+//! The xkb options, model, and rules of keyboards as configured in the sway config.
+//!
+//! Sway does not report these in `get_inputs`, so they are read from the `input` commands of the
+//! config returned by `get_config`. Files included by the config are not considered.
+
+use swayipc::{Connection, Input};
+use swaytools::sway_config;
+
+/// The xkb settings configured for a keyboard, `None` if sway's default is used.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XkbSettings {
+    pub options: Option<String>,
+    pub model: Option<String>,
+    pub rules: Option<String>,
+}
+
+/// The xkb settings of all `input` commands of the sway config.
+#[derive(Debug, Default)]
+pub struct XkbConfig {
+    /// The input identifier, setting name, and value in the order of the config.
+    entries: Vec<(String, String, String)>,
+}
+
+impl XkbConfig {
+    /// Reads the current sway config, an unavailable config yields no settings.
+    pub fn load(sway: &mut Connection) -> XkbConfig {
+        sway.get_config()
+            .map(|config| XkbConfig::parse(&config.config))
+            .unwrap_or_default()
+    }
+
+    /// Parses the `input` commands (single lines and blocks) of the sway `config`.
+    pub fn parse(config: &str) -> XkbConfig {
+        let mut entries = Vec::new();
+        let mut depth: usize = 0;
+        let mut block: Option<String> = None;
+        for words in sway_config::lines(config) {
+            match words.first().map(String::as_str) {
+                None => continue,
+                Some(word) if word.starts_with('#') => continue,
+                Some("}") => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        block = None;
+                    }
+                    continue;
+                }
+                _ => (),
+            }
+            let opens_block = words.last().is_some_and(|word| word == "{");
+            if let Some(identifier) = &block {
+                push_setting(&mut entries, identifier, &words);
+            } else if depth == 0 && words[0] == "input" && words.len() >= 2 {
+                if opens_block && words.len() == 3 {
+                    block = Some(words[1].to_owned());
+                } else {
+                    push_setting(&mut entries, &words[1], &words[2..]);
+                }
+            }
+            if opens_block {
+                depth += 1;
+            }
+        }
+        XkbConfig { entries }
+    }
+
+    /// Returns the settings of `input`, where settings for the identifier win over settings for
+    /// `type:keyboard`, which in turn win over settings for `*`.
+    pub fn settings(&self, input: &Input) -> XkbSettings {
+        let mut settings = XkbSettings::default();
+        for target in ["*", "type:keyboard", &input.identifier] {
+            for (identifier, name, value) in &self.entries {
+                if identifier != target {
+                    continue;
+                }
+                let value = Some(value.to_owned());
+                match name.as_str() {
+                    "xkb_options" => settings.options = value,
+                    "xkb_model" => settings.model = value,
+                    "xkb_rules" => settings.rules = value,
+                    _ => (),
+                }
+            }
+        }
+        settings
+    }
+}
+
+/// Stores the setting given by `words` (name and value) if it is an xkb setting.
+fn push_setting(entries: &mut Vec<(String, String, String)>, identifier: &str, words: &[String]) {
+    if let [name, value @ ..] = words {
+        if matches!(name.as_str(), "xkb_options" | "xkb_model" | "xkb_rules") {
+            entries.push((identifier.to_owned(), name.to_owned(), value.join(" ")));
+        }
+    }
+}
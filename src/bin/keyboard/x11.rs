@@ -0,0 +1,105 @@
+//! The layout of the core keyboard of an X server, for running the tool on i3 or other X11
+//! window managers where sway's input IPC is not available.
+//!
+//! X only knows a single (core) keyboard whose layouts are the XKB groups, so include and exclude
+//! lists have no effect here.
+
+use std::{env, io};
+use swaytools::error::{Error, Fallible};
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        xkb::{self, ConnectionExt as _},
+        xproto::ConnectionExt as _,
+        Event,
+    },
+    rust_connection::RustConnection,
+};
+
+/// The name shown for the core keyboard.
+pub const KEYBOARD_NAME: &str = "X11 core keyboard";
+
+/// Returns whether the X11 backend should be used, i.e., whether this is an i3 or X session and
+/// not a sway session.
+pub fn is_preferred() -> bool {
+    env::var_os("SWAYSOCK").is_none()
+        && (env::var_os("I3SOCK").is_some() || env::var_os("DISPLAY").is_some())
+}
+
+/// Wraps errors of the X connection.
+fn x11_error<E: std::error::Error + Send + Sync + 'static>(err: E) -> Error {
+    Error::Io(io::Error::other(err))
+}
+
+/// A connection to the X server watching the core keyboard.
+pub struct CoreKeyboard {
+    connection: RustConnection,
+}
+
+impl CoreKeyboard {
+    /// Connects to the X server given by `$DISPLAY` and subscribes to layout changes.
+    pub fn connect() -> Fallible<CoreKeyboard> {
+        let (connection, _) = x11rb::connect(None).map_err(x11_error)?;
+        connection
+            .xkb_use_extension(1, 0)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?;
+        connection
+            .xkb_select_events(
+                xkb::ID::USE_CORE_KBD.into(),
+                xkb::EventType::from(0u16),
+                xkb::EventType::STATE_NOTIFY
+                    | xkb::EventType::NAMES_NOTIFY
+                    | xkb::EventType::NEW_KEYBOARD_NOTIFY,
+                xkb::MapPart::from(0u16),
+                xkb::MapPart::from(0u16),
+                &xkb::SelectEventsAux::new(),
+            )
+            .map_err(x11_error)?
+            .check()
+            .map_err(x11_error)?;
+        Ok(CoreKeyboard { connection })
+    }
+
+    /// Returns the descriptions of all configured layouts and the index of the active one.
+    pub fn layouts(&self) -> Fallible<(Vec<String>, i32)> {
+        let state = self
+            .connection
+            .xkb_get_state(xkb::ID::USE_CORE_KBD.into())
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?;
+        let names = self
+            .connection
+            .xkb_get_names(xkb::ID::USE_CORE_KBD.into(), xkb::NameDetail::GROUP_NAMES)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?;
+        let mut layouts = Vec::new();
+        for atom in names.value_list.groups.unwrap_or_default() {
+            let name = self
+                .connection
+                .get_atom_name(atom)
+                .map_err(x11_error)?
+                .reply()
+                .map_err(x11_error)?
+                .name;
+            layouts.push(String::from_utf8_lossy(&name).into_owned());
+        }
+        Ok((layouts, i32::from(u8::from(state.group))))
+    }
+
+    /// Blocks until the active layout or the configured layouts change.
+    pub fn wait_for_change(&self) -> Fallible<()> {
+        loop {
+            match self.connection.wait_for_event().map_err(x11_error)? {
+                Event::XkbStateNotify(ev) if ev.changed.contains(xkb::StatePart::GROUP_STATE) => {
+                    return Ok(())
+                }
+                Event::XkbNamesNotify(_) | Event::XkbNewKeyboardNotify(_) => return Ok(()),
+                _ => (),
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Launching applications on workspaces when they are created empty.
+//!
+//! The autostart file lists the commands per workspace name or number:
+//!
+//! ```toml
+//! [workspace]
+//! 1 = ["foot"]
+//! 9 = ["firefox", "thunderbird"]
+//! ```
+//!
+//! Whenever one of these workspaces is created without windows, i.e., by switching to it, its
+//! commands are run. A workspace which is created by moving a window to it is left alone.
+
+use clap::ValueHint;
+use serde::Deserialize;
+use std::{collections::HashMap, fs};
+use swayipc::{Event, EventType, Node, NodeType, WorkspaceChange};
+use swaytools::{
+    env,
+    error::Fallible,
+    events::{EventStream, StreamEvent},
+    tree, Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The TOML file with the commands [default: $XDG_CONFIG_HOME/swaytools/autostart.toml]
+    #[arg(short, long, value_hint = ValueHint::FilePath)]
+    config: Option<String>,
+}
+
+/// The contents of an autostart file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Autostart {
+    #[serde(default)]
+    workspace: HashMap<String, Vec<String>>,
+}
+
+impl Autostart {
+    /// Returns the commands of the `workspace` if it is empty.
+    fn commands(&self, workspace: &Node) -> &[String] {
+        if !workspace.nodes.is_empty() || !workspace.floating_nodes.is_empty() {
+            return &[];
+        }
+        let name = workspace.name.as_deref().unwrap_or_default();
+        self.workspace
+            .get(name)
+            .or_else(|| {
+                let num = workspace.num?.to_string();
+                self.workspace.get(&num)
+            })
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Runs the commands of the focused `workspace`.
+    fn launch(&self, workspace: &Node, sway: &mut Connection) -> Fallible<()> {
+        let commands: Vec<String> = self
+            .commands(workspace)
+            .iter()
+            .map(|command| format!("exec {command}"))
+            .collect();
+        if commands.is_empty() {
+            return Ok(());
+        }
+        sway.run(commands.join("; "))
+    }
+}
+
+/// Launches the applications of every new empty workspace until sway exits.
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let path = args.config.unwrap_or_else(|| {
+        env::config_file("autostart.toml")
+            .to_string_lossy()
+            .into_owned()
+    });
+    let autostart: Autostart = toml::from_str(&fs::read_to_string(path)?)?;
+    let mut sway = Connection::new(dry_run)?;
+    // Subscribe before looking at the focused workspace so that no workspace is missed.
+    let events = EventStream::subscribe(&[EventType::Workspace])?;
+    // The workspace sway started with was created before the daemon.
+    launch_if_focused(&autostart, None, &mut sway)?;
+
+    for event in events {
+        let event = match event {
+            StreamEvent::Event(event) => event,
+            StreamEvent::Reconnected => {
+                sway = Connection::new(dry_run)?;
+                continue;
+            }
+        };
+        let Event::Workspace(event) = *event else {
+            continue;
+        };
+        if event.change != WorkspaceChange::Init {
+            continue;
+        }
+        let Some(id) = event.current.map(|workspace| workspace.id) else {
+            continue;
+        };
+        if let Err(err) = launch_if_focused(&autostart, Some(id), &mut sway) {
+            eprintln!("Cannot launch the applications of a new workspace: {err}");
+        }
+    }
+    Ok(())
+}
+
+/// Runs the commands of the workspace with the given `id` (or any workspace if `None`) if it is
+/// empty and focused.
+///
+/// sway reports a new workspace before a window moved to it arrives, so the workspace is looked
+/// up again to tell a workspace the user switched to from one receiving a window.
+fn launch_if_focused(
+    autostart: &Autostart,
+    id: Option<i64>,
+    sway: &mut Connection,
+) -> Fallible<()> {
+    let tree = sway.get_tree()?;
+    let focused = tree::find(&tree, |node| {
+        node.focused && node.node_type == NodeType::Workspace && id.is_none_or(|id| node.id == id)
+    });
+    match focused {
+        Some(workspace) => autostart.launch(workspace.node, sway),
+        None => Ok(()),
+    }
+}
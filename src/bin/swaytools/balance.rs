@@ -0,0 +1,63 @@
+//! Giving the tiled containers of the focused workspace equal sizes.
+//!
+//! At each horizontal or vertical split level, all children get the same share of the width or
+//! height of their parent. The tree is balanced from the workspace downwards, so the sizes of
+//! nested containers follow from the already balanced ones. Tabbed and stacked containers only
+//! have their children balanced.
+
+use swayipc::{Node, NodeLayout};
+use swaytools::{
+    error::{Error, Fallible},
+    tree, Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {}
+
+/// Collects the `resize set` commands balancing the children of `node`, which is (or will be)
+/// `width` by `height` pixels large.
+fn balance(node: &Node, width: i32, height: i32, commands: &mut Vec<String>) {
+    let count = i32::try_from(node.nodes.len()).unwrap_or(i32::MAX);
+    for child in &node.nodes {
+        let (mut child_width, mut child_height) = (width, height);
+        if count > 1 {
+            match node.layout {
+                NodeLayout::SplitH => {
+                    child_width = width / count;
+                    commands.push(format!(
+                        "[con_id={}] resize set width {child_width} px",
+                        child.id
+                    ));
+                }
+                NodeLayout::SplitV => {
+                    child_height = height / count;
+                    commands.push(format!(
+                        "[con_id={}] resize set height {child_height} px",
+                        child.id
+                    ));
+                }
+                _ => (),
+            }
+        }
+        balance(child, child_width, child_height, commands);
+    }
+}
+
+pub fn run(_args: Args, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    let tree = sway.get_tree()?;
+    let workspace = tree::find(&tree, |node| node.focused)
+        .and_then(|location| location.workspace)
+        .ok_or(Error::NoFocusedWorkspace)?;
+    let mut commands = Vec::new();
+    balance(
+        workspace,
+        workspace.rect.width,
+        workspace.rect.height,
+        &mut commands,
+    );
+    if commands.is_empty() {
+        return Ok(());
+    }
+    sway.run(commands.join("; "))
+}
@@ -0,0 +1,264 @@
+//! Hiding and showing the bars depending on the visible workspaces.
+//!
+//! A bar is hidden while the visible workspace of each of its outputs has a fullscreen window, is
+//! one of the configured workspaces, or nothing happened on it for a while. The rules are set at
+//! the top level of the bars file and can be changed per output name or identifier:
+//!
+//! ```toml
+//! fullscreen = true
+//! workspaces = ["games", "8-10"]
+//! # Seconds without window or workspace events
+//! idle = 30
+//!
+//! [output."HDMI-A-1"]
+//! idle = 0
+//!
+//! # The outputs of each bar, all outputs for bars not listed
+//! [bar]
+//! bar-0 = ["eDP-1"]
+//! bar-1 = ["HDMI-A-1"]
+//! ```
+//!
+//! sway bars are hidden with `bar … mode invisible` and shown with `bar … mode dock` (or the
+//! `mode` from the file). With `waybar = true`, waybar is sent `SIGUSR1` instead, which toggles
+//! all of its bars at once, so it is only hidden while all outputs would hide their bars.
+
+use clap::ValueHint;
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    process::Command,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+use swayipc::{Event, EventType};
+use swaytools::{
+    env,
+    error::{Error, Fallible},
+    events::{EventStream, StreamEvent},
+    mapping::parse_workspaces,
+    output_identifier, tree, Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The TOML file with the rules [default: $XDG_CONFIG_HOME/swaytools/bars.toml]
+    #[arg(short, long, value_hint = ValueHint::FilePath)]
+    config: Option<String>,
+}
+
+/// The rules of an output, each falling back to the top level of the file if not set.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Rules {
+    fullscreen: Option<bool>,
+    workspaces: Option<Vec<String>>,
+    idle: Option<u64>,
+}
+
+/// The contents of a bars file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BarsFile {
+    /// Hide the bars while a fullscreen window is visible.
+    #[serde(default = "default_fullscreen")]
+    fullscreen: bool,
+    /// The workspaces (names, numbers, or ranges) on which the bars are hidden.
+    #[serde(default)]
+    workspaces: Vec<String>,
+    /// The seconds after the last window or workspace event the bars are hidden, 0 for never.
+    #[serde(default)]
+    idle: u64,
+    /// The mode of shown sway bars.
+    #[serde(default = "default_mode")]
+    mode: String,
+    /// Signal waybar instead of changing the mode of sway bars.
+    #[serde(default)]
+    waybar: bool,
+    #[serde(default)]
+    output: HashMap<String, Rules>,
+    #[serde(default)]
+    bar: HashMap<String, Vec<String>>,
+}
+
+fn default_fullscreen() -> bool {
+    true
+}
+
+fn default_mode() -> String {
+    "dock".to_owned()
+}
+
+impl BarsFile {
+    /// Returns whether the rules for `output` hide its bars after `idle` time without events.
+    fn hides(&self, output: &swayipc::Output, tree: &swayipc::Node, idle: Duration) -> bool {
+        let default = Rules::default();
+        let rules = self
+            .output
+            .get(&output.name)
+            .or_else(|| self.output.get(&output_identifier(output)))
+            .unwrap_or(&default);
+        let Some(workspace) = tree::walk(tree).into_iter().find(|location| {
+            location.node.node_type == swayipc::NodeType::Workspace
+                && location.output_name() == Some(output.name.as_str())
+                && output.current_workspace.as_deref() == location.node.name.as_deref()
+        }) else {
+            return false;
+        };
+        let timeout = rules.idle.unwrap_or(self.idle);
+        if timeout > 0 && idle >= Duration::from_secs(timeout) {
+            return true;
+        }
+        let workspaces = rules.workspaces.as_ref().unwrap_or(&self.workspaces);
+        let name = workspace.node.name.as_deref().unwrap_or_default();
+        let num = workspace.node.num.unwrap_or(-1);
+        if workspaces.iter().any(|key| {
+            key == name || parse_workspaces(key, 0, key).is_ok_and(|numbers| numbers.contains(&num))
+        }) {
+            return true;
+        }
+        rules.fullscreen.unwrap_or(self.fullscreen)
+            && tree::walk(workspace.node)
+                .iter()
+                .any(|location| location.node.fullscreen_mode.is_some_and(|mode| mode > 0))
+    }
+
+    /// Returns the idle timeouts of all outputs in ascending order.
+    fn timeouts(&self) -> Vec<Duration> {
+        let mut timeouts: Vec<u64> = self
+            .output
+            .values()
+            .map(|rules| rules.idle.unwrap_or(self.idle))
+            .chain([self.idle])
+            .filter(|&timeout| timeout > 0)
+            .collect();
+        timeouts.sort_unstable();
+        timeouts.dedup();
+        timeouts.into_iter().map(Duration::from_secs).collect()
+    }
+}
+
+/// Sets the visibility of the bars.
+struct Bars {
+    file: BarsFile,
+    /// Whether each bar is hidden, as far as known.
+    hidden: HashMap<String, bool>,
+}
+
+impl Bars {
+    /// Hides or shows the bars whose visibility changed.
+    fn update(&mut self, idle: Duration, sway: &mut Connection) -> Fallible<()> {
+        let tree = sway.get_tree()?;
+        let outputs: Vec<swayipc::Output> = sway
+            .get_outputs()?
+            .into_iter()
+            .filter(|output| output.active)
+            .collect();
+        let hiding: HashSet<&str> = outputs
+            .iter()
+            .filter(|output| self.file.hides(output, &tree, idle))
+            .map(|output| output.name.as_str())
+            .collect();
+        // Without outputs nothing is to be seen anyway.
+        let all_hiding = !outputs.is_empty() && hiding.len() == outputs.len();
+        if self.file.waybar {
+            return self.set("waybar", all_hiding, sway);
+        }
+        for id in sway.get_bar_ids()? {
+            let hidden = match self.file.bar.get(&id) {
+                Some(names) => names.iter().all(|name| hiding.contains(name.as_str())),
+                None => all_hiding,
+            };
+            self.set(&id, hidden, sway)?;
+        }
+        Ok(())
+    }
+
+    /// Hides or shows the bar `id` if it is not already.
+    fn set(&mut self, id: &str, hidden: bool, sway: &mut Connection) -> Fallible<()> {
+        if self.hidden.get(id) == Some(&hidden) {
+            return Ok(());
+        }
+        if !self.file.waybar {
+            let mode = if hidden { "invisible" } else { &self.file.mode };
+            sway.run(format!("bar {id} mode {mode}"))?;
+        } else if self.hidden.get(id).copied().unwrap_or(false) != hidden && !sway.dry_run() {
+            // waybar starts visible and toggles on each signal.
+            let status = Command::new("pkill")
+                .args(["-USR1", "-x", "waybar"])
+                .status()?;
+            if !status.success() {
+                return Err(Error::CommandFailed("pkill".to_owned(), status.to_string()));
+            }
+        }
+        self.hidden.insert(id.to_owned(), hidden);
+        Ok(())
+    }
+}
+
+/// Hides and shows the bars until sway exits.
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let path = args
+        .config
+        .unwrap_or_else(|| env::config_file("bars.toml").to_string_lossy().into_owned());
+    let file: BarsFile = toml::from_str(&fs::read_to_string(path)?)?;
+    let timeouts = file.timeouts();
+    let mut bars = Bars {
+        file,
+        hidden: HashMap::new(),
+    };
+    let mut sway = Connection::new(dry_run)?;
+    // Subscribe before the first update so that no change is missed in between.
+    let events =
+        EventStream::subscribe(&[EventType::Workspace, EventType::Window, EventType::Output])?;
+    let mut last_activity = Instant::now();
+    bars.update(Duration::ZERO, &mut sway)?;
+
+    // sway events are waited for in a thread, so the idle timeouts can pass in between.
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for event in events {
+            if sender.send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    loop {
+        // Wake up when the next timeout passes.
+        let elapsed = last_activity.elapsed();
+        let event = match timeouts.iter().find(|&&timeout| elapsed < timeout) {
+            Some(&timeout) => match receiver.recv_timeout(timeout - elapsed) {
+                Ok(event) => Some(event),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            },
+            _ => match receiver.recv() {
+                Ok(event) => Some(event),
+                Err(_) => break,
+            },
+        };
+        let result = match event {
+            Some(StreamEvent::Event(event)) => {
+                if !matches!(*event, Event::Output(_)) {
+                    last_activity = Instant::now();
+                }
+                bars.update(last_activity.elapsed(), &mut sway)
+            }
+            Some(StreamEvent::Reconnected) => Connection::new(dry_run).and_then(|connection| {
+                sway = connection;
+                // The bars of a new sway are as configured.
+                bars.hidden.clear();
+                last_activity = Instant::now();
+                bars.update(Duration::ZERO, &mut sway)
+            }),
+            None => bars.update(last_activity.elapsed(), &mut sway),
+        };
+        if let Err(err) = result {
+            eprintln!("Cannot update the bars: {err}");
+        }
+    }
+    Ok(())
+}
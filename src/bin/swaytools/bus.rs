@@ -0,0 +1,226 @@
+//! Exporting the workspaces, the focused window, and the keyboard layout on the session bus.
+//!
+//! The daemon owns the name `org.swaytools` and serves the interface `org.swaytools.State` at
+//! `/org/swaytools`:
+//!
+//! - `GetWorkspaces() -> a(issbb)`: number, name, output, focused, and visible of each workspace
+//! - `GetFocusedWindow() -> (xss)`: container id (`-1` if none), app id or class, and title
+//! - `GetKeyboardLayout() -> (sas)`: the active layout and all layouts of the first keyboard
+//!
+//! Each method has a signal of the same name ending in `Changed` instead of starting with `Get`,
+//! e.g., `WorkspacesChanged(a(issbb))`, which is emitted with the new value whenever it changes.
+
+use dbus::{
+    blocking::{stdintf::org_freedesktop_dbus::RequestNameReply, Connection as Bus},
+    channel::{MatchingReceiver, Sender},
+    message::MatchRule,
+    Message,
+};
+use std::{
+    io,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+use swayipc::EventType;
+use swaytools::{
+    error::{Error, Fallible},
+    events::{EventStream, StreamEvent},
+    tree, Connection,
+};
+
+/// The well-known name of the daemon on the session bus.
+const BUS_NAME: &str = "org.swaytools";
+/// The path of the exported object.
+const PATH: &str = "/org/swaytools";
+/// The name of the exported interface.
+const INTERFACE: &str = "org.swaytools.State";
+
+/// The introspection data of the exported object.
+const INTROSPECTION: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name="org.swaytools.State">
+    <method name="GetWorkspaces"><arg type="a(issbb)" direction="out"/></method>
+    <method name="GetFocusedWindow"><arg type="(xss)" direction="out"/></method>
+    <method name="GetKeyboardLayout"><arg type="(sas)" direction="out"/></method>
+    <signal name="WorkspacesChanged"><arg type="a(issbb)"/></signal>
+    <signal name="FocusedWindowChanged"><arg type="(xss)"/></signal>
+    <signal name="KeyboardLayoutChanged"><arg type="(sas)"/></signal>
+  </interface>
+  <interface name="org.freedesktop.DBus.Introspectable">
+    <method name="Introspect"><arg type="s" direction="out"/></method>
+  </interface>
+</node>"#;
+
+/// How long to wait for messages on the bus before looking for sway events.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(clap::Args, Debug)]
+pub struct Args {}
+
+type Workspaces = Vec<(i32, String, String, bool, bool)>;
+type FocusedWindow = (i64, String, String);
+type KeyboardLayout = (String, Vec<String>);
+
+/// The exported state.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct State {
+    workspaces: Workspaces,
+    focused_window: FocusedWindow,
+    keyboard_layout: KeyboardLayout,
+}
+
+impl State {
+    /// Queries the current state from sway.
+    fn query(sway: &mut Connection) -> Fallible<State> {
+        let workspaces = sway
+            .get_workspaces()?
+            .into_iter()
+            .map(|ws| (ws.num, ws.name, ws.output, ws.focused, ws.visible))
+            .collect();
+        let tree = sway.get_tree()?;
+        let focused_window = tree::find(&tree, |node| node.focused)
+            .filter(|location| location.is_window())
+            .map_or((-1, String::new(), String::new()), |location| {
+                let node = location.node;
+                (
+                    node.id,
+                    tree::app(node).unwrap_or_default().to_owned(),
+                    node.name.clone().unwrap_or_default(),
+                )
+            });
+        let keyboard_layout = sway
+            .get_inputs()?
+            .into_iter()
+            .find(|input| input.input_type == "keyboard" && !input.xkb_layout_names.is_empty())
+            .map(|input| {
+                (
+                    input.xkb_active_layout_name.unwrap_or_default(),
+                    input.xkb_layout_names,
+                )
+            })
+            .unwrap_or_default();
+        Ok(State {
+            workspaces,
+            focused_window,
+            keyboard_layout,
+        })
+    }
+
+    /// Returns the signals for the values which differ from `previous`.
+    fn signals(&self, previous: &State) -> Vec<Message> {
+        let signal = |name: &str| {
+            Message::new_signal(PATH, INTERFACE, name).expect("signal names are valid")
+        };
+        let mut signals = Vec::new();
+        if self.workspaces != previous.workspaces {
+            signals.push(signal("WorkspacesChanged").append1(&self.workspaces));
+        }
+        if self.focused_window != previous.focused_window {
+            signals.push(signal("FocusedWindowChanged").append1(&self.focused_window));
+        }
+        if self.keyboard_layout != previous.keyboard_layout {
+            signals.push(signal("KeyboardLayoutChanged").append1(&self.keyboard_layout));
+        }
+        signals
+    }
+}
+
+/// Returns the reply to the method `call` with the current `state`.
+fn reply(call: &Message, state: &State) -> Message {
+    let (interface, member) = (call.interface(), call.member());
+    match (interface.as_deref(), member.as_deref()) {
+        (Some(INTERFACE) | None, Some("GetWorkspaces")) => {
+            Message::method_return(call).append1(&state.workspaces)
+        }
+        (Some(INTERFACE) | None, Some("GetFocusedWindow")) => {
+            Message::method_return(call).append1(&state.focused_window)
+        }
+        (Some(INTERFACE) | None, Some("GetKeyboardLayout")) => {
+            Message::method_return(call).append1(&state.keyboard_layout)
+        }
+        (Some("org.freedesktop.DBus.Introspectable") | None, Some("Introspect")) => {
+            Message::method_return(call).append1(INTROSPECTION)
+        }
+        _ => Message::error(
+            call,
+            &"org.freedesktop.DBus.Error.UnknownMethod".into(),
+            c"unknown method",
+        ),
+    }
+}
+
+/// Reports a failure of the session bus as an I/O error.
+fn bus_error(err: dbus::Error) -> Error {
+    io::Error::other(err.to_string()).into()
+}
+
+/// Serves the state on the session bus and signals its changes until sway exits.
+pub fn run(_args: Args, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    // Subscribe before the first query so that no change is missed in between.
+    let events =
+        EventStream::subscribe(&[EventType::Workspace, EventType::Window, EventType::Input])?;
+    let state = Arc::new(Mutex::new(State::query(&mut sway)?));
+
+    let bus = Bus::new_session().map_err(bus_error)?;
+    // Another running daemon keeps the name, this one must not wait in line for it.
+    let owner = bus
+        .request_name(BUS_NAME, false, true, true)
+        .map_err(bus_error)?;
+    if owner != RequestNameReply::PrimaryOwner {
+        return Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("{BUS_NAME} is already owned by another process"),
+        )
+        .into());
+    }
+    let served = Arc::clone(&state);
+    bus.start_receive(
+        MatchRule::new_method_call().with_path(PATH),
+        Box::new(move |call, bus| {
+            let state = served.lock().expect("the state lock is not poisoned");
+            // A failed send means the caller went away.
+            let _ = bus.send(reply(&call, &state));
+            true
+        }),
+    );
+
+    // sway events are waited for in a thread, the bus is polled in between.
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for event in events {
+            if sender
+                .send(matches!(event, StreamEvent::Reconnected))
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    loop {
+        bus.process(POLL_INTERVAL).map_err(bus_error)?;
+        let pending: Vec<bool> = receiver.try_iter().collect();
+        if pending.is_empty() {
+            continue;
+        }
+        if pending.contains(&true) {
+            sway = Connection::new(dry_run)?;
+        }
+        let current = match State::query(&mut sway) {
+            Ok(current) => current,
+            Err(err) => {
+                eprintln!("Cannot query the state: {err}");
+                continue;
+            }
+        };
+        let mut state = state.lock().expect("the state lock is not poisoned");
+        for signal in current.signals(&state) {
+            // Signals are sent without any receiver in mind, nothing to do if this fails.
+            let _ = bus.send(signal);
+        }
+        *state = current;
+    }
+}
@@ -0,0 +1,153 @@
+//! Moving the workspaces of the internal output away while it is disabled and back afterwards.
+//!
+//! When the internal output is disabled, e.g., because the lid of a docked laptop is closed,
+//! sway moves its workspaces to some other output. Each of them which is mapped to a connected
+//! output is moved there instead. The numbers of the workspaces which were on the internal output
+//! are kept in the state file, so they are moved back once it is enabled again, even if the
+//! daemon was restarted in between.
+
+use std::collections::HashMap;
+use swayipc::{Event, EventType, Output};
+use swaytools::{
+    error::Fallible,
+    events::{EventStream, StreamEvent},
+    load_config, move_workspaces, output_identifier, output_if_exists,
+    state::StateFile,
+    Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The internal output, by name or by make, model, and serial number
+    #[arg(short, long, default_value = "eDP-1")]
+    output: String,
+}
+
+/// The internal output and the workspaces last seen on it.
+struct Clamshell {
+    output: String,
+    state: StateFile,
+    /// Whether the output was enabled when last checked.
+    active: Option<bool>,
+    /// The numbered workspaces on the output while it is enabled.
+    workspaces: Vec<i32>,
+}
+
+impl Clamshell {
+    /// Returns whether `output` is the internal output.
+    fn is(&self, output: &Output) -> bool {
+        output.name == self.output || output_identifier(output) == self.output
+    }
+
+    /// Updates the workspaces of the internal output and moves them if it was disabled or enabled
+    /// since the last update.
+    fn update(&mut self, sway: &mut Connection) -> Fallible<()> {
+        let outputs = sway.get_outputs()?;
+        let internal = outputs
+            .iter()
+            .find(|output| self.is(output) && output.active);
+        let active = internal.is_some();
+        let was_active = self.active.replace(active);
+
+        match (was_active, internal) {
+            (_, Some(internal)) => {
+                let name = internal.name.clone();
+                // Also on start in case the daemon was restarted while the output was disabled.
+                if was_active != Some(true) {
+                    self.restore(&name, sway)?;
+                }
+                self.workspaces = sway
+                    .get_workspaces()?
+                    .into_iter()
+                    .filter(|ws| ws.output == name && ws.num >= 0)
+                    .map(|ws| ws.num)
+                    .collect();
+            }
+            (Some(true), None) => self.evacuate(&outputs, sway)?,
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Moves the workspaces last seen on the internal output to their mapped outputs.
+    fn evacuate(&mut self, outputs: &[Output], sway: &mut Connection) -> Fallible<()> {
+        let evacuated = std::mem::take(&mut self.workspaces);
+        if !sway.dry_run() {
+            self.state
+                .update(|state| state.evacuated = evacuated.clone())?;
+        }
+        let existing: Vec<i32> = sway.get_workspaces()?.iter().map(|ws| ws.num).collect();
+        let mut mapping: HashMap<String, Vec<i32>> = HashMap::new();
+        // A missing mapping maps nothing.
+        for (output, workspaces) in load_config().unwrap_or_default() {
+            let Some(name) = output_if_exists(output, sway) else {
+                continue;
+            };
+            if !outputs.iter().any(|o| o.name == name && o.active) {
+                continue;
+            }
+            let moved: Vec<i32> = workspaces
+                .into_iter()
+                .filter(|num| evacuated.contains(num) && existing.contains(num))
+                .collect();
+            if !moved.is_empty() {
+                mapping.entry(name).or_default().extend(moved);
+            }
+        }
+        if mapping.is_empty() {
+            return Ok(());
+        }
+        move_workspaces(&mapping, sway)
+    }
+
+    /// Moves the workspaces evacuated earlier back to the internal output called `name`.
+    fn restore(&mut self, name: &str, sway: &mut Connection) -> Fallible<()> {
+        let evacuated = self.state.load()?.evacuated;
+        if evacuated.is_empty() {
+            return Ok(());
+        }
+        let existing: Vec<i32> = sway.get_workspaces()?.iter().map(|ws| ws.num).collect();
+        let returning: Vec<i32> = evacuated
+            .into_iter()
+            .filter(|num| existing.contains(num))
+            .collect();
+        if !returning.is_empty() {
+            move_workspaces(&HashMap::from([(name.to_owned(), returning)]), sway)?;
+        }
+        if !sway.dry_run() {
+            self.state.update(|state| state.evacuated.clear())?;
+        }
+        Ok(())
+    }
+}
+
+/// Moves the workspaces whenever the internal output is disabled or enabled until sway exits.
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    let mut clamshell = Clamshell {
+        output: args.output,
+        state: StateFile::open_default(),
+        active: None,
+        workspaces: Vec::new(),
+    };
+    // Subscribe before looking at the outputs so that no change is missed in between.
+    let events = EventStream::subscribe(&[EventType::Output, EventType::Workspace])?;
+    clamshell.update(&mut sway)?;
+
+    for event in events {
+        let result = match event {
+            StreamEvent::Event(event) => match *event {
+                Event::Output(_) | Event::Workspace(_) => clamshell.update(&mut sway),
+                _ => continue,
+            },
+            StreamEvent::Reconnected => Connection::new(dry_run).and_then(|connection| {
+                sway = connection;
+                clamshell.update(&mut sway)
+            }),
+        };
+        if let Err(err) = result {
+            eprintln!("Cannot move the workspaces of the internal output: {err}");
+        }
+    }
+    Ok(())
+}
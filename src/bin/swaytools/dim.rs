@@ -0,0 +1,115 @@
+//! Dimming all windows but the focused one.
+//!
+//! The opacity of a window is set whenever it gains or loses the focus. Windows of excluded apps
+//! are never touched. On `SIGINT`, `SIGTERM`, or `SIGHUP` all dimmed windows are fully opaque
+//! again before the daemon exits.
+
+use crate::rules;
+use regex::Regex;
+use signal_hook::{
+    consts::{SIGHUP, SIGINT, SIGTERM},
+    iterator::Signals,
+};
+use std::{process, thread};
+use swayipc::{Event, EventType, Node, WindowChange};
+use swaytools::{
+    error::Fallible,
+    events::{EventStream, StreamEvent},
+    tree, Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The opacity of unfocused windows between 0 and 1
+    #[arg(short, long, default_value_t = 0.8)]
+    opacity: f64,
+
+    /// Never dim windows whose app id or class matches this regex; may be repeated
+    #[arg(short, long, value_name = "REGEX")]
+    exclude: Vec<String>,
+}
+
+/// Returns whether `window` is not excluded.
+fn is_dimmed(window: &Node, exclude: &[Regex]) -> bool {
+    !tree::app(window).is_some_and(|app| exclude.iter().any(|re| re.is_match(app)))
+}
+
+/// Sets the opacity of all windows but the focused one to `opacity`.
+fn dim_all(opacity: f64, exclude: &[Regex], sway: &mut Connection) -> Fallible<()> {
+    let commands: Vec<String> = tree::windows(&sway.get_tree()?)
+        .into_iter()
+        .filter(|window| is_dimmed(window.node, exclude))
+        .map(|window| {
+            let opacity = if window.node.focused { 1.0 } else { opacity };
+            format!("[con_id={}] opacity {opacity}", window.node.id)
+        })
+        .collect();
+    if commands.is_empty() {
+        return Ok(());
+    }
+    sway.run(commands.join("; "))
+}
+
+/// Dims the unfocused windows until sway exits or a signal arrives.
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let exclude = args
+        .exclude
+        .iter()
+        .map(|pattern| rules::regex(pattern))
+        .collect::<Fallible<Vec<_>>>()?;
+
+    let mut signals = Signals::new([SIGINT, SIGTERM, SIGHUP])?;
+    let restore = exclude.clone();
+    thread::spawn(move || {
+        if signals.forever().next().is_some() {
+            let reset =
+                Connection::new(dry_run).and_then(|mut sway| dim_all(1.0, &restore, &mut sway));
+            if let Err(err) = reset {
+                eprintln!("Cannot reset the opacity: {err}");
+            }
+            process::exit(0);
+        }
+    });
+
+    let mut sway = Connection::new(dry_run)?;
+    // Subscribe before dimming the current windows so that no focus change is missed in between.
+    let events = EventStream::subscribe(&[EventType::Window])?;
+    dim_all(args.opacity, &exclude, &mut sway)?;
+    // The focused window unless it is excluded.
+    let mut focused = tree::find(&sway.get_tree()?, |node| node.focused)
+        .filter(|location| location.is_window() && is_dimmed(location.node, &exclude))
+        .map(|location| location.node.id);
+
+    for event in events {
+        let result = match event {
+            StreamEvent::Event(event) => match *event {
+                Event::Window(event) if event.change == WindowChange::Focus => {
+                    let window = event.container;
+                    let mut commands = Vec::new();
+                    if let Some(previous) = focused.filter(|&id| id != window.id) {
+                        commands.push(format!("[con_id={previous}] opacity {}", args.opacity));
+                    }
+                    focused = is_dimmed(&window, &exclude).then_some(window.id);
+                    if focused.is_some() {
+                        commands.push(format!("[con_id={}] opacity 1", window.id));
+                    }
+                    // The previous window may have been closed, so failures are ignored.
+                    if commands.is_empty() {
+                        continue;
+                    }
+                    sway.run_command(commands.join("; ")).map(|_| ())
+                }
+                _ => continue,
+            },
+            // sway may have been restarted, so all windows are new.
+            StreamEvent::Reconnected => Connection::new(dry_run).and_then(|connection| {
+                sway = connection;
+                dim_all(args.opacity, &exclude, &mut sway)
+            }),
+        };
+        if let Err(err) = result {
+            eprintln!("Cannot update the opacity: {err}");
+        }
+    }
+    Ok(())
+}
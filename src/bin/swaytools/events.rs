@@ -0,0 +1,262 @@
+//! Recording sway events to a log file and printing them for analysis.
+//!
+//! `record` writes one JSON object per line with the time in milliseconds since the Unix epoch and
+//! the event as swayipc serializes it, so a log can be read back with serde, e.g., as a fixture
+//! for tests:
+//!
+//! ```json
+//! {"time_ms":1700000000000,"event":{"Workspace":{"change":"focus","current":{…},"old":{…}}}}
+//! ```
+//!
+//! `replay` prints a summary line per recorded event with its offset from the first one, `tail`
+//! prints the same lines for live events.
+
+use clap::{Subcommand, ValueEnum, ValueHint};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use swayipc::{Event, EventType};
+use swaytools::{
+    error::Fallible,
+    events::{EventStream, StreamEvent},
+    tree,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Command,
+
+    /// The comma-separated types of the events [default: all]
+    #[arg(short, long, global = true, value_delimiter = ',')]
+    types: Vec<Kind>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Append the events as JSON lines to a file or print them
+    Record {
+        /// The log file, stdout if not given
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        output: Option<String>,
+    },
+    /// Print a summary of each event of a recorded log
+    Replay {
+        /// The log file, stdin if not given
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: Option<String>,
+
+        /// Wait between the events as long as they were apart when recorded
+        #[arg(short, long)]
+        realtime: bool,
+
+        /// Speed up (or slow down) the waiting of --realtime by this factor
+        #[arg(short, long, default_value_t = 1.0, requires = "realtime")]
+        speed: f64,
+    },
+    /// Print a summary of each event as it happens
+    Tail,
+}
+
+/// The event types which can be subscribed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Kind {
+    Workspace,
+    Output,
+    Mode,
+    Window,
+    BarconfigUpdate,
+    Binding,
+    Shutdown,
+    Tick,
+    BarStateUpdate,
+    Input,
+}
+
+impl Kind {
+    fn event_type(self) -> EventType {
+        match self {
+            Kind::Workspace => EventType::Workspace,
+            Kind::Output => EventType::Output,
+            Kind::Mode => EventType::Mode,
+            Kind::Window => EventType::Window,
+            Kind::BarconfigUpdate => EventType::BarConfigUpdate,
+            Kind::Binding => EventType::Binding,
+            Kind::Shutdown => EventType::Shutdown,
+            Kind::Tick => EventType::Tick,
+            Kind::BarStateUpdate => EventType::BarStateUpdate,
+            Kind::Input => EventType::Input,
+        }
+    }
+
+    /// Returns the kind of `event`, `None` for event types this version does not know.
+    fn of(event: &Event) -> Option<Kind> {
+        Some(match event {
+            Event::Workspace(_) => Kind::Workspace,
+            Event::Output(_) => Kind::Output,
+            Event::Mode(_) => Kind::Mode,
+            Event::Window(_) => Kind::Window,
+            Event::BarConfigUpdate(_) => Kind::BarconfigUpdate,
+            Event::Binding(_) => Kind::Binding,
+            Event::Shutdown(_) => Kind::Shutdown,
+            Event::Tick(_) => Kind::Tick,
+            Event::BarStateUpdate(_) => Kind::BarStateUpdate,
+            Event::Input(_) => Kind::Input,
+            _ => return None,
+        })
+    }
+}
+
+/// A line of a log file.
+#[derive(Debug, Serialize, Deserialize)]
+struct Record {
+    /// The time the event was received in milliseconds since the Unix epoch.
+    time_ms: u64,
+    event: Event,
+}
+
+pub fn run(args: Args, _dry_run: bool) -> Fallible<()> {
+    let kinds = if args.types.is_empty() {
+        Kind::value_variants().to_vec()
+    } else {
+        args.types
+    };
+    match args.command {
+        Command::Record { output } => {
+            let mut writer: Box<dyn Write> = match output {
+                Some(path) => Box::new(OpenOptions::new().create(true).append(true).open(path)?),
+                None => Box::new(io::stdout()),
+            };
+            subscribe(&kinds, |event| {
+                let record = Record {
+                    time_ms: now_ms(),
+                    event,
+                };
+                writeln!(writer, "{}", serde_json::to_string(&record)?)?;
+                Ok(writer.flush()?)
+            })
+        }
+        Command::Replay {
+            file,
+            realtime,
+            speed,
+        } => {
+            let reader: Box<dyn BufRead> = match file {
+                Some(path) => Box::new(BufReader::new(File::open(path)?)),
+                None => Box::new(io::stdin().lock()),
+            };
+            replay(reader, &kinds, realtime.then_some(speed))
+        }
+        Command::Tail => subscribe(&kinds, |event| {
+            println!("{} {}", now_ms(), summary(&event));
+            Ok(())
+        }),
+    }
+}
+
+/// Returns the current time in milliseconds since the Unix epoch.
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis() as u64)
+}
+
+/// Passes every event of the given `kinds` to `handle` until sway exits or `handle` fails.
+fn subscribe<F: FnMut(Event) -> Fallible<()>>(kinds: &[Kind], mut handle: F) -> Fallible<()> {
+    let event_types: Vec<EventType> = kinds.iter().map(|kind| kind.event_type()).collect();
+    for event in EventStream::subscribe(&event_types)? {
+        match event {
+            StreamEvent::Event(event) => handle(*event)?,
+            StreamEvent::Reconnected => eprintln!("Reconnected to sway, events may be missing"),
+        }
+    }
+    Ok(())
+}
+
+/// Prints the summaries of the recorded events of the given `kinds` with their offsets from the
+/// first event, waiting between them for the recorded time divided by `speed` if given.
+fn replay(reader: Box<dyn BufRead>, kinds: &[Kind], speed: Option<f64>) -> Fallible<()> {
+    let mut start = None;
+    let mut previous = None;
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Record = serde_json::from_str(&line)?;
+        if !Kind::of(&record.event).is_some_and(|kind| kinds.contains(&kind)) {
+            continue;
+        }
+        let start = *start.get_or_insert(record.time_ms);
+        if let (Some(speed), Some(previous)) = (speed, previous) {
+            let delay = record.time_ms.saturating_sub(previous) as f64 / speed;
+            thread::sleep(Duration::from_secs_f64(delay / 1000.0));
+        }
+        previous = Some(record.time_ms);
+        let offset = record.time_ms.saturating_sub(start) as f64 / 1000.0;
+        println!("+{offset:.3}s {}", summary(&record.event));
+    }
+    Ok(())
+}
+
+/// Returns the name of the `change` of an event as sway reports it, like `focus`.
+fn change<T: Serialize>(change: &T) -> String {
+    serde_json::to_value(change)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_owned))
+        .unwrap_or_default()
+}
+
+/// Returns a line describing `event`.
+fn summary(event: &Event) -> String {
+    match event {
+        Event::Workspace(event) => {
+            let name = |workspace: &Option<swayipc::Node>| {
+                workspace
+                    .as_ref()
+                    .and_then(|workspace| workspace.name.clone())
+                    .unwrap_or_else(|| "-".to_owned())
+            };
+            format!(
+                "workspace {} {} (old {})",
+                change(&event.change),
+                name(&event.current),
+                name(&event.old)
+            )
+        }
+        Event::Output(event) => format!("output {}", change(&event.change)),
+        Event::Mode(event) => format!("mode {}", event.change),
+        Event::Window(event) => {
+            let window = &event.container;
+            format!(
+                "window {} con_id={} app={} title={:?}",
+                change(&event.change),
+                window.id,
+                tree::app(window).unwrap_or("-"),
+                window.name.as_deref().unwrap_or_default()
+            )
+        }
+        Event::BarConfigUpdate(bar) => format!("barconfig_update {}", bar.id),
+        Event::Binding(event) => format!(
+            "binding {} {}",
+            change(&event.change),
+            event.binding.command
+        ),
+        Event::Shutdown(event) => format!("shutdown {}", change(&event.change)),
+        Event::Tick(event) => format!("tick {}", event.payload),
+        Event::BarStateUpdate(event) => {
+            format!(
+                "bar_state_update {} visible={}",
+                event.id, event.visible_by_modifier
+            )
+        }
+        Event::Input(event) => {
+            format!("input {} {}", change(&event.change), event.input.identifier)
+        }
+        _ => "unknown".to_owned(),
+    }
+}
@@ -0,0 +1,93 @@
+//! Launching an application on a fresh workspace.
+//!
+//! The workspace is the first one of the focused output's mapped workspaces which does not exist
+//! yet (or, without a mapping, the lowest unused number). With `--wait` the command returns once
+//! the first window of the launched program appeared, so a keybinding can chain further commands.
+//! The window is recognized by its app id or class or by the name of its process, which has to be
+//! the name of the program.
+
+use std::{fs, path::Path, time::Duration};
+use swayipc::{Event, EventType, Node, WindowChange};
+use swaytools::{
+    connection,
+    error::{Error, Fallible},
+    exec_command, load_config, output_if_exists, tree, Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Wait until the first window of the launched program appeared
+    #[arg(short, long)]
+    wait: bool,
+
+    /// How many seconds to wait for the window
+    #[arg(short, long, default_value_t = 30, requires = "wait")]
+    timeout: u64,
+
+    /// The command to launch
+    #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    command: Vec<String>,
+}
+
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    let workspaces = sway.get_workspaces()?;
+    let output = workspaces
+        .iter()
+        .find(|ws| ws.focused)
+        .map(|ws| ws.output.to_owned())
+        .ok_or(Error::NoFocusedWorkspace)?;
+    let used = |num: &i32| workspaces.iter().any(|ws| ws.num == *num);
+
+    // A missing mapping maps nothing.
+    let mapping = load_config().unwrap_or_default();
+    let mapped = mapping.into_iter().find_map(|(o, workspaces)| {
+        (output_if_exists(o, &mut sway).as_deref() == Some(&output)).then_some(workspaces)
+    });
+    let num = match mapped {
+        Some(mut mapped) => {
+            mapped.sort();
+            mapped.into_iter().find(|num| !used(num))
+        }
+        None => (1..).find(|num| !used(num)),
+    }
+    .ok_or_else(|| Error::NotFound("empty workspace", format!("is mapped to output '{output}'")))?;
+
+    let commands = format!(
+        "workspace --no-auto-back-and-forth number {num}; {}",
+        exec_command(&args.command)
+    );
+    if !args.wait || dry_run {
+        return sway.run(commands);
+    }
+    // Subscribe before launching so that the window cannot be missed.
+    let timeout = Duration::from_secs(args.timeout);
+    let events = connection::connect_with_timeout(Some(timeout))?.subscribe([EventType::Window])?;
+    sway.run(commands)?;
+    let program = Path::new(&args.command[0])
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(&args.command[0]);
+    for event in events {
+        if let Event::Window(event) = event? {
+            if event.change == WindowChange::New && is_launched(&event.container, program) {
+                return Ok(());
+            }
+        }
+    }
+    Err(Error::Timeout)
+}
+
+/// Returns whether the `window` belongs to `program`, i.e., whether its app id, class, or process
+/// name is the name of the program.
+fn is_launched(window: &Node, program: &str) -> bool {
+    if tree::app(window).is_some_and(|app| app.eq_ignore_ascii_case(program)) {
+        return true;
+    }
+    // The kernel truncates process names to 15 bytes.
+    let truncated = program.get(..15).unwrap_or(program);
+    window.pid.is_some_and(|pid| {
+        fs::read_to_string(format!("/proc/{pid}/comm"))
+            .is_ok_and(|comm| comm.trim_end() == truncated)
+    })
+}
@@ -0,0 +1,136 @@
+//! Placing the focused window as a floating window within the usable area of its workspace.
+//!
+//! A tiled window is made floating first. Positions are computed from the window's size after
+//! that, so `swaytools float size 60% 70%` followed by `swaytools float center` centers the
+//! resized window. With `--output` the window is moved to the visible workspace of another output
+//! first, which may also be given relative to the primary output, e.g., `--output secondary`.
+
+use crate::geometry::{self, Length};
+use clap::{Subcommand, ValueEnum};
+use swaytools::{
+    error::{Error, Fallible},
+    output_if_exists, Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    action: Action,
+
+    /// The distance to keep from the edges of the usable area
+    #[arg(short, long, global = true, default_value = "0")]
+    margin: Length,
+
+    /// Move the window to this output first, e.g., 'HDMI-A-1', 'primary', or 'left-of-primary'
+    #[arg(short, long, global = true)]
+    output: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Action {
+    /// Center the window
+    Center,
+    /// Move the window to an edge or a corner
+    Snap {
+        #[arg(value_enum)]
+        position: Position,
+    },
+    /// Resize the window, percentages are relative to the usable area
+    Size {
+        /// The width, e.g., '60%' or '800px'
+        width: Length,
+        /// The height, e.g., '70%' or '600px'
+        height: Length,
+    },
+}
+
+/// The edges and corners a window can be snapped to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Position {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Position {
+    /// Returns whether the window is aligned to the start, the center, or the end of the area
+    /// horizontally and vertically as -1, 0, or 1.
+    fn alignment(self) -> (i32, i32) {
+        match self {
+            Position::TopLeft => (-1, -1),
+            Position::Top => (0, -1),
+            Position::TopRight => (1, -1),
+            Position::Left => (-1, 0),
+            Position::Right => (1, 0),
+            Position::BottomLeft => (-1, 1),
+            Position::Bottom => (0, 1),
+            Position::BottomRight => (1, 1),
+        }
+    }
+}
+
+/// Returns the coordinate of a window of `size` aligned to the start, center, or end of the area
+/// from `start` with `total` length.
+fn align(alignment: i32, start: i32, total: i32, size: i32, margin: i32) -> i32 {
+    match alignment {
+        -1 => start + margin,
+        0 => start + (total - size) / 2,
+        _ => start + total - size - margin,
+    }
+}
+
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    let mut focused = geometry::focused(&mut sway)?;
+    if !focused.floating {
+        sway.run(format!("[con_id={}] floating enable", focused.id))?;
+        focused = geometry::focused(&mut sway)?;
+    }
+    if let Some(output) = args.output {
+        let name =
+            output_if_exists(output.clone(), &mut sway).ok_or(Error::OutputNotFound(output))?;
+        sway.run(format!(
+            "[con_id={}] move container to output '{name}', focus",
+            focused.id
+        ))?;
+        focused = geometry::focused(&mut sway)?;
+    }
+    let (window, area) = (focused.window, focused.area);
+
+    let command = match args.action {
+        Action::Size { width, height } => format!(
+            "resize set {} px {} px",
+            width.resolve(area.width),
+            height.resolve(area.height)
+        ),
+        action => {
+            let (horizontal, vertical) = match action {
+                Action::Snap { position } => position.alignment(),
+                _ => (0, 0),
+            };
+            format!(
+                "move absolute position {} px {} px",
+                align(
+                    horizontal,
+                    area.x,
+                    area.width,
+                    window.width,
+                    args.margin.resolve(area.width)
+                ),
+                align(
+                    vertical,
+                    area.y,
+                    area.height,
+                    window.height,
+                    args.margin.resolve(area.height)
+                ),
+            )
+        }
+    };
+    sway.run(format!("[con_id={}] {command}", focused.id))
+}
@@ -0,0 +1,153 @@
+//! Restoring the floating windows of an output configuration when it reappears.
+//!
+//! sway piles all floating windows onto the remaining outputs when an output is disconnected and
+//! leaves them there when it returns. The geometries of the floating windows are recorded for the
+//! set of connected outputs on every window and workspace event, so a window moved with the mouse
+//! is recorded with the next focus change. Whenever the set of outputs changes to one with a
+//! recorded snapshot, its windows are moved and resized back.
+
+use swayipc::{Event, EventType, NodeType};
+use swaytools::{
+    error::Fallible,
+    events::{EventStream, StreamEvent},
+    output_identifier,
+    state::{FloatingGeometry, StateFile},
+    tree, Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {}
+
+/// The recorded snapshots and the output configuration they were last restored or recorded for.
+struct Snapshots {
+    state: StateFile,
+    /// The sorted identifiers of the connected outputs.
+    outputs: Option<String>,
+    /// Whether a snapshot was restored and no window or workspace event has followed yet.
+    restored: bool,
+}
+
+impl Snapshots {
+    /// Restores the snapshot of a changed output configuration, restoring it again on further
+    /// output events until another event shows that the outputs have settled, and records the
+    /// floating windows otherwise.
+    fn update(&mut self, output_event: bool, sway: &mut Connection) -> Fallible<()> {
+        let outputs = outputs_key(sway)?;
+        if self.outputs.as_ref() != Some(&outputs) || (output_event && self.restored) {
+            self.restore(&outputs, sway)?;
+            self.outputs = Some(outputs);
+            self.restored = true;
+            return Ok(());
+        }
+        if output_event {
+            return Ok(());
+        }
+        self.restored = false;
+        self.record(outputs, sway)
+    }
+
+    /// Moves and resizes the floating windows recorded for `outputs` which still exist.
+    fn restore(&self, outputs: &str, sway: &mut Connection) -> Fallible<()> {
+        let Some(snapshot) = self.state.load()?.floating.remove(outputs) else {
+            return Ok(());
+        };
+        let tree = sway.get_tree()?;
+        let commands: Vec<String> = snapshot
+            .iter()
+            .filter(|geometry| {
+                tree::find_by_id(&tree, geometry.id)
+                    .is_some_and(|location| location.node.node_type == NodeType::FloatingCon)
+            })
+            .map(|geometry| {
+                format!(
+                    "[con_id={}] resize set {} px {} px, move absolute position {} px {} px",
+                    geometry.id, geometry.width, geometry.height, geometry.x, geometry.y
+                )
+            })
+            .collect();
+        if commands.is_empty() {
+            return Ok(());
+        }
+        sway.run(commands.join("; "))
+    }
+
+    /// Replaces the snapshot of `outputs` by the current floating windows.
+    fn record(&self, outputs: String, sway: &mut Connection) -> Fallible<()> {
+        if sway.dry_run() {
+            return Ok(());
+        }
+        let tree = sway.get_tree()?;
+        let snapshot: Vec<FloatingGeometry> = tree::windows(&tree)
+            .into_iter()
+            .filter(|location| location.node.node_type == NodeType::FloatingCon)
+            .filter(|location| location.workspace_name() != Some("__i3_scratch"))
+            .map(|location| {
+                let rect = &location.node.rect;
+                FloatingGeometry {
+                    id: location.node.id,
+                    x: rect.x,
+                    y: rect.y,
+                    width: rect.width,
+                    height: rect.height,
+                }
+            })
+            .collect();
+        // Most events do not move floating windows, so the state file is only written on changes.
+        if self.state.load()?.floating.get(&outputs) == Some(&snapshot) {
+            return Ok(());
+        }
+        self.state.update(|state| {
+            state.floating.insert(outputs, snapshot);
+        })
+    }
+}
+
+/// Returns the sorted identifiers of the connected outputs joined by newlines.
+fn outputs_key(sway: &mut Connection) -> Fallible<String> {
+    let mut identifiers: Vec<String> = sway
+        .get_outputs()?
+        .iter()
+        .filter(|output| output.active)
+        .map(output_identifier)
+        .collect();
+    identifiers.sort();
+    Ok(identifiers.join("\n"))
+}
+
+/// Records and restores the floating windows until sway exits.
+pub fn run(_args: Args, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    let mut snapshots = Snapshots {
+        state: StateFile::open_default(),
+        outputs: None,
+        restored: false,
+    };
+    // Subscribe before the first snapshot so that no change is missed in between.
+    let events =
+        EventStream::subscribe(&[EventType::Window, EventType::Workspace, EventType::Output])?;
+    // The windows are where they belong when the daemon starts.
+    snapshots.outputs = Some(outputs_key(&mut sway)?);
+    snapshots.update(false, &mut sway)?;
+
+    for event in events {
+        let result = match event {
+            StreamEvent::Event(event) => {
+                let output_event = matches!(*event, Event::Output(_));
+                snapshots.update(output_event, &mut sway)
+            }
+            // Window ids do not survive a restart of sway.
+            StreamEvent::Reconnected => Connection::new(dry_run).and_then(|connection| {
+                sway = connection;
+                snapshots.outputs = None;
+                if dry_run {
+                    return Ok(());
+                }
+                snapshots.state.update(|state| state.floating.clear())
+            }),
+        };
+        if let Err(err) = result {
+            eprintln!("Cannot restore the floating windows: {err}");
+        }
+    }
+    Ok(())
+}
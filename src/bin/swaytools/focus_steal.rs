@@ -0,0 +1,121 @@
+//! Keeping new windows from taking the focus.
+//!
+//! A new window which is focused although it opened on another workspace than the focused one,
+//! or which belongs to a denied app, gives the focus back to the previously focused window (or
+//! workspace) and is marked urgent instead.
+
+use swayipc::{Event, EventType, WindowChange, WorkspaceChange};
+use swaytools::{
+    error::Fallible,
+    events::{EventStream, StreamEvent},
+    pattern::Pattern,
+    tree, Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Never let windows of this app id or class take the focus, even on the focused workspace;
+    /// may be a glob or a regex enclosed in slashes
+    #[arg(short, long, value_name = "APP")]
+    deny: Vec<String>,
+
+    /// Always let windows of this app id or class take the focus
+    #[arg(short, long, value_name = "APP")]
+    allow: Vec<String>,
+}
+
+/// The focus before a new window took it.
+struct Focus {
+    window: Option<i64>,
+    workspace: Option<String>,
+}
+
+/// Returns the currently focused window and workspace.
+fn current_focus(sway: &mut Connection) -> Fallible<Focus> {
+    let tree = sway.get_tree()?;
+    let focused = tree::find(&tree, |node| node.focused);
+    Ok(Focus {
+        window: focused
+            .filter(|location| location.is_window())
+            .map(|location| location.node.id),
+        workspace: focused
+            .and_then(|location| location.workspace_name())
+            .map(str::to_owned),
+    })
+}
+
+/// Returns whether `app` matches any of the `patterns`.
+fn matches(patterns: &[Pattern], app: Option<&str>) -> bool {
+    app.is_some_and(|app| patterns.iter().any(|pattern| pattern.matches(app)))
+}
+
+/// Gives the focus back whenever a new window took it until sway exits.
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let parse = |patterns: &[String]| -> Fallible<Vec<Pattern>> {
+        patterns.iter().map(|p| Pattern::parse(p)).collect()
+    };
+    let (deny, allow) = (parse(&args.deny)?, parse(&args.allow)?);
+    let mut sway = Connection::new(dry_run)?;
+    // Subscribe before looking at the focus so that no change is missed in between.
+    let events = EventStream::subscribe(&[EventType::Window, EventType::Workspace])?;
+    let mut focus = current_focus(&mut sway)?;
+
+    for event in events {
+        let event = match event {
+            StreamEvent::Event(event) => event,
+            StreamEvent::Reconnected => {
+                sway = Connection::new(dry_run)?;
+                focus = current_focus(&mut sway)?;
+                continue;
+            }
+        };
+        match *event {
+            Event::Workspace(event) if event.change == WorkspaceChange::Focus => {
+                focus.workspace = event.current.and_then(|workspace| workspace.name);
+                focus.window = None;
+            }
+            Event::Window(event) if event.change == WindowChange::Focus => {
+                focus.window = Some(event.container.id);
+            }
+            Event::Window(event) if event.change == WindowChange::New => {
+                if let Err(err) = prevent(&event.container, &focus, &deny, &allow, &mut sway) {
+                    eprintln!("Cannot restore the focus: {err}");
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+/// Focuses the `previous` window or workspace again and marks `window` urgent if it took the
+/// focus from another workspace or is denied.
+fn prevent(
+    window: &swayipc::Node,
+    previous: &Focus,
+    deny: &[Pattern],
+    allow: &[Pattern],
+    sway: &mut Connection,
+) -> Fallible<()> {
+    let app = tree::app(window);
+    if matches(allow, app) {
+        return Ok(());
+    }
+    let tree = sway.get_tree()?;
+    let Some(location) = tree::find_by_id(&tree, window.id) else {
+        return Ok(());
+    };
+    let other_workspace = previous
+        .workspace
+        .as_deref()
+        .is_some_and(|workspace| location.workspace_name() != Some(workspace));
+    if !location.node.focused || !(other_workspace || matches(deny, app)) {
+        return Ok(());
+    }
+    let restore = match (previous.window, &previous.workspace) {
+        (Some(id), _) => format!("[con_id={id}] focus"),
+        (None, Some(workspace)) => format!("workspace --no-auto-back-and-forth '{workspace}'"),
+        (None, None) => return Ok(()),
+    };
+    sway.run(format!("{restore}; [con_id={}] urgent enable", window.id))
+}
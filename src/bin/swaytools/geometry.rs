@@ -0,0 +1,78 @@
+//! Sizes relative to the usable area of a workspace, i.e., the output without bars and panels.
+
+use serde::Deserialize;
+use std::str::FromStr;
+use swayipc::{NodeType, Rect};
+use swaytools::{
+    error::{Error, Fallible},
+    tree, Connection,
+};
+
+/// A length in pixels or in percent of a reference length.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Length {
+    Pixels(i32),
+    Percent(f64),
+}
+
+impl FromStr for Length {
+    type Err = String;
+
+    /// Parses `60%`, `800px`, or `800`.
+    fn from_str(s: &str) -> Result<Length, String> {
+        let length = match s.strip_suffix('%') {
+            Some(percent) => percent.parse().ok().map(Length::Percent),
+            None => s
+                .strip_suffix("px")
+                .unwrap_or(s)
+                .parse()
+                .ok()
+                .map(Length::Pixels),
+        };
+        length.ok_or_else(|| format!("'{s}' is neither a percentage nor a number of pixels"))
+    }
+}
+
+impl TryFrom<String> for Length {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Length, String> {
+        s.parse()
+    }
+}
+
+impl Length {
+    /// Returns the length in pixels, where percentages are relative to `total`.
+    pub fn resolve(self, total: i32) -> i32 {
+        match self {
+            Length::Pixels(pixels) => pixels,
+            Length::Percent(percent) => (f64::from(total) * percent / 100.0).round() as i32,
+        }
+    }
+}
+
+/// The focused window and the workspace it is on.
+pub struct Focused {
+    pub id: i64,
+    pub floating: bool,
+    /// The window including its borders in absolute coordinates.
+    pub window: Rect,
+    /// The usable area of the workspace in absolute coordinates.
+    pub area: Rect,
+}
+
+/// Returns the focused window, failing if a workspace or output is focused.
+pub fn focused(sway: &mut Connection) -> Fallible<Focused> {
+    let tree = sway.get_tree()?;
+    let location = tree::find(&tree, |node| node.focused)
+        .filter(|location| location.is_window())
+        .ok_or_else(|| Error::WindowNotFound("focus".to_owned()))?;
+    let workspace = location.workspace.ok_or(Error::UnexpectedTree)?;
+    Ok(Focused {
+        id: location.node.id,
+        floating: location.node.node_type == NodeType::FloatingCon,
+        window: location.node.rect,
+        area: workspace.rect,
+    })
+}
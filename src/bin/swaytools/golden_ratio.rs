@@ -0,0 +1,195 @@
+//! Enlarging the focused tiled window to a ratio of its parent like golden-ratio.el.
+//!
+//! The focused window and each container above it get the ratio of their parent's width (in a
+//! horizontal split) or height (in a vertical split), so the focused window is large at every
+//! split level. Tabbed and stacked containers and parents with a single child are skipped.
+//!
+//! `watch` does this whenever the focus changes, but only on the workspaces enabled with
+//! `enable` or `toggle` (kept in the state file) unless `--all` is given.
+
+use clap::Subcommand;
+use swayipc::{Event, EventType, Node, NodeLayout, NodeType, WindowChange};
+use swaytools::{
+    error::{Error, Fallible},
+    events::{EventStream, StreamEvent},
+    state::StateFile,
+    tree, Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Command,
+
+    /// The share of its parent the focused window gets
+    #[arg(short, long, global = true, default_value_t = 0.618, value_parser = parse_ratio)]
+    ratio: f64,
+}
+
+/// Parses a `ratio` strictly between 0 and 1.
+fn parse_ratio(ratio: &str) -> Result<f64, String> {
+    let ratio = ratio.parse::<f64>().map_err(|err| err.to_string())?;
+    if ratio > 0.0 && ratio < 1.0 {
+        Ok(ratio)
+    } else {
+        Err("expected a number between 0 and 1".to_owned())
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Resize the focused window once
+    Apply,
+    /// Resize the focused window on the enabled workspaces whenever the focus changes
+    Watch {
+        /// Resize on all workspaces
+        #[arg(short, long)]
+        all: bool,
+    },
+    /// Enable resizing on the focused workspace
+    Enable,
+    /// Disable resizing on the focused workspace
+    Disable,
+    /// Enable or disable resizing on the focused workspace
+    Toggle,
+}
+
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    let state = StateFile::open_default();
+    let enable = match args.command {
+        Command::Apply => return resize(args.ratio, &mut sway),
+        Command::Watch { all } => return watch(args.ratio, all, &state, dry_run),
+        Command::Enable => Some(true),
+        Command::Disable => Some(false),
+        Command::Toggle => None,
+    };
+    let workspace = sway
+        .get_workspaces()?
+        .into_iter()
+        .find(|ws| ws.focused)
+        .ok_or(Error::NoFocusedWorkspace)?
+        .name;
+    if dry_run {
+        return Ok(());
+    }
+    state.update(|state| {
+        let enabled = state.golden_ratio.contains(&workspace);
+        if enable.unwrap_or(!enabled) {
+            if !enabled {
+                state.golden_ratio.push(workspace);
+            }
+        } else {
+            state.golden_ratio.retain(|ws| *ws != workspace);
+        }
+    })
+}
+
+/// Returns the `resize set` commands giving each container on `path` the `ratio` of its parent if
+/// that is split horizontally or vertically and has other children.
+fn resize_commands(path: &[&Node], ratio: f64) -> Vec<String> {
+    let mut commands = Vec::new();
+    // Resizing a container changes the size of its children, so the new size is carried down.
+    let Some(first) = path.first() else {
+        return commands;
+    };
+    let (mut width, mut height) = (first.rect.width, first.rect.height);
+    for pair in path.windows(2) {
+        let (parent, node) = (pair[0], pair[1]);
+        let (mut node_width, mut node_height) = (node.rect.width, node.rect.height);
+        if parent.nodes.len() > 1 {
+            let size = |total: i32| (f64::from(total) * ratio).round() as i32;
+            match parent.layout {
+                NodeLayout::SplitH => {
+                    node_width = size(width);
+                    node_height = height;
+                    commands.push(format!(
+                        "[con_id={}] resize set width {node_width} px",
+                        node.id
+                    ));
+                }
+                NodeLayout::SplitV => {
+                    node_height = size(height);
+                    node_width = width;
+                    commands.push(format!(
+                        "[con_id={}] resize set height {node_height} px",
+                        node.id
+                    ));
+                }
+                _ => (),
+            }
+        }
+        (width, height) = (node_width, node_height);
+    }
+    commands
+}
+
+/// Resizes the focused tiled window and its containers to `ratio` of their parents.
+fn resize(ratio: f64, sway: &mut Connection) -> Fallible<()> {
+    let tree = sway.get_tree()?;
+    let Some(focused) = tree::find(&tree, |node| node.focused).filter(|l| l.is_window()) else {
+        return Ok(());
+    };
+    let path = tree::path(&tree, focused.node.id);
+    // Only the tiled part below the workspace is resized.
+    let Some(start) = path
+        .iter()
+        .position(|node| node.node_type == NodeType::Workspace)
+    else {
+        return Ok(());
+    };
+    if path.iter().any(|node| {
+        node.node_type == NodeType::FloatingCon || node.fullscreen_mode.is_some_and(|mode| mode > 0)
+    }) {
+        return Ok(());
+    }
+    let commands = resize_commands(&path[start..], ratio);
+    if commands.is_empty() {
+        return Ok(());
+    }
+    sway.run(commands.join("; "))
+}
+
+/// Resizes the focused window whenever the focus changes on an enabled workspace until sway
+/// exits.
+fn watch(ratio: f64, all: bool, state: &StateFile, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    for event in EventStream::subscribe(&[EventType::Window])? {
+        let result = match event {
+            StreamEvent::Event(event) => match *event {
+                Event::Window(event) if event.change == WindowChange::Focus => {
+                    enabled(event.container.id, all, state, &mut sway).and_then(|enabled| {
+                        if enabled {
+                            resize(ratio, &mut sway)
+                        } else {
+                            Ok(())
+                        }
+                    })
+                }
+                _ => continue,
+            },
+            StreamEvent::Reconnected => Connection::new(dry_run).map(|connection| {
+                sway = connection;
+            }),
+        };
+        if let Err(err) = result {
+            eprintln!("Cannot resize the focused window: {err}");
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether resizing is enabled on the workspace of the window `id`.
+fn enabled(id: i64, all: bool, state: &StateFile, sway: &mut Connection) -> Fallible<bool> {
+    if all {
+        return Ok(true);
+    }
+    let enabled = state.load()?.golden_ratio;
+    if enabled.is_empty() {
+        return Ok(false);
+    }
+    let tree = sway.get_tree()?;
+    Ok(tree::find_by_id(&tree, id)
+        .and_then(|location| location.workspace_name())
+        .is_some_and(|workspace| enabled.iter().any(|ws| ws == workspace)))
+}
@@ -0,0 +1,97 @@
+//! Keeping the screen awake while a window is fullscreen.
+//!
+//! sway's own `inhibit_idle fullscreen` has to be set up per window in the config. Instead this
+//! daemon inhibits idle for every fullscreen window (or only those of the given apps) and releases
+//! the inhibitor once the window leaves fullscreen. A closed window takes its inhibitor with it.
+
+use crate::rules;
+use regex::Regex;
+use std::collections::HashSet;
+use swayipc::{Event, EventType, Node, WindowChange};
+use swaytools::{
+    error::Fallible,
+    events::{EventStream, StreamEvent},
+    tree, Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Only inhibit idle for windows whose app id or class matches this regex; may be repeated
+    #[arg(short, long, value_name = "REGEX")]
+    app: Vec<String>,
+}
+
+/// The inhibitors set by the daemon.
+struct Inhibitor {
+    apps: Vec<Regex>,
+    inhibited: HashSet<i64>,
+}
+
+impl Inhibitor {
+    /// Returns whether idle is inhibited for `window` while it is fullscreen.
+    fn is_allowed(&self, window: &Node) -> bool {
+        self.apps.is_empty()
+            || tree::app(window).is_some_and(|app| self.apps.iter().any(|re| re.is_match(app)))
+    }
+
+    /// Inhibits idle for `window` if it is fullscreen and releases it otherwise.
+    fn update(&mut self, window: &Node, sway: &mut Connection) -> Fallible<()> {
+        let fullscreen = window.fullscreen_mode.is_some_and(|mode| mode != 0);
+        let inhibited = self.inhibited.contains(&window.id);
+        if fullscreen && !inhibited && self.is_allowed(window) {
+            sway.run(format!("[con_id={}] inhibit_idle open", window.id))?;
+            self.inhibited.insert(window.id);
+        } else if !fullscreen && inhibited {
+            sway.run(format!("[con_id={}] inhibit_idle none", window.id))?;
+            self.inhibited.remove(&window.id);
+        }
+        Ok(())
+    }
+}
+
+/// Inhibits idle for fullscreen windows until sway exits.
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let apps = args
+        .app
+        .iter()
+        .map(|app| rules::regex(app))
+        .collect::<Fallible<_>>()?;
+    let mut inhibitor = Inhibitor {
+        apps,
+        inhibited: HashSet::new(),
+    };
+    let mut sway = Connection::new(dry_run)?;
+    // Subscribe before looking at the current windows so that no change is missed in between.
+    let events = EventStream::subscribe(&[EventType::Window])?;
+    for window in tree::windows(&sway.get_tree()?) {
+        inhibitor.update(window.node, &mut sway)?;
+    }
+
+    for event in events {
+        let result = match event {
+            StreamEvent::Event(event) => match *event {
+                Event::Window(event) if event.change == WindowChange::Close => {
+                    inhibitor.inhibited.remove(&event.container.id);
+                    Ok(())
+                }
+                Event::Window(event) if event.change == WindowChange::FullscreenMode => {
+                    inhibitor.update(&event.container, &mut sway)
+                }
+                _ => continue,
+            },
+            // sway may have been restarted, so all windows and their inhibitors are new.
+            StreamEvent::Reconnected => Connection::new(dry_run).and_then(|connection| {
+                sway = connection;
+                inhibitor.inhibited.clear();
+                for window in tree::windows(&sway.get_tree()?) {
+                    inhibitor.update(window.node, &mut sway)?;
+                }
+                Ok(())
+            }),
+        };
+        if let Err(err) = result {
+            eprintln!("Cannot update the idle inhibitors: {err}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,181 @@
+//! Applying libinput and keyboard settings to input devices as they are added.
+//!
+//! The profiles file lists profiles in order, later matching profiles override earlier ones:
+//!
+//! ```toml
+//! [[profile]]
+//! identifier = "*:*:*Touchpad*"
+//! [profile.settings]
+//! tap = true
+//! natural_scroll = true
+//!
+//! # Slower pointers while docked
+//! [[profile]]
+//! type = "pointer"
+//! outputs = ["Dell Inc. DELL U2415 7MT0186417KS"]
+//! [profile.settings]
+//! pointer_accel = -0.3
+//!
+//! [[profile]]
+//! type = "keyboard"
+//! [profile.settings]
+//! repeat_delay = 250
+//! repeat_rate = 40
+//! ```
+//!
+//! `identifier` is a pattern like the include entries of the keyboard tool and matched against
+//! the identifier and the name of a device. A profile with `outputs` only applies while all of
+//! these outputs (names or `make model serial`) are connected. Each setting becomes an `input`
+//! command, where `true` and `false` are written as `enabled` and `disabled`.
+
+use clap::ValueHint;
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs};
+use swayipc::{Event, EventType, Input, InputChange, Output};
+use swaytools::{
+    env,
+    error::Fallible,
+    events::{EventStream, StreamEvent},
+    output_identifier,
+    pattern::Pattern,
+    Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The TOML file with the profiles [default: $XDG_CONFIG_HOME/swaytools/inputs.toml]
+    #[arg(short, long, value_hint = ValueHint::FilePath)]
+    profiles: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ProfilesFile {
+    #[serde(default, rename = "profile")]
+    profiles: Vec<ProfileEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileEntry {
+    identifier: Option<String>,
+    #[serde(rename = "type")]
+    input_type: Option<String>,
+    #[serde(default)]
+    outputs: Vec<String>,
+    settings: BTreeMap<String, toml::Value>,
+}
+
+/// Settings for the devices matching the identifier and type while the outputs are connected.
+struct Profile {
+    identifier: Option<Pattern>,
+    input_type: Option<String>,
+    outputs: Vec<String>,
+    settings: Vec<(String, String)>,
+}
+
+impl Profile {
+    /// Returns whether the profile applies to `input` with the connected `outputs`.
+    fn matches(&self, input: &Input, outputs: &[Output]) -> bool {
+        self.identifier.as_ref().is_none_or(|pattern| {
+            pattern.matches(&input.identifier) || pattern.matches(&input.name)
+        }) && self
+            .input_type
+            .as_ref()
+            .is_none_or(|input_type| *input_type == input.input_type)
+            && self.outputs.iter().all(|output| {
+                outputs
+                    .iter()
+                    .any(|o| o.name == *output || output_identifier(o) == *output)
+            })
+    }
+}
+
+/// Loads the profiles from the TOML file at `path`.
+fn load(path: &str) -> Fallible<Vec<Profile>> {
+    let file: ProfilesFile = toml::from_str(&fs::read_to_string(path)?)?;
+    let mut profiles = Vec::new();
+    for entry in file.profiles {
+        let settings = entry
+            .settings
+            .into_iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    toml::Value::Boolean(true) => "enabled".to_owned(),
+                    toml::Value::Boolean(false) => "disabled".to_owned(),
+                    toml::Value::String(value) => value,
+                    value => value.to_string(),
+                };
+                (name, value)
+            })
+            .collect();
+        profiles.push(Profile {
+            identifier: entry
+                .identifier
+                .as_deref()
+                .map(Pattern::parse)
+                .transpose()?,
+            input_type: entry.input_type,
+            outputs: entry.outputs,
+            settings,
+        });
+    }
+    Ok(profiles)
+}
+
+/// Applies the settings of all matching profiles to `inputs`.
+fn apply(profiles: &[Profile], inputs: &[Input], sway: &mut Connection) -> Fallible<()> {
+    let outputs = sway.get_outputs()?;
+    let mut commands = Vec::new();
+    for input in inputs {
+        for profile in profiles.iter().filter(|p| p.matches(input, &outputs)) {
+            for (name, value) in &profile.settings {
+                commands.push(format!("input '{}' {name} {value}", input.identifier));
+            }
+        }
+    }
+    if commands.is_empty() {
+        return Ok(());
+    }
+    sway.run(commands.join("; "))
+}
+
+/// Applies the profiles to all devices now, to every added device, and to all devices whenever
+/// the outputs change.
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let path = args.profiles.unwrap_or_else(|| {
+        env::config_file("inputs.toml")
+            .to_string_lossy()
+            .into_owned()
+    });
+    let profiles = load(&path)?;
+    let mut sway = Connection::new(dry_run)?;
+    // Subscribe before applying so that no device is missed in between.
+    let events = EventStream::subscribe(&[EventType::Input, EventType::Output])?;
+    let inputs = sway.get_inputs()?;
+    apply(&profiles, &inputs, &mut sway)?;
+
+    for event in events {
+        let result = match event {
+            StreamEvent::Event(event) => match *event {
+                Event::Input(event) if matches!(event.change, InputChange::Added) => {
+                    apply(&profiles, &[event.input], &mut sway)
+                }
+                Event::Output(_) => sway
+                    .get_inputs()
+                    .map_err(Into::into)
+                    .and_then(|inputs| apply(&profiles, &inputs, &mut sway)),
+                _ => continue,
+            },
+            // sway may have been restarted with the settings of its config.
+            StreamEvent::Reconnected => Connection::new(dry_run).and_then(|connection| {
+                sway = connection;
+                let inputs = sway.get_inputs()?;
+                apply(&profiles, &inputs, &mut sway)
+            }),
+        };
+        if let Err(err) = result {
+            eprintln!("Cannot apply the input profiles: {err}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,259 @@
+//! Printing the layout tree or selected nodes of it for debugging.
+//!
+//! Each node is printed with the chosen fields, indented by its depth, or one matching node per
+//! line with `--select`. A selector `FIELD=PATTERN` matches nodes whose field matches the pattern
+//! (a glob or a regex enclosed in slashes), several selectors must all match:
+//!
+//! ```sh
+//! swaytools tree --select 'app_id=firefox' --fields con_id,workspace,rect
+//! swaytools tree --select 'type=floating_con' --select 'workspace=/^[1-3]$/'
+//! ```
+//!
+//! Colors are used if stdout is a terminal and `$NO_COLOR` is not set.
+
+use serde::Serialize;
+use std::io::{self, IsTerminal};
+use swayipc::{EventType, Node, NodeType};
+use swaytools::{
+    env,
+    error::{Error, Fallible},
+    events::{EventStream, StreamEvent},
+    pattern::Pattern,
+    tree::{self, Location},
+    Connection,
+};
+
+/// The fields nodes can be selected by and printed with.
+const FIELDS: &[&str] = &[
+    "con_id",
+    "type",
+    "layout",
+    "app",
+    "app_id",
+    "class",
+    "name",
+    "workspace",
+    "output",
+    "rect",
+    "pid",
+    "marks",
+    "focused",
+    "floating",
+    "fullscreen",
+    "sticky",
+    "urgent",
+];
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Print only the nodes with FIELD matching PATTERN, a glob or a regex enclosed in slashes
+    #[arg(short, long, value_name = "FIELD=PATTERN")]
+    select: Vec<String>,
+
+    /// The comma-separated fields to print of each node
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        default_value = "con_id,type,layout,app,name"
+    )]
+    fields: Vec<String>,
+
+    /// Print the tree again whenever a window, workspace, or output changes
+    #[arg(short, long)]
+    watch: bool,
+}
+
+/// A selector matching nodes whose `field` matches `pattern`.
+struct Selector {
+    field: String,
+    pattern: Pattern,
+}
+
+impl Selector {
+    fn parse(selector: &str) -> Fallible<Selector> {
+        let invalid =
+            |reason: &str| Error::Invalid("selector", selector.to_owned(), reason.to_owned());
+        let (field, pattern) = selector
+            .split_once('=')
+            .ok_or_else(|| invalid("expected FIELD=PATTERN"))?;
+        if !FIELDS.contains(&field) {
+            return Err(invalid(&format!("known fields are {}", FIELDS.join(", "))));
+        }
+        Ok(Selector {
+            field: field.to_owned(),
+            pattern: Pattern::parse(pattern)?,
+        })
+    }
+
+    fn matches(&self, location: &Location) -> bool {
+        field(location, &self.field).is_some_and(|value| self.pattern.matches(&value))
+    }
+}
+
+/// Returns the name sway uses for `value` in its JSON replies, like `floating_con`.
+fn serialized<T: Serialize>(value: &T) -> Option<String> {
+    match serde_json::to_value(value).ok()? {
+        serde_json::Value::String(value) => Some(value),
+        _ => None,
+    }
+}
+
+/// Returns the value of the field called `name` of the node at `location`, `None` if the node has
+/// no such value.
+fn field(location: &Location, name: &str) -> Option<String> {
+    let node = location.node;
+    let flag = |value: bool| Some(value.to_string());
+    match name {
+        "con_id" => Some(node.id.to_string()),
+        "type" => serialized(&node.node_type),
+        "layout" => serialized(&node.layout),
+        "app" => tree::app(node).map(str::to_owned),
+        "app_id" => node.app_id.clone(),
+        "class" => node
+            .window_properties
+            .as_ref()
+            .and_then(|properties| properties.class.clone()),
+        "name" => node.name.clone(),
+        "workspace" => location.workspace_name().map(str::to_owned),
+        "output" => location.output_name().map(str::to_owned),
+        "rect" => Some(format!(
+            "{},{} {}x{}",
+            node.rect.x, node.rect.y, node.rect.width, node.rect.height
+        )),
+        "pid" => node.pid.map(|pid| pid.to_string()),
+        "marks" => (!node.marks.is_empty()).then(|| node.marks.join(",")),
+        "focused" => flag(node.focused),
+        "floating" => flag(node.node_type == NodeType::FloatingCon),
+        "fullscreen" => flag(node.fullscreen_mode.is_some_and(|mode| mode > 0)),
+        "sticky" => flag(node.sticky),
+        "urgent" => flag(node.urgent),
+        _ => None,
+    }
+}
+
+/// Formats the nodes with ANSI colors or as plain text.
+struct Printer {
+    fields: Vec<String>,
+    color: bool,
+}
+
+impl Printer {
+    /// Wraps `text` in the ANSI escape sequence `style` if colors are enabled.
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{style}m{text}\x1b[0m")
+        } else {
+            text.to_owned()
+        }
+    }
+
+    /// Returns the line of the node at `location` with all fields it has values for.
+    fn line(&self, location: &Location) -> String {
+        let node = location.node;
+        let value_style = match node.node_type {
+            NodeType::Root | NodeType::Output => "1;34",
+            NodeType::Workspace => "1;36",
+            _ if node.urgent => "1;31",
+            _ if node.focused => "1;32",
+            _ => "0",
+        };
+        self.fields
+            .iter()
+            .filter_map(|name| {
+                let value = field(location, name)?;
+                Some(format!(
+                    "{}{}",
+                    self.paint("2", &format!("{name}=")),
+                    self.paint(value_style, &value)
+                ))
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Prints `node` and its children indented by their depth.
+    fn print_tree(&self, location: Location, depth: usize) {
+        println!("{}{}", "  ".repeat(depth), self.line(&location));
+        let node = location.node;
+        for child in node.nodes.iter().chain(node.floating_nodes.iter()) {
+            let mut child_location = Location {
+                node: child,
+                ..location
+            };
+            match child.node_type {
+                NodeType::Output => child_location.output = Some(child),
+                NodeType::Workspace => child_location.workspace = Some(child),
+                _ => (),
+            }
+            self.print_tree(child_location, depth + 1);
+        }
+    }
+
+    /// Prints the whole `tree` or the nodes matching all `selectors` if there are any.
+    fn print(&self, tree: &Node, selectors: &[Selector]) {
+        if selectors.is_empty() {
+            let root = Location {
+                node: tree,
+                output: None,
+                workspace: None,
+            };
+            self.print_tree(root, 0);
+            return;
+        }
+        for location in tree::walk(tree) {
+            if selectors.iter().all(|selector| selector.matches(&location)) {
+                println!("{}", self.line(&location));
+            }
+        }
+    }
+}
+
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let selectors = args
+        .select
+        .iter()
+        .map(|selector| Selector::parse(selector))
+        .collect::<Fallible<Vec<_>>>()?;
+    if let Some(name) = args
+        .fields
+        .iter()
+        .find(|name| !FIELDS.contains(&name.as_str()))
+    {
+        return Err(Error::UnexpectedArgument(name.clone()));
+    }
+    let printer = Printer {
+        fields: args.fields,
+        color: io::stdout().is_terminal() && env::var("NO_COLOR").is_none(),
+    };
+    let mut sway = Connection::new(dry_run)?;
+    if !args.watch {
+        printer.print(&sway.get_tree()?, &selectors);
+        return Ok(());
+    }
+
+    // Subscribe before the first print so that no change is missed in between.
+    let events =
+        EventStream::subscribe(&[EventType::Window, EventType::Workspace, EventType::Output])?;
+    printer.print(&sway.get_tree()?, &selectors);
+    for event in events {
+        if let StreamEvent::Reconnected = event {
+            sway = Connection::new(dry_run)?;
+        }
+        let tree = match sway.get_tree() {
+            Ok(tree) => tree,
+            Err(err) => {
+                eprintln!("Cannot get the tree: {err}");
+                continue;
+            }
+        };
+        // Clear the screen and move the cursor to the top left corner.
+        if printer.color {
+            print!("\x1b[2J\x1b[H");
+        } else {
+            println!();
+        }
+        printer.print(&tree, &selectors);
+    }
+    Ok(())
+}
@@ -0,0 +1,97 @@
+//! Toggling the container of the focused window between tabbed and split layouts.
+//!
+//! sway's `layout toggle` forgets the sizes of the children when going from tabbed back to a
+//! split layout. This command remembers the split layout and the share of each child in the state
+//! file when the container is made tabbed, and sets them again when it is split. The order of the
+//! children is kept by sway and the focused window is focused again afterwards.
+
+use swayipc::{NodeLayout, NodeType};
+use swaytools::{
+    error::{Error, Fallible},
+    state::{SavedSplit, StateFile},
+    tree, Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The split layout used for a container made tabbed otherwise
+    #[arg(short, long, value_parser = ["splith", "splitv"], default_value = "splith")]
+    split: String,
+}
+
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    let state = StateFile::open_default();
+    let tree = sway.get_tree()?;
+    let focused = tree::find(&tree, |node| node.focused)
+        .ok_or_else(|| Error::WindowNotFound("focus".to_owned()))?;
+    if focused.node.node_type != NodeType::Con {
+        return Ok(());
+    }
+    let path = tree::path(&tree, focused.node.id);
+    let Some(container) = path.len().checked_sub(2).map(|index| path[index]) else {
+        return Ok(());
+    };
+    // The layout command changes the layout of the parent of the container it is run for.
+    let focus = format!("[con_id={}] focus", focused.node.id);
+    let layout = |layout: &str| format!("[con_id={}] layout {layout}", focused.node.id);
+
+    let split = match container.layout {
+        NodeLayout::SplitH => "splith",
+        NodeLayout::SplitV => "splitv",
+        NodeLayout::Tabbed | NodeLayout::Stacked => {
+            let mut saved = state.load()?.split_layouts.remove(&container.id);
+            let children: Vec<i64> = container.nodes.iter().map(|node| node.id).collect();
+            // Sizes only fit if the children are still the same.
+            saved.take_if(|saved| {
+                saved.sizes.len() != children.len()
+                    || saved.sizes.iter().any(|(id, _)| !children.contains(id))
+            });
+            let split = saved
+                .as_ref()
+                .map_or(args.split.as_str(), |saved| saved.layout.as_str());
+            let dimension = if split == "splitv" { "height" } else { "width" };
+            let mut commands = vec![layout(split)];
+            commands.extend(
+                saved
+                    .iter()
+                    .flat_map(|saved| &saved.sizes)
+                    .map(|(id, share)| {
+                        format!(
+                            "[con_id={id}] resize set {dimension} {} ppt",
+                            (share * 100.0).round()
+                        )
+                    }),
+            );
+            commands.push(focus);
+            sway.run(commands.join("; "))?;
+            if !sway.dry_run() {
+                state.update(|state| {
+                    state.split_layouts.remove(&container.id);
+                })?;
+            }
+            return Ok(());
+        }
+        _ => return Ok(()),
+    };
+
+    sway.run(format!("{}; {focus}", layout("tabbed")))?;
+    if sway.dry_run() {
+        return Ok(());
+    }
+    let saved = SavedSplit {
+        layout: split.to_owned(),
+        sizes: container
+            .nodes
+            .iter()
+            .map(|node| (node.id, node.percent.unwrap_or_default()))
+            .collect(),
+    };
+    state.update(|state| {
+        // Forget the containers which are gone.
+        state
+            .split_layouts
+            .retain(|&id, _| tree::find_by_id(&tree, id).is_some());
+        state.split_layouts.insert(container.id, saved);
+    })
+}
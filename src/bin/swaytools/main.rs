@@ -0,0 +1,170 @@
+mod autostart;
+mod balance;
+mod bars;
+mod bus;
+mod clamshell;
+mod dim;
+mod events;
+mod exec_new;
+mod float;
+mod float_snapshots;
+mod focus_steal;
+mod geometry;
+mod golden_ratio;
+mod idle;
+mod inputs;
+mod inspect;
+mod layout_toggle;
+mod marks;
+mod minimize;
+mod mode;
+mod monocle;
+mod mru;
+mod outputs;
+mod overview;
+mod picker;
+mod raise;
+mod resize;
+mod rules;
+mod session;
+mod set_primary;
+mod stash;
+mod sticky;
+mod urgent_window;
+mod wallpaper;
+
+use clap::{Parser, Subcommand};
+use swaytools::{
+    env,
+    error::{ErrorFormat, Fallible},
+};
+
+/// Additional tools for sway.
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+
+    /// Only show commands instead of executing them [env: SWAYTOOLS_DRY_RUN].
+    #[arg(short = 'n', long, global = true)]
+    dry_run: bool,
+
+    /// How errors are reported [env: SWAYTOOLS_ERRORS].
+    #[arg(long, value_enum, global = true)]
+    errors: Option<ErrorFormat>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Apply output profiles matching the connected outputs
+    Outputs(outputs::Args),
+    /// Place and size new windows according to rules
+    Rules(rules::Args),
+    /// Switch between the most recently used windows
+    Mru(mru::Args),
+    /// Set marks on windows and jump to them
+    Marks(marks::Args),
+    /// Keep the screen awake while a window is fullscreen
+    Idle(idle::Args),
+    /// Center, snap, or resize the focused window as a floating window
+    Float(float::Args),
+    /// Resize the focused window by presets and relative steps
+    Resize(resize::Args),
+    /// Dim all windows but the focused one
+    Dim(dim::Args),
+    /// Print the current binding mode for waybar
+    Mode(mode::Args),
+    /// Save the windows of all workspaces and restore them later
+    Session(session::Args),
+    /// Launch applications on workspaces when they are created empty
+    Autostart(autostart::Args),
+    /// Apply input settings to devices as they are added
+    Inputs(inputs::Args),
+    /// Keep new windows from taking the focus and mark them urgent instead
+    FocusSteal(focus_steal::Args),
+    /// Launch an application on the next empty workspace of the focused output
+    ExecNew(exec_new::Args),
+    /// Keep chosen floating windows visible on every workspace of their output
+    Sticky(sticky::Args),
+    /// Print a JSON overview of all workspaces and windows, optionally with thumbnails
+    Overview(overview::Args),
+    /// Switch the background of each output to the wallpaper of its visible workspace
+    Wallpaper(wallpaper::Args),
+    /// Print the layout tree or the nodes matching selectors
+    Tree(inspect::Args),
+    /// Focus a window of an application or launch it if it has none
+    Raise(raise::Args),
+    /// Record sway events to a log file and print them for analysis
+    Events(events::Args),
+    /// Move the workspaces of the internal output away while it is disabled and back afterwards
+    Clamshell(clamshell::Args),
+    /// Restore the floating windows of an output configuration when it reappears
+    FloatSnapshots(float_snapshots::Args),
+    /// Export the workspaces, the focused window, and the keyboard layout on D-Bus
+    Dbus(bus::Args),
+    /// Hide all windows in the scratchpad and put them back where they were
+    Stash(stash::Args),
+    /// Minimize windows to the scratchpad and restore them to their workspaces
+    Minimize(minimize::Args),
+    /// Enlarge the focused tiled window to a ratio of its parent
+    GoldenRatio(golden_ratio::Args),
+    /// Give the tiled containers of the focused workspace equal sizes
+    Balance(balance::Args),
+    /// Toggle the container of the focused window between tabbed and split keeping the sizes
+    LayoutToggle(layout_toggle::Args),
+    /// Show only the focused window on its workspace or bring the others back
+    Monocle(monocle::Args),
+    /// Hide and show the bars depending on fullscreen windows, workspaces, and idle time
+    Bars(bars::Args),
+    /// Focus urgent windows and revisit the ones whose urgency was dismissed
+    UrgentWindow(urgent_window::Args),
+    /// Set or print the primary output other commands can target as `primary`
+    SetPrimary(set_primary::Args),
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let errors = ErrorFormat::resolve(cli.errors);
+    if let Err(err) = run(cli) {
+        errors.exit(&err);
+    }
+}
+
+fn run(cli: Cli) -> Fallible<()> {
+    let dry_run = env::dry_run(cli.dry_run);
+    match cli.command {
+        Commands::Outputs(args) => outputs::run(args, dry_run),
+        Commands::Rules(args) => rules::run(args, dry_run),
+        Commands::Mru(args) => mru::run(args, dry_run),
+        Commands::Marks(args) => marks::run(args, dry_run),
+        Commands::Idle(args) => idle::run(args, dry_run),
+        Commands::Float(args) => float::run(args, dry_run),
+        Commands::Resize(args) => resize::run(args, dry_run),
+        Commands::Dim(args) => dim::run(args, dry_run),
+        Commands::Mode(args) => mode::run(args, dry_run),
+        Commands::Session(args) => session::run(args, dry_run),
+        Commands::Autostart(args) => autostart::run(args, dry_run),
+        Commands::Inputs(args) => inputs::run(args, dry_run),
+        Commands::FocusSteal(args) => focus_steal::run(args, dry_run),
+        Commands::ExecNew(args) => exec_new::run(args, dry_run),
+        Commands::Sticky(args) => sticky::run(args, dry_run),
+        Commands::Overview(args) => overview::run(args, dry_run),
+        Commands::Wallpaper(args) => wallpaper::run(args, dry_run),
+        Commands::Tree(args) => inspect::run(args, dry_run),
+        Commands::Raise(args) => raise::run(args, dry_run),
+        Commands::Events(args) => events::run(args, dry_run),
+        Commands::Clamshell(args) => clamshell::run(args, dry_run),
+        Commands::FloatSnapshots(args) => float_snapshots::run(args, dry_run),
+        Commands::Dbus(args) => bus::run(args, dry_run),
+        Commands::Stash(args) => stash::run(args, dry_run),
+        Commands::Minimize(args) => minimize::run(args, dry_run),
+        Commands::GoldenRatio(args) => golden_ratio::run(args, dry_run),
+        Commands::Balance(args) => balance::run(args, dry_run),
+        Commands::LayoutToggle(args) => layout_toggle::run(args, dry_run),
+        Commands::Monocle(args) => monocle::run(args, dry_run),
+        Commands::Bars(args) => bars::run(args, dry_run),
+        Commands::UrgentWindow(args) => urgent_window::run(args, dry_run),
+        Commands::SetPrimary(args) => set_primary::run(args, dry_run),
+    }
+}
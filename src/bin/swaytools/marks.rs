@@ -0,0 +1,103 @@
+//! Setting marks on windows and jumping to them.
+//!
+//! Marks are looked up in the whole tree, so marked floating windows and windows in the
+//! scratchpad are found as well. A window in the scratchpad is shown instead of focused.
+
+use crate::picker::{self, DEFAULT_PICKER};
+use clap::Subcommand;
+use swaytools::{
+    error::{Error, Fallible},
+    tree::{self, Location},
+    Connection,
+};
+
+/// The name of the workspace sway keeps the scratchpad windows on.
+const SCRATCHPAD: &str = "__i3_scratch";
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Add a mark to the focused window, moving it from any other window
+    Set { mark: String },
+    /// Focus the marked window, switching workspace and output as needed
+    Goto {
+        /// The mark to jump to, picked with --picker if omitted
+        mark: Option<String>,
+        /// The dmenu-compatible command to pick a mark with
+        #[arg(short, long, value_name = "COMMAND", default_value = DEFAULT_PICKER)]
+        picker: String,
+    },
+    /// List the marks and the windows they are set on
+    List,
+    /// Remove the mark or all marks
+    Clear { mark: Option<String> },
+}
+
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    match args.command {
+        Command::Set { mark } => sway.run(format!("mark --add '{mark}'")),
+        Command::Goto { mark, picker } => goto(mark, &picker, &mut sway),
+        Command::List => {
+            for (mark, location) in marks(&sway.get_tree()?) {
+                println!("{mark}\t{}", picker::describe(&location));
+            }
+            Ok(())
+        }
+        Command::Clear { mark: Some(mark) } => sway.run(format!("unmark '{mark}'")),
+        Command::Clear { mark: None } => sway.mark_remove_all(),
+    }
+}
+
+/// Returns all marks in tree order together with the containers they are set on.
+fn marks(tree: &swayipc::Node) -> Vec<(&str, Location<'_>)> {
+    tree::walk(tree)
+        .into_iter()
+        .flat_map(|location| {
+            location
+                .node
+                .marks
+                .iter()
+                .map(move |mark| (mark.as_str(), location))
+        })
+        .collect()
+}
+
+/// Focuses the container with `mark` or the one picked with `picker` if `mark` is `None`.
+fn goto(mark: Option<String>, picker: &str, sway: &mut Connection) -> Fallible<()> {
+    let tree = sway.get_tree()?;
+    let marks = marks(&tree);
+    let found = match mark {
+        Some(mark) => marks
+            .iter()
+            .find(|(m, _)| *m == mark)
+            .ok_or_else(|| Error::WindowNotFound(format!("mark '{mark}'")))?,
+        None => {
+            let entries: Vec<String> = marks
+                .iter()
+                .map(|(mark, location)| format!("{mark}: {}", picker::describe(location)))
+                .collect();
+            match picker::pick(picker, &entries)? {
+                Some(index) => &marks[index],
+                None => return Ok(()),
+            }
+        }
+    };
+    let (_, location) = found;
+    focus(location, sway)
+}
+
+/// Focuses the container at `location`, showing it instead if it is in the scratchpad.
+pub fn focus(location: &Location, sway: &mut Connection) -> Fallible<()> {
+    let action = if location.workspace_name() == Some(SCRATCHPAD) {
+        "scratchpad show"
+    } else {
+        "focus"
+    };
+    sway.run(format!("[con_id={}] {action}", location.node.id))
+}
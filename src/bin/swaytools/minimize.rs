@@ -0,0 +1,213 @@
+//! Minimizing windows to the scratchpad and restoring them to the workspace they came from.
+//!
+//! Minimized windows are kept in the state file with their workspace and whether they were
+//! floating. Windows which were closed or taken out of the scratchpad otherwise are forgotten
+//! whenever the list is looked at.
+
+use crate::picker::{self, DEFAULT_PICKER};
+use clap::Subcommand;
+use serde::Serialize;
+use swayipc::{Event, EventType, Node, NodeType};
+use swaytools::{
+    error::{Error, Fallible},
+    events::{EventStream, StreamEvent},
+    state::{MinimizedWindow, StateFile},
+    tree, Connection, WorkspaceTarget,
+};
+
+/// The name of the workspace sway keeps the scratchpad windows on.
+const SCRATCHPAD: &str = "__i3_scratch";
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Move the focused (or given) window to the scratchpad
+    Window {
+        #[arg(long)]
+        con_id: Option<i64>,
+    },
+    /// Move the most recently minimized (or picked) window back to its workspace and focus it
+    Restore {
+        /// Choose the window with a dmenu-like picker
+        #[arg(short, long, num_args = 0..=1, default_missing_value = DEFAULT_PICKER, value_name = "COMMAND")]
+        picker: Option<String>,
+    },
+    /// List the minimized windows, most recently minimized first
+    List {
+        /// Print a JSON object for a waybar custom module
+        #[arg(long)]
+        waybar: bool,
+
+        /// Print the list again whenever a window changes
+        #[arg(short, long)]
+        watch: bool,
+    },
+}
+
+#[derive(Serialize)]
+struct WaybarOutput {
+    text: String,
+    tooltip: String,
+    alt: String,
+    class: String,
+}
+
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    let state = StateFile::open_default();
+    match args.command {
+        Command::Window { con_id } => minimize(con_id, &state, &mut sway),
+        Command::Restore { picker } => restore(picker.as_deref(), &state, &mut sway),
+        Command::List { waybar, watch } => {
+            print_list(waybar, &state, &mut sway)?;
+            if !watch {
+                return Ok(());
+            }
+            // Windows are moved to and from the scratchpad without a workspace event.
+            for event in EventStream::subscribe(&[EventType::Window])? {
+                let result = match event {
+                    StreamEvent::Event(event) if matches!(*event, Event::Window(_)) => {
+                        print_list(waybar, &state, &mut sway)
+                    }
+                    StreamEvent::Event(_) => continue,
+                    StreamEvent::Reconnected => Connection::new(dry_run).and_then(|connection| {
+                        sway = connection;
+                        print_list(waybar, &state, &mut sway)
+                    }),
+                };
+                if let Err(err) = result {
+                    eprintln!("Cannot list the minimized windows: {err}");
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Moves the window `con_id` or the focused window to the scratchpad and remembers its
+/// workspace.
+fn minimize(con_id: Option<i64>, state: &StateFile, sway: &mut Connection) -> Fallible<()> {
+    let tree = sway.get_tree()?;
+    let location = match con_id {
+        Some(id) => tree::find_by_id(&tree, id),
+        None => tree::find(&tree, |node| node.focused),
+    }
+    .filter(|location| location.is_window())
+    .ok_or_else(|| match con_id {
+        Some(id) => Error::WindowNotFound(format!("con_id {id}")),
+        None => Error::WindowNotFound("focus".to_owned()),
+    })?;
+    let Some(workspace) = location.workspace_name().filter(|ws| *ws != SCRATCHPAD) else {
+        return Ok(());
+    };
+    let window = MinimizedWindow {
+        id: location.node.id,
+        workspace: workspace.to_owned(),
+        floating: location.node.node_type == NodeType::FloatingCon,
+    };
+    sway.run(format!("[con_id={}] move scratchpad", window.id))?;
+    if !sway.dry_run() {
+        state.update(|state| {
+            state
+                .minimized
+                .retain(|minimized| minimized.id != window.id);
+            state.minimized.insert(0, window);
+        })?;
+    }
+    Ok(())
+}
+
+/// Returns the minimized windows which are still in the scratchpad and forgets the others.
+fn minimized(state: &StateFile, tree: &Node, dry_run: bool) -> Fallible<Vec<MinimizedWindow>> {
+    let in_scratchpad = |id: i64| {
+        tree::find_by_id(tree, id)
+            .is_some_and(|location| location.workspace_name() == Some(SCRATCHPAD))
+    };
+    let mut windows = state.load()?.minimized;
+    let before = windows.len();
+    windows.retain(|window| in_scratchpad(window.id));
+    if windows.len() != before && !dry_run {
+        state.update(|state| state.minimized.retain(|window| in_scratchpad(window.id)))?;
+    }
+    Ok(windows)
+}
+
+/// Returns the line describing the minimized `window`, i.e., its app, title, and workspace.
+fn describe(window: &MinimizedWindow, tree: &Node) -> String {
+    let node = tree::find_by_id(tree, window.id).map(|location| location.node);
+    format!(
+        "{} — {} — {} [{}]",
+        node.and_then(tree::app).unwrap_or_default(),
+        node.and_then(|node| node.name.as_deref())
+            .unwrap_or_default(),
+        window.workspace,
+        window.id
+    )
+}
+
+/// Moves the most recently minimized window or the one picked with `picker` back to its
+/// workspace and focuses it.
+fn restore(picker: Option<&str>, state: &StateFile, sway: &mut Connection) -> Fallible<()> {
+    let tree = sway.get_tree()?;
+    let windows = minimized(state, &tree, sway.dry_run())?;
+    let window = match picker {
+        Some(picker) => {
+            let entries: Vec<String> = windows.iter().map(|w| describe(w, &tree)).collect();
+            match picker::pick(picker, &entries)? {
+                Some(index) => &windows[index],
+                None => return Ok(()),
+            }
+        }
+        None => windows
+            .first()
+            .ok_or_else(|| Error::WindowNotFound("minimized state".to_owned()))?,
+    };
+
+    let id = window.id;
+    let mut command = format!("[con_id={id}] scratchpad show, ");
+    if !window.floating {
+        command.push_str("floating disable, ");
+    }
+    command.push_str(&format!(
+        "move container to workspace {}; [con_id={id}] focus",
+        WorkspaceTarget::parse(&window.workspace).selector()
+    ));
+    sway.run(command)?;
+    if !sway.dry_run() {
+        state.update(|state| state.minimized.retain(|minimized| minimized.id != id))?;
+    }
+    Ok(())
+}
+
+/// Prints the minimized windows one per line or as a waybar JSON object.
+fn print_list(waybar: bool, state: &StateFile, sway: &mut Connection) -> Fallible<()> {
+    let tree = sway.get_tree()?;
+    let lines: Vec<String> = minimized(state, &tree, sway.dry_run())?
+        .iter()
+        .map(|window| describe(window, &tree))
+        .collect();
+    if !waybar {
+        for line in lines {
+            println!("{line}");
+        }
+        return Ok(());
+    }
+    let status = if lines.is_empty() {
+        "empty"
+    } else {
+        "minimized"
+    };
+    let output = WaybarOutput {
+        text: lines.len().to_string(),
+        tooltip: lines.join("\n"),
+        alt: status.to_owned(),
+        class: status.to_owned(),
+    };
+    println!("{}", serde_json::to_string(&output)?);
+    Ok(())
+}
@@ -0,0 +1,102 @@
+//! Printing the current binding mode for waybar.
+//!
+//! Every mode change prints a JSON object with `text`, `tooltip`, `alt` (the mode), and `class`
+//! (`mode-<mode>` with non-alphanumeric characters replaced by `-`) per line, so a custom module
+//! with `"return-type": "json"` can style each mode. The text is empty in the default mode, which
+//! hides the module.
+
+use serde::Serialize;
+use swayipc::{Event, EventType};
+use swaytools::{
+    error::{Error, Fallible},
+    events::{EventStream, StreamEvent},
+    Connection,
+};
+use tinytemplate::TinyTemplate;
+
+/// The name of the mode sway starts in.
+const DEFAULT_MODE: &str = "default";
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The output string formatting
+    #[arg(short, long, default_value = "{mode}")]
+    format: String,
+
+    /// The tooltip string formatting
+    #[arg(short, long, default_value = "Binding mode: {mode}")]
+    tooltip: String,
+
+    /// Also show the default mode instead of hiding the module
+    #[arg(long)]
+    show_default: bool,
+}
+
+/// A line of the waybar output.
+#[derive(Serialize)]
+struct WaybarOutput {
+    text: String,
+    tooltip: String,
+    alt: String,
+    class: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ModeContext<'a> {
+    mode: &'a str,
+}
+
+/// Prints the current binding mode and again whenever it changes.
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let mut templater = TinyTemplate::new();
+    templater.set_default_formatter(&tinytemplate::format_unescaped);
+    for (name, template) in [("format", &args.format), ("tooltip", &args.tooltip)] {
+        templater
+            .add_template(name, template)
+            .map_err(|err| Error::Invalid("template", name.to_owned(), err.to_string()))?;
+    }
+    let print = |mode: &str| {
+        let render = |name| {
+            templater
+                .render(name, &ModeContext { mode })
+                .unwrap_or_default()
+        };
+        let hidden = mode == DEFAULT_MODE && !args.show_default;
+        let class = mode
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '-' })
+            .collect::<String>();
+        let output = WaybarOutput {
+            text: if hidden {
+                String::new()
+            } else {
+                render("format")
+            },
+            tooltip: render("tooltip"),
+            alt: mode.to_owned(),
+            class: vec![format!("mode-{class}")],
+        };
+        println!("{}", serde_json::to_string(&output).unwrap());
+    };
+
+    // Subscribe before querying the mode so that no change is missed in between.
+    let events = EventStream::subscribe(&[EventType::Mode])?;
+    print(&Connection::new(dry_run)?.get_binding_state()?);
+    for event in events {
+        match event {
+            StreamEvent::Event(event) => {
+                if let Event::Mode(event) = *event {
+                    print(&event.change);
+                }
+            }
+            // sway may have been restarted in another mode.
+            StreamEvent::Reconnected => {
+                match Connection::new(dry_run).and_then(|mut sway| Ok(sway.get_binding_state()?)) {
+                    Ok(mode) => print(&mode),
+                    Err(err) => eprintln!("Cannot query the binding mode: {err}"),
+                }
+            }
+        }
+    }
+    Ok(())
+}
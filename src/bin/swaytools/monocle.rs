@@ -0,0 +1,111 @@
+//! Showing only the focused window on its workspace and bringing the others back.
+//!
+//! Unlike fullscreen, the window keeps its gaps and bars and the other outputs stay usable. The
+//! layout of the workspace is saved like a session in the state file and all other windows are
+//! moved to the scratchpad. Toggling again puts the windows back into the saved layout as `stash
+//! unstash` does and focuses the maximized window. Windows opened meanwhile are left where they
+//! are, and the windows are brought back even if the maximized window was closed.
+
+use crate::session::{self, Restorer, Session};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+use swayipc::NodeType;
+use swaytools::{
+    error::{Error, Fallible},
+    state::StateFile,
+    tree::{self, Location},
+    Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {}
+
+/// The name the maximized workspaces are kept under in the state file.
+const TOOL: &str = "monocle";
+
+/// A workspace showing only one window.
+#[derive(Debug, Serialize, Deserialize)]
+struct Monocle {
+    session: Session,
+    /// The maximized window.
+    focused: i64,
+}
+
+/// Maximizes the focused window or restores the layout of its workspace.
+pub fn run(_args: Args, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    let state = StateFile::open_default();
+    let tree = sway.get_tree()?;
+    let focused = tree::find(&tree, |node| node.focused).ok_or(Error::NoFocusedWorkspace)?;
+    let workspace = focused
+        .workspace_name()
+        .ok_or(Error::NoFocusedWorkspace)?
+        .to_owned();
+
+    let mut monocles: HashMap<String, Monocle> = state.load()?.tool(TOOL)?.unwrap_or_default();
+    if let Some(monocle) = monocles.remove(&workspace) {
+        restore(&monocle, &mut sway)?;
+    } else {
+        let Some(monocle) = maximize(&focused, &tree, &mut sway)? else {
+            return Ok(());
+        };
+        monocles.insert(workspace, monocle);
+    }
+    if sway.dry_run() {
+        return Ok(());
+    }
+    let monocles = serde_json::to_value(&monocles)?;
+    state.update(|state| {
+        state.tools.insert(TOOL.to_owned(), monocles);
+    })
+}
+
+/// Saves the workspace of the `focused` tiled window and moves its other windows to the
+/// scratchpad, `None` if there are no other windows.
+fn maximize(
+    focused: &Location,
+    tree: &swayipc::Node,
+    sway: &mut Connection,
+) -> Fallible<Option<Monocle>> {
+    if !focused.is_window() || focused.node.node_type == NodeType::FloatingCon {
+        return Ok(None);
+    }
+    let Some(workspace) = focused
+        .workspace
+        .and_then(|ws| tree::find_by_id(tree, ws.id))
+    else {
+        return Ok(None);
+    };
+    let commands: Vec<String> = tree::windows(workspace.node)
+        .iter()
+        .filter(|window| window.node.id != focused.node.id)
+        .map(|window| format!("[con_id={}] move scratchpad", window.node.id))
+        .collect();
+    let Some(saved) = session::save_workspace(&workspace, true) else {
+        return Ok(None);
+    };
+    if commands.is_empty() {
+        return Ok(None);
+    }
+    sway.run(commands.join("; "))?;
+    Ok(Some(Monocle {
+        session: Session {
+            workspaces: vec![saved],
+        },
+        focused: focused.node.id,
+    }))
+}
+
+/// Puts the maximized window and the hidden windows back into the saved layout.
+fn restore(monocle: &Monocle, sway: &mut Connection) -> Fallible<()> {
+    let id = monocle.focused;
+    // The maximized window is placed from the scratchpad like the others.
+    if tree::find_by_id(&sway.get_tree()?, id).is_some() {
+        sway.run(format!("[con_id={id}] move scratchpad"))?;
+    }
+    Restorer::new(sway, HashMap::new(), Duration::ZERO)?.restore(&monocle.session)?;
+    if tree::find_by_id(&sway.get_tree()?, id).is_some() {
+        sway.run(format!("[con_id={id}] focus"))?;
+    }
+    Ok(())
+}
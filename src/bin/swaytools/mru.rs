@@ -0,0 +1,119 @@
+//! Switching between the most recently used windows.
+//!
+//! `swaytools mru watch` records the focused windows in the state file, `swaytools mru switch`
+//! focuses the window focused before the current one (across workspaces and outputs) or lets the
+//! user pick one of all windows in the order they were last used.
+
+use crate::picker::{self, DEFAULT_PICKER};
+use clap::Subcommand;
+use swayipc::{Event, EventType, WindowChange};
+use swaytools::{
+    error::Fallible,
+    events::{EventStream, StreamEvent},
+    state::StateFile,
+    tree, Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Record the order in which windows are focused
+    Watch,
+    /// Focus the previously used window
+    Switch {
+        /// Pick the window with this dmenu-compatible command instead, e.g., 'wofi --dmenu'
+        #[arg(short, long, value_name = "COMMAND", num_args = 0..=1, default_missing_value = DEFAULT_PICKER)]
+        picker: Option<String>,
+    },
+}
+
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    match args.command {
+        Command::Watch => watch(dry_run),
+        Command::Switch { picker } => switch(picker.as_deref(), dry_run),
+    }
+}
+
+/// Records every focused window and forgets closed ones, a dry run only prints them.
+fn watch(dry_run: bool) -> Fallible<()> {
+    let state = StateFile::open_default();
+    if let Some(focused) = Connection::new(dry_run)?
+        .get_tree()?
+        .find_focused(|n| n.focused)
+    {
+        record(&state, focused.id, true, dry_run)?;
+    }
+    for event in EventStream::subscribe(&[EventType::Window])? {
+        let StreamEvent::Event(event) = event else {
+            continue;
+        };
+        let Event::Window(event) = *event else {
+            continue;
+        };
+        let result = match event.change {
+            WindowChange::Focus => record(&state, event.container.id, true, dry_run),
+            WindowChange::Close => record(&state, event.container.id, false, dry_run),
+            _ => continue,
+        };
+        if let Err(err) = result {
+            eprintln!("Cannot record the window history: {err}");
+        }
+    }
+    Ok(())
+}
+
+/// Records the window `id` as focused or closed in the `state`.
+fn record(state: &StateFile, id: i64, focused: bool, dry_run: bool) -> Fallible<()> {
+    if dry_run {
+        println!("{} window {id}", if focused { "Focused" } else { "Closed" });
+        return Ok(());
+    }
+    state.update(|state| {
+        if focused {
+            state.push_window(id);
+        } else {
+            state.windows.retain(|&window| window != id);
+        }
+    })
+}
+
+/// Focuses the window used before the focused one or the window chosen with `picker`.
+///
+/// Windows which were never focused since the history is recorded come last in tree order.
+fn switch(picker: Option<&str>, dry_run: bool) -> Fallible<()> {
+    let history = StateFile::open_default().load()?.windows;
+    let mut sway = Connection::new(dry_run)?;
+    let tree = sway.get_tree()?;
+    let mut windows = tree::windows(&tree);
+    windows.sort_by_key(|location| {
+        history
+            .iter()
+            .position(|&id| id == location.node.id)
+            .unwrap_or(usize::MAX)
+    });
+    // The focused window is not necessarily the first one if the history is outdated.
+    if let Some(focused) = windows.iter().position(|location| location.node.focused) {
+        let focused = windows.remove(focused);
+        windows.insert(0, focused);
+    }
+
+    let index = match picker {
+        Some(picker) => {
+            let entries: Vec<String> = windows.iter().map(picker::describe).collect();
+            match picker::pick(picker, &entries)? {
+                Some(index) => index,
+                None => return Ok(()),
+            }
+        }
+        None => 1,
+    };
+    match windows.get(index) {
+        Some(location) => sway.run(format!("[con_id={}] focus", location.node.id)),
+        None => Ok(()),
+    }
+}
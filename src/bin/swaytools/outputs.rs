@@ -0,0 +1,370 @@
+//! Output profiles which are applied depending on the connected outputs.
+//!
+//! A profile configures each of its outputs and optionally maps workspaces to them. It matches if
+//! every connected output is listed in the profile and every listed output is connected:
+//!
+//! ```toml
+//! [[profile]]
+//! name = "docked"
+//! workspaces = ["Dell Inc. DELL U2415 7MT0186417KS:1-5", "eDP-1:6-10"]
+//!
+//! [[profile.output]]
+//! output = "Dell Inc. DELL U2415 7MT0186417KS"
+//! mode = "1920x1200@59.950Hz"
+//! position = [0, 0]
+//!
+//! [[profile.output]]
+//! output = "eDP-1"
+//! position = [1920, 0]
+//! scale = 1.5
+//!
+//! [[profile]]
+//! name = "mobile"
+//!
+//! [[profile.output]]
+//! output = "eDP-1"
+//! ```
+//!
+//! `power` turns outputs on and off without disabling them, so that their workspaces stay where
+//! they are. `power only` keeps just one output on, e.g., for presentations, and remembers which
+//! outputs it turned off for `power restore`.
+
+use clap::{Subcommand, ValueHint};
+use serde::Deserialize;
+use std::fs;
+use swayipc::{Event, EventType, Output};
+use swaytools::{
+    env,
+    error::{Error, Fallible},
+    events::{EventStream, StreamEvent},
+    make_config, move_workspaces, output_identifier, output_if_exists, save_config,
+    state::StateFile,
+    Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Command,
+
+    /// The TOML file with the output profiles [default: $XDG_CONFIG_HOME/swaytools/outputs.toml]
+    #[arg(short, long, global = true, value_hint = ValueHint::FilePath)]
+    profiles: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List the profiles, marking the one matching the connected outputs with '*'
+    List,
+    /// Apply the profile matching the connected outputs or the given one
+    Apply {
+        /// The name of the profile to apply regardless of the connected outputs
+        name: Option<String>,
+    },
+    /// Apply the matching profile now and whenever the connected outputs change
+    Watch,
+    /// Turn outputs on or off
+    Power {
+        #[command(subcommand)]
+        action: PowerAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PowerAction {
+    /// Turn the given output or all outputs on
+    On {
+        /// The name of the output or its make, model, and serial number
+        output: Option<String>,
+    },
+    /// Turn the given output or all outputs off
+    Off {
+        /// The name of the output or its make, model, and serial number
+        output: Option<String>,
+    },
+    /// Turn the given output or all outputs on if they are off and off otherwise
+    Toggle {
+        /// The name of the output or its make, model, and serial number
+        output: Option<String>,
+    },
+    /// Turn all outputs off except the given or the focused one
+    Only {
+        /// The name of the output or its make, model, and serial number
+        output: Option<String>,
+    },
+    /// Turn the outputs turned off by `only` on again
+    Restore,
+}
+
+/// The contents of a profiles file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Profiles {
+    #[serde(default, rename = "profile")]
+    profiles: Vec<Profile>,
+}
+
+/// A named set of output settings.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Profile {
+    name: String,
+    #[serde(default, rename = "output")]
+    outputs: Vec<OutputSettings>,
+    /// Workspace mappings in the form "output:workspaces" as for workspaces-to-outputs.
+    #[serde(default)]
+    workspaces: Vec<String>,
+}
+
+/// The settings of one output of a profile, where unset values are left unchanged.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OutputSettings {
+    /// The name of the output or its make, model, and serial number.
+    output: String,
+    #[serde(default = "enabled")]
+    enable: bool,
+    /// The mode as `<width>x<height>[@<refresh rate>Hz]`.
+    mode: Option<String>,
+    position: Option<(i32, i32)>,
+    scale: Option<f64>,
+    transform: Option<String>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Profile {
+    /// Returns whether the profile lists exactly the `outputs`.
+    fn matches(&self, outputs: &[Output]) -> bool {
+        outputs
+            .iter()
+            .all(|output| self.outputs.iter().any(|s| s.is(output)))
+            && self
+                .outputs
+                .iter()
+                .all(|s| outputs.iter().any(|output| s.is(output)))
+    }
+}
+
+impl OutputSettings {
+    /// Returns whether the settings belong to `output`.
+    fn is(&self, output: &Output) -> bool {
+        self.output == output.name || self.output == output_identifier(output)
+    }
+
+    /// Returns the sway command applying the settings to the output named `name`.
+    fn command(&self, name: &str) -> String {
+        if !self.enable {
+            return format!("output '{name}' disable");
+        }
+        let mut command = format!("output '{name}' enable");
+        if let Some(mode) = &self.mode {
+            command.push_str(&format!(" mode {mode}"));
+        }
+        if let Some((x, y)) = self.position {
+            command.push_str(&format!(" position {x} {y}"));
+        }
+        if let Some(scale) = self.scale {
+            command.push_str(&format!(" scale {scale}"));
+        }
+        if let Some(transform) = &self.transform {
+            command.push_str(&format!(" transform {transform}"));
+        }
+        command
+    }
+}
+
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    if let Command::Power { action } = args.command {
+        return power(action, dry_run);
+    }
+    let path = args.profiles.unwrap_or_else(|| {
+        env::config_file("outputs.toml")
+            .to_string_lossy()
+            .into_owned()
+    });
+    let profiles: Profiles = toml::from_str(&fs::read_to_string(path)?)?;
+    match args.command {
+        Command::List => list(&profiles, dry_run),
+        Command::Apply { name } => {
+            let mut sway = Connection::new(dry_run)?;
+            let outputs = sway.get_outputs()?;
+            let profile = match name {
+                Some(name) => profiles
+                    .profiles
+                    .iter()
+                    .find(|profile| profile.name == name)
+                    .ok_or_else(|| Error::NotFound("output profile", format!("named '{name}'")))?,
+                None => matching(&profiles, &outputs)?,
+            };
+            apply(profile, &outputs, &mut sway)
+        }
+        Command::Watch => watch(&profiles, dry_run),
+        Command::Power { .. } => unreachable!("handled before loading the profiles"),
+    }
+}
+
+/// Returns the name of the connected output called `output` or with this make, model, and
+/// serial number, or `*` for all outputs if `output` is `None`.
+fn resolve(output: Option<String>, sway: &mut Connection) -> Fallible<String> {
+    match output {
+        Some(output) => output_if_exists(output.clone(), sway).ok_or(Error::OutputNotFound(output)),
+        None => Ok("*".to_owned()),
+    }
+}
+
+/// Runs the power `action`, a dry run keeps the state file untouched.
+fn power(action: PowerAction, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    match action {
+        PowerAction::On { output } => {
+            let name = resolve(output, &mut sway)?;
+            sway.run(format!("output '{name}' power on"))
+        }
+        PowerAction::Off { output } => {
+            let name = resolve(output, &mut sway)?;
+            sway.run(format!("output '{name}' power off"))
+        }
+        PowerAction::Toggle { output } => {
+            let name = resolve(output, &mut sway)?;
+            sway.run(format!("output '{name}' power toggle"))
+        }
+        PowerAction::Only { output } => {
+            let outputs = sway.get_outputs()?;
+            let keep = match output {
+                Some(output) => resolve(Some(output), &mut sway)?,
+                None => outputs
+                    .iter()
+                    .find(|output| output.focused)
+                    .map(|output| output.name.clone())
+                    .ok_or(Error::NoFocusedOutput)?,
+            };
+            let others: Vec<&Output> = outputs
+                .iter()
+                .filter(|output| output.active && output.power && output.name != keep)
+                .collect();
+            let mut commands = vec![format!("output '{keep}' power on")];
+            commands.extend(
+                others
+                    .iter()
+                    .map(|output| format!("output '{}' power off", output.name)),
+            );
+            sway.run(commands.join("; "))?;
+            if !dry_run {
+                StateFile::open_default().update(|state| {
+                    for output in &others {
+                        let identifier = output_identifier(output);
+                        if !state.powered_off.contains(&identifier) {
+                            state.powered_off.push(identifier);
+                        }
+                    }
+                })?;
+            }
+            Ok(())
+        }
+        PowerAction::Restore => {
+            let state = StateFile::open_default();
+            // Outputs which were disconnected in the meantime are forgotten as well.
+            let commands: Vec<String> = state
+                .load()?
+                .powered_off
+                .into_iter()
+                .filter_map(|output| output_if_exists(output, &mut sway))
+                .map(|name| format!("output '{name}' power on"))
+                .collect();
+            if !commands.is_empty() {
+                sway.run(commands.join("; "))?;
+            }
+            if !dry_run {
+                state.update(|state| state.powered_off.clear())?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Prints the names of all profiles, marking the first one which matches the connected outputs.
+fn list(profiles: &Profiles, dry_run: bool) -> Fallible<()> {
+    let outputs = Connection::new(dry_run)?.get_outputs()?;
+    let current = profiles.profiles.iter().position(|p| p.matches(&outputs));
+    for (i, profile) in profiles.profiles.iter().enumerate() {
+        let marker = if current == Some(i) { '*' } else { ' ' };
+        println!("{marker} {}", profile.name);
+    }
+    Ok(())
+}
+
+/// Returns the first profile which matches the connected `outputs`.
+fn matching<'a>(profiles: &'a Profiles, outputs: &[Output]) -> Fallible<&'a Profile> {
+    profiles
+        .profiles
+        .iter()
+        .find(|profile| profile.matches(outputs))
+        .ok_or_else(|| {
+            Error::NotFound("output profile", "matches the connected outputs".to_owned())
+        })
+}
+
+/// Configures the connected `outputs` and moves the workspaces according to `profile`.
+///
+/// Settings for outputs which are not connected are skipped. Outputs which cannot be configured
+/// do not keep the workspaces from being moved.
+fn apply(profile: &Profile, outputs: &[Output], sway: &mut Connection) -> Fallible<()> {
+    let commands: Vec<String> = profile
+        .outputs
+        .iter()
+        .filter_map(|settings| {
+            let output = outputs.iter().find(|output| settings.is(output))?;
+            Some(settings.command(&output.name))
+        })
+        .collect();
+    let mut failures = Vec::new();
+    if !commands.is_empty() {
+        sway.run_tracked(commands.join("; "), &mut failures)?;
+    }
+    if !profile.workspaces.is_empty() {
+        let config = make_config(profile.workspaces.clone(), false, sway)?;
+        if !sway.dry_run() {
+            save_config(&config)?;
+        }
+        move_workspaces(&config, sway)?;
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::PartialApply(failures))
+    }
+}
+
+/// Applies the matching profile and again whenever the set of connected outputs changes.
+///
+/// Output events caused by applying a profile do not change the set and are ignored.
+fn watch(profiles: &Profiles, dry_run: bool) -> Fallible<()> {
+    // Subscribe before the first apply so that no output change is missed in between.
+    let events = EventStream::subscribe(&[EventType::Output])?;
+    let mut connected = None;
+    for event in std::iter::once(StreamEvent::Reconnected).chain(events) {
+        match event {
+            StreamEvent::Event(event) if matches!(*event, Event::Output(_)) => (),
+            StreamEvent::Reconnected => (),
+            StreamEvent::Event(_) => continue,
+        }
+        // A fresh connection as sway may have been restarted since the last apply.
+        let mut sway = Connection::new(dry_run)?;
+        let outputs = sway.get_outputs()?;
+        let mut identifiers: Vec<String> = outputs.iter().map(output_identifier).collect();
+        identifiers.sort();
+        if connected.as_ref() == Some(&identifiers) {
+            continue;
+        }
+        connected = Some(identifiers);
+        if let Err(err) =
+            matching(profiles, &outputs).and_then(|profile| apply(profile, &outputs, &mut sway))
+        {
+            eprintln!("Cannot apply an output profile: {err}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,256 @@
+//! A JSON overview of all workspaces and their windows for overview and exposé frontends.
+//!
+//! Each workspace lists its output, whether it is visible or focused, and its windows with their
+//! absolute geometry and their geometry relative to the output as fractions, which a frontend can
+//! scale onto a thumbnail of any size.
+//!
+//! With `--thumbnails` the visible workspaces are captured with `grim`, one PNG file per
+//! workspace in the given directory. Hidden workspaces are not rendered by sway, so they keep the
+//! thumbnail from when they were last visible. Thumbnails of workspaces which no longer exist are
+//! removed.
+
+use clap::ValueHint;
+use serde::Serialize;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use swayipc::{Node, NodeType, Rect};
+use swaytools::{
+    error::{Error, Fallible},
+    tree, Connection,
+};
+
+/// The extension of the thumbnail files.
+const THUMBNAIL_EXTENSION: &str = "png";
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Capture the visible workspaces into this directory [default: $XDG_RUNTIME_DIR/swaytools-overview]
+    #[arg(short, long, num_args = 0..=1, default_missing_value = "", value_hint = ValueHint::DirPath)]
+    thumbnails: Option<String>,
+
+    /// The scale of the thumbnails relative to the outputs
+    #[arg(short, long, default_value_t = 0.25)]
+    scale: f64,
+
+    /// Print indented JSON
+    #[arg(long)]
+    pretty: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Overview {
+    workspaces: Vec<WorkspaceOverview>,
+}
+
+#[derive(Debug, Serialize)]
+struct WorkspaceOverview {
+    name: String,
+    num: Option<i32>,
+    output: String,
+    visible: bool,
+    focused: bool,
+    /// The geometry of the output.
+    rect: Geometry,
+    /// The thumbnail of the workspace if one was captured now or earlier.
+    thumbnail: Option<PathBuf>,
+    windows: Vec<WindowOverview>,
+}
+
+#[derive(Debug, Serialize)]
+struct WindowOverview {
+    id: i64,
+    app: Option<String>,
+    title: Option<String>,
+    floating: bool,
+    focused: bool,
+    urgent: bool,
+    rect: Geometry,
+    /// The geometry relative to the output, each value as a fraction of its width or height.
+    relative: RelativeGeometry,
+}
+
+#[derive(Debug, Serialize)]
+struct Geometry {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+#[derive(Debug, Serialize)]
+struct RelativeGeometry {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+impl From<&Rect> for Geometry {
+    fn from(rect: &Rect) -> Self {
+        Geometry {
+            x: rect.x,
+            y: rect.y,
+            width: rect.width,
+            height: rect.height,
+        }
+    }
+}
+
+impl RelativeGeometry {
+    /// Returns `rect` relative to `area`, both in absolute coordinates.
+    fn new(rect: &Rect, area: &Rect) -> Self {
+        let fraction = |value: i32, total: i32| {
+            if total == 0 {
+                0.0
+            } else {
+                f64::from(value) / f64::from(total)
+            }
+        };
+        RelativeGeometry {
+            x: fraction(rect.x - area.x, area.width),
+            y: fraction(rect.y - area.y, area.height),
+            width: fraction(rect.width, area.width),
+            height: fraction(rect.height, area.height),
+        }
+    }
+}
+
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    let tree = sway.get_tree()?;
+    let workspaces = sway.get_workspaces()?;
+    let visible: HashSet<&str> = workspaces
+        .iter()
+        .filter(|workspace| workspace.visible)
+        .map(|workspace| workspace.name.as_str())
+        .collect();
+    let directory = args.thumbnails.map(|directory| match directory.as_str() {
+        "" => swaytools::env::runtime_file("swaytools-overview"),
+        _ => PathBuf::from(directory),
+    });
+    if let Some(directory) = &directory {
+        fs::create_dir_all(directory)?;
+    }
+
+    let mut overview = Overview {
+        workspaces: Vec::new(),
+    };
+    for location in tree::walk(&tree) {
+        let (workspace, Some(output)) = (location.node, location.output) else {
+            continue;
+        };
+        if workspace.node_type != NodeType::Workspace {
+            continue;
+        }
+        let name = workspace.name.clone().unwrap_or_default();
+        // The scratchpad is a hidden workspace on a hidden output.
+        if name == "__i3_scratch" {
+            continue;
+        }
+        let output_name = output.name.clone().unwrap_or_default();
+        let is_visible = visible.contains(name.as_str());
+        let thumbnail = match &directory {
+            Some(directory) => {
+                let path = thumbnail_path(directory, &name);
+                if is_visible {
+                    capture(&output_name, args.scale, &path)?;
+                }
+                path.exists().then_some(path)
+            }
+            None => None,
+        };
+        overview.workspaces.push(WorkspaceOverview {
+            visible: is_visible,
+            focused: workspaces.iter().any(|ws| ws.focused && ws.name == name),
+            num: workspace.num,
+            rect: Geometry::from(&output.rect),
+            thumbnail,
+            windows: windows(workspace, &output.rect),
+            name,
+            output: output_name,
+        });
+    }
+    if let Some(directory) = &directory {
+        remove_stale(directory, &overview)?;
+    }
+
+    let json = if args.pretty {
+        serde_json::to_string_pretty(&overview)?
+    } else {
+        serde_json::to_string(&overview)?
+    };
+    println!("{json}");
+    Ok(())
+}
+
+/// Returns the windows of `workspace`, tiled ones first, with their geometry relative to `area`.
+fn windows(workspace: &Node, area: &Rect) -> Vec<WindowOverview> {
+    tree::windows(workspace)
+        .into_iter()
+        .map(|location| {
+            let node = location.node;
+            WindowOverview {
+                id: node.id,
+                app: tree::app(node).map(str::to_owned),
+                title: node.name.clone(),
+                floating: node.node_type == NodeType::FloatingCon,
+                focused: node.focused,
+                urgent: node.urgent,
+                rect: Geometry::from(&node.rect),
+                relative: RelativeGeometry::new(&node.rect, area),
+            }
+        })
+        .collect()
+}
+
+/// Returns the thumbnail file of the workspace called `name` in `directory`.
+///
+/// Characters other than letters and digits are replaced since workspace names may contain
+/// slashes and the like.
+fn thumbnail_path(directory: &Path, name: &str) -> PathBuf {
+    let stem: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    directory.join(format!("workspace-{stem}.{THUMBNAIL_EXTENSION}"))
+}
+
+/// Captures the output called `output` scaled by `scale` into `path`.
+fn capture(output: &str, scale: f64, path: &Path) -> Fallible<()> {
+    let status = Command::new("grim")
+        .arg("-o")
+        .arg(output)
+        .arg("-s")
+        .arg(scale.to_string())
+        .arg(path)
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::CommandFailed("grim".to_owned(), status.to_string()))
+    }
+}
+
+/// Removes the thumbnails in `directory` which belong to none of the workspaces in `overview`.
+fn remove_stale(directory: &Path, overview: &Overview) -> Fallible<()> {
+    let current: HashSet<&Path> = overview
+        .workspaces
+        .iter()
+        .filter_map(|workspace| workspace.thumbnail.as_deref())
+        .collect();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        let is_thumbnail = path.file_name().is_some_and(|name| {
+            let name = name.to_string_lossy();
+            name.starts_with("workspace-") && name.ends_with(&format!(".{THUMBNAIL_EXTENSION}"))
+        });
+        if is_thumbnail && !current.contains(path.as_path()) {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,44 @@
+//! Choosing an entry with a dmenu-compatible picker like `rofi -dmenu` or `wofi --dmenu`.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+use swaytools::{
+    error::Fallible,
+    tree::{self, Location},
+};
+
+/// The picker used if none is given.
+pub const DEFAULT_PICKER: &str = "rofi -dmenu -i";
+
+/// Runs the shell `command` with one entry per line on its stdin and returns the index of the
+/// entry it printed, `None` if the selection was cancelled.
+pub fn pick(command: &str, entries: &[String]) -> Fallible<Option<usize>> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(entries.join("\n").as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    let selected = String::from_utf8_lossy(&output.stdout);
+    let selected = selected.trim_end_matches('\n');
+    Ok(entries.iter().position(|entry| entry == selected))
+}
+
+/// Returns the line describing the window at `location` in a picker, i.e., its app id (or class),
+/// title, workspace, and container id.
+pub fn describe(location: &Location) -> String {
+    let node = location.node;
+    format!(
+        "{} — {} — {} [{}]",
+        tree::app(node).unwrap_or_default(),
+        node.name.as_deref().unwrap_or_default(),
+        location.workspace_name().unwrap_or_default(),
+        node.id
+    )
+}
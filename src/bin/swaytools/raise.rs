@@ -0,0 +1,77 @@
+//! Focusing a window of an application or launching it if it has none.
+//!
+//! All windows of the tree are considered, including floating windows, windows on other outputs,
+//! and windows in the scratchpad. If the focused window already matches, the next matching window
+//! is focused, so repeating the binding cycles through them.
+
+use crate::marks;
+use swaytools::{
+    env,
+    error::Fallible,
+    exec_command,
+    manager::WorkspaceManager,
+    pattern::Pattern,
+    tree::{self, Location},
+    Connection,
+};
+
+#[derive(clap::Args, Debug)]
+#[command(group(clap::ArgGroup::new("criteria").required(true).multiple(true).args(["app", "title"])))]
+pub struct Args {
+    /// The app id or class of the window, a glob or a regex enclosed in slashes
+    #[arg(short, long)]
+    app: Option<String>,
+
+    /// The title of the window, a glob or a regex enclosed in slashes
+    #[arg(short, long)]
+    title: Option<String>,
+
+    /// Launch the command on this workspace, which is created on its mapped output
+    #[arg(short, long, value_name = "NUM")]
+    workspace: Option<i32>,
+
+    /// The command to launch if no window matches
+    #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    command: Vec<String>,
+}
+
+/// Returns whether the window at `location` matches all given patterns.
+fn matches(location: &Location, app: Option<&Pattern>, title: Option<&Pattern>) -> bool {
+    let node = location.node;
+    app.is_none_or(|app| tree::app(node).is_some_and(|value| app.matches(value)))
+        && title.is_none_or(|title| {
+            node.name
+                .as_deref()
+                .is_some_and(|value| title.matches(value))
+        })
+}
+
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let app = args.app.as_deref().map(Pattern::parse).transpose()?;
+    let title = args.title.as_deref().map(Pattern::parse).transpose()?;
+    let mut sway = Connection::new(dry_run)?;
+    let tree = sway.get_tree()?;
+    let windows: Vec<Location> = tree::windows(&tree)
+        .into_iter()
+        .filter(|location| matches(location, app.as_ref(), title.as_ref()))
+        .collect();
+
+    if !windows.is_empty() {
+        let next = windows
+            .iter()
+            .position(|location| location.node.focused)
+            .map_or(0, |index| (index + 1) % windows.len());
+        return marks::focus(&windows[next], &mut sway);
+    }
+
+    let command = exec_command(&args.command);
+    match args.workspace {
+        Some(num) => {
+            let mut manager =
+                WorkspaceManager::new(dry_run)?.with_mapping_file(env::mapping_file(None));
+            manager.focus(Some(num), None, false)?;
+            manager.connection().run(command)
+        }
+        None => sway.run(command),
+    }
+}
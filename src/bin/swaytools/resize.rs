@@ -0,0 +1,100 @@
+//! Resizing the focused window by presets and steps relative to the usable area of its workspace.
+//!
+//! Presets are read from a TOML file, where unset dimensions are left unchanged:
+//!
+//! ```toml
+//! [preset.reading]
+//! width = "60%"
+//!
+//! [preset.video]
+//! width = "1280px"
+//! height = "720px"
+//! ```
+
+use crate::geometry::{self, Length};
+use clap::{Subcommand, ValueHint};
+use serde::Deserialize;
+use std::{collections::HashMap, fs};
+use swaytools::{
+    env,
+    error::{Error, Fallible},
+    Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    action: Action,
+
+    /// The TOML file with the presets [default: $XDG_CONFIG_HOME/swaytools/resize.toml]
+    #[arg(short, long, global = true, value_hint = ValueHint::FilePath)]
+    presets: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Action {
+    /// Resize the window to a preset
+    Preset { name: String },
+    /// Make the window wider, e.g., by '5%' or '50px'
+    GrowWidth { step: Length },
+    /// Make the window narrower
+    ShrinkWidth { step: Length },
+    /// Make the window taller
+    GrowHeight { step: Length },
+    /// Make the window shorter
+    ShrinkHeight { step: Length },
+}
+
+/// The contents of a presets file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Presets {
+    #[serde(default)]
+    preset: HashMap<String, Preset>,
+}
+
+/// The size of a preset.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Preset {
+    width: Option<Length>,
+    height: Option<Length>,
+}
+
+/// Resizes the focused window, tiled or floating.
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    let focused = geometry::focused(&mut sway)?;
+    let area = focused.area;
+    let resize = |change: &str, dimension: &str, step: Length, total: i32| {
+        format!("resize {change} {dimension} {} px", step.resolve(total))
+    };
+
+    let command = match args.action {
+        Action::Preset { name } => {
+            let path = args.presets.unwrap_or_else(|| {
+                env::config_file("resize.toml")
+                    .to_string_lossy()
+                    .into_owned()
+            });
+            let mut presets: Presets = toml::from_str(&fs::read_to_string(path)?)?;
+            let preset = presets
+                .preset
+                .remove(&name)
+                .ok_or_else(|| Error::NotFound("preset", format!("named '{name}'")))?;
+            let mut command = "resize set".to_owned();
+            if let Some(width) = preset.width {
+                command.push_str(&format!(" width {} px", width.resolve(area.width)));
+            }
+            if let Some(height) = preset.height {
+                command.push_str(&format!(" height {} px", height.resolve(area.height)));
+            }
+            command
+        }
+        Action::GrowWidth { step } => resize("grow", "width", step, area.width),
+        Action::ShrinkWidth { step } => resize("shrink", "width", step, area.width),
+        Action::GrowHeight { step } => resize("grow", "height", step, area.height),
+        Action::ShrinkHeight { step } => resize("shrink", "height", step, area.height),
+    };
+    sway.run(format!("[con_id={}] {command}", focused.id))
+}
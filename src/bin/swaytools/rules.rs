@@ -0,0 +1,200 @@
+//! Rules placing and sizing new windows.
+//!
+//! The rules file is a TOML file with a list of rules, the first rule matching a new window wins:
+//!
+//! ```toml
+//! [[rule]]
+//! app_id = "^firefox$"
+//! title = "Picture-in-Picture"
+//! floating = true
+//! size = [480, 270]
+//!
+//! [[rule]]
+//! class = "^(Slack|discord)$"
+//! workspace = "9"
+//!
+//! [[rule]]
+//! app_id = "^mpv$"
+//! output = "LG Electronics LG TV 0x01010101"
+//! ```
+//!
+//! `app_id`, `class`, and `title` are regular expressions which must all match. A `workspace`
+//! which does not exist yet is opened on `output` or, if that is not set, the output it is mapped
+//! to, without changing the focus. A rule with only an `output` moves the window to the visible
+//! workspace of that output. Outputs are given by name or by make, model, and serial number, an
+//! output which is not connected is ignored. The file is read again once it changed, so rules can
+//! be edited while the daemon is running.
+
+use clap::ValueHint;
+use regex::Regex;
+use serde::Deserialize;
+use std::{fs, time::SystemTime};
+use swayipc::{Event, EventType, Node, WindowChange};
+use swaytools::{
+    env,
+    error::{Error, Fallible},
+    events::{EventStream, StreamEvent},
+    get_output_for_workspace, output_if_exists,
+    placement::move_window_silently,
+    Connection, WorkspaceTarget,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The TOML file with the rules [default: $XDG_CONFIG_HOME/swaytools/rules.toml]
+    #[arg(short, long, value_hint = ValueHint::FilePath)]
+    rules: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RulesFile {
+    #[serde(default, rename = "rule")]
+    rules: Vec<RuleEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleEntry {
+    app_id: Option<String>,
+    class: Option<String>,
+    title: Option<String>,
+    workspace: Option<String>,
+    output: Option<String>,
+    floating: Option<bool>,
+    size: Option<(u32, u32)>,
+}
+
+/// The actions for the windows matching all given patterns.
+struct Rule {
+    app_id: Option<Regex>,
+    class: Option<Regex>,
+    title: Option<Regex>,
+    workspace: Option<WorkspaceTarget>,
+    output: Option<String>,
+    floating: Option<bool>,
+    size: Option<(u32, u32)>,
+}
+
+impl Rule {
+    /// Returns whether `window` matches all patterns of the rule.
+    fn matches(&self, window: &Node) -> bool {
+        let class = window
+            .window_properties
+            .as_ref()
+            .and_then(|properties| properties.class.as_deref());
+        let matches = |regex: &Option<Regex>, value: Option<&str>| {
+            regex
+                .as_ref()
+                .is_none_or(|regex| value.is_some_and(|value| regex.is_match(value)))
+        };
+        matches(&self.app_id, window.app_id.as_deref())
+            && matches(&self.class, class)
+            && matches(&self.title, window.name.as_deref())
+    }
+
+    /// Applies the actions of the rule to the window with the container id `id`.
+    fn apply(&self, id: i64, sway: &mut Connection) -> Fallible<()> {
+        let criteria = format!("[con_id={id}] ");
+        let output = self
+            .output
+            .clone()
+            .and_then(|output| output_if_exists(output, sway));
+        let mut commands = Vec::new();
+        if let Some(floating) = self.floating {
+            let state = if floating { "enable" } else { "disable" };
+            commands.push(format!("{criteria}floating {state}"));
+        }
+        if let Some((width, height)) = self.size {
+            commands.push(format!("{criteria}resize set {width} px {height} px"));
+        }
+        if let (None, Some(output)) = (&self.workspace, &output) {
+            commands.push(format!("{criteria}move container to output '{output}'"));
+        }
+        if !commands.is_empty() {
+            sway.run(commands.join("; "))?;
+        }
+        if let Some(workspace) = &self.workspace {
+            let output = output.or_else(|| workspace.num.and_then(get_output_for_workspace));
+            move_window_silently(sway, &criteria, workspace, output.as_deref())?;
+        }
+        Ok(())
+    }
+}
+
+/// The rules of a rules file in their order.
+struct Rules(Vec<Rule>);
+
+impl Rules {
+    /// Loads the rules from the TOML file at `path`.
+    fn load(path: &str) -> Fallible<Rules> {
+        let file: RulesFile = toml::from_str(&fs::read_to_string(path)?)?;
+        let mut rules = Vec::new();
+        for entry in file.rules {
+            rules.push(Rule {
+                app_id: entry.app_id.as_deref().map(regex).transpose()?,
+                class: entry.class.as_deref().map(regex).transpose()?,
+                title: entry.title.as_deref().map(regex).transpose()?,
+                workspace: entry.workspace.as_deref().map(WorkspaceTarget::parse),
+                output: entry.output,
+                floating: entry.floating,
+                size: entry.size,
+            });
+        }
+        Ok(Rules(rules))
+    }
+
+    /// Returns the first rule matching `window`.
+    fn rule_for(&self, window: &Node) -> Option<&Rule> {
+        self.0.iter().find(|rule| rule.matches(window))
+    }
+}
+
+/// Compiles `pattern`, reporting an invalid one as [`Error::Invalid`].
+pub fn regex(pattern: &str) -> Fallible<Regex> {
+    Regex::new(pattern)
+        .map_err(|err| Error::Invalid("pattern", pattern.to_owned(), err.to_string()))
+}
+
+/// Returns when the file at `path` was last modified.
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Applies the rules to every new window until sway exits.
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let path = args.rules.unwrap_or_else(|| {
+        env::config_file("rules.toml")
+            .to_string_lossy()
+            .into_owned()
+    });
+    let mut rules = Rules::load(&path)?;
+    let mut loaded = modified(&path);
+    let mut sway = Connection::new(dry_run)?;
+
+    for event in EventStream::subscribe(&[EventType::Window])? {
+        let window = match event {
+            StreamEvent::Event(event) => match *event {
+                Event::Window(event) if event.change == WindowChange::New => event.container,
+                _ => continue,
+            },
+            StreamEvent::Reconnected => {
+                sway = Connection::new(dry_run)?;
+                continue;
+            }
+        };
+        // Keep the previous rules while the file is invalid.
+        if modified(&path) != loaded {
+            loaded = modified(&path);
+            match Rules::load(&path) {
+                Ok(reloaded) => rules = reloaded,
+                Err(err) => eprintln!("Cannot reload the rules: {err}"),
+            }
+        }
+        if let Some(rule) = rules.rule_for(&window) {
+            if let Err(err) = rule.apply(window.id, &mut sway) {
+                eprintln!("Cannot apply the rule to window {}: {err}", window.id);
+            }
+        }
+    }
+    Ok(())
+}
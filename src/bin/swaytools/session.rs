@@ -0,0 +1,444 @@
+//! Saving the windows of all workspaces and launching them again in the same places.
+//!
+//! `save` writes the layout tree of every workspace with the app id (or class) of each window to a
+//! JSON file. `restore` launches every window with the command of its app from a TOML table:
+//!
+//! ```toml
+//! [app]
+//! foot = "foot"
+//! firefox = "firefox --new-window"
+//! ```
+//!
+//! sway has no placeholder windows to swallow, so the placeholders are emulated: each launched
+//! window is claimed by the next new window of its app and moved to its place. The first child of
+//! a container is split into a new container with the saved layout and marked, all further
+//! children are moved to that mark. Floating windows get their saved position and size, tiled
+//! windows share their containers evenly. Windows of apps without a command are skipped.
+
+use clap::{Subcommand, ValueHint};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+    time::Duration,
+};
+use swayipc::{Event, EventType, Node, NodeLayout, NodeType, WindowChange};
+use swaytools::{
+    connection,
+    error::{Error, Fallible},
+    placement::move_window_silently,
+    tree::{self, Location},
+    Connection, WorkspaceTarget,
+};
+
+/// The prefix of the marks set on the first child of each restored container.
+const MARK_PREFIX: &str = "_swaytools_session_";
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Command,
+
+    /// The session file [default: $XDG_CONFIG_HOME/swaytools/session.json]
+    #[arg(short, long, global = true, value_hint = ValueHint::FilePath)]
+    file: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Save the layout and windows of all workspaces
+    Save,
+    /// Launch the saved windows and move them to their saved places
+    Restore {
+        /// The TOML file with the commands of the apps [default: $XDG_CONFIG_HOME/swaytools/apps.toml]
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        apps: Option<String>,
+
+        /// How many seconds to wait for each launched window
+        #[arg(short, long, default_value_t = 30)]
+        timeout: u64,
+    },
+}
+
+/// The contents of a session file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Session {
+    pub workspaces: Vec<SavedWorkspace>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedWorkspace {
+    name: String,
+    output: String,
+    layout: Option<String>,
+    nodes: Vec<SavedNode>,
+    floating: Vec<SavedWindow>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum SavedNode {
+    Container {
+        layout: Option<String>,
+        nodes: Vec<SavedNode>,
+    },
+    Window(SavedWindow),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedWindow {
+    app: String,
+    title: String,
+    /// The position and size of a floating window.
+    rect: Option<(i32, i32, i32, i32)>,
+    /// The container id of a window which is taken from the scratchpad instead of launched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<i64>,
+}
+
+/// The contents of an apps file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Apps {
+    #[serde(default)]
+    app: HashMap<String, String>,
+}
+
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let file = args
+        .file
+        .map(PathBuf::from)
+        .unwrap_or_else(|| swaytools::env::config_file("session.json"));
+    let mut sway = Connection::new(dry_run)?;
+    match args.command {
+        Command::Save => {
+            let session = save(&sway.get_tree()?);
+            let json = serde_json::to_string_pretty(&session)?;
+            if dry_run {
+                println!("{json}");
+                return Ok(());
+            }
+            if let Some(dir) = file.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            Ok(fs::write(file, json)?)
+        }
+        Command::Restore { apps, timeout } => {
+            let session: Session = serde_json::from_str(&fs::read_to_string(file)?)?;
+            let apps = apps
+                .map(PathBuf::from)
+                .unwrap_or_else(|| swaytools::env::config_file("apps.toml"));
+            let apps: Apps = toml::from_str(&fs::read_to_string(apps)?)?;
+            Restorer::new(&mut sway, apps.app, Duration::from_secs(timeout))?.restore(&session)
+        }
+    }
+}
+
+/// Returns the workspaces of `tree` without the scratchpad.
+fn save(tree: &Node) -> Session {
+    let workspaces = tree::walk(tree)
+        .into_iter()
+        .filter(|location| location.node.node_type == NodeType::Workspace)
+        .filter(|location| location.output_name() != Some("__i3"))
+        .filter_map(|location| save_workspace(&location, false))
+        .collect();
+    Session { workspaces }
+}
+
+/// Returns the workspace at `location`, `None` if it has no windows to save.
+///
+/// With `keep_ids` the windows keep their container ids and windows without an app are saved as
+/// well, so they can be taken from the scratchpad later.
+pub fn save_workspace(location: &Location, keep_ids: bool) -> Option<SavedWorkspace> {
+    let workspace = location.node;
+    let saved = SavedWorkspace {
+        name: workspace.name.clone().unwrap_or_default(),
+        output: location.output_name().unwrap_or_default().to_owned(),
+        layout: layout(workspace),
+        nodes: workspace
+            .nodes
+            .iter()
+            .filter_map(|node| save_node(node, keep_ids))
+            .collect(),
+        floating: workspace
+            .floating_nodes
+            .iter()
+            .filter_map(|node| {
+                let mut window = save_window(node, keep_ids)?;
+                let rect = node.rect;
+                window.rect = Some((rect.x, rect.y, rect.width, rect.height));
+                Some(window)
+            })
+            .collect(),
+    };
+    (!saved.nodes.is_empty() || !saved.floating.is_empty()).then_some(saved)
+}
+
+/// Returns the tiled `node` with its children, `None` for an empty container or a window without
+/// an app.
+fn save_node(node: &Node, keep_ids: bool) -> Option<SavedNode> {
+    if node.nodes.is_empty() {
+        return save_window(node, keep_ids).map(SavedNode::Window);
+    }
+    let nodes: Vec<SavedNode> = node
+        .nodes
+        .iter()
+        .filter_map(|node| save_node(node, keep_ids))
+        .collect();
+    (!nodes.is_empty()).then(|| SavedNode::Container {
+        layout: layout(node),
+        nodes,
+    })
+}
+
+fn save_window(node: &Node, keep_ids: bool) -> Option<SavedWindow> {
+    let app = match tree::app(node) {
+        Some(app) => app,
+        None if keep_ids => "",
+        None => return None,
+    };
+    Some(SavedWindow {
+        app: app.to_owned(),
+        title: node.name.clone().unwrap_or_default(),
+        rect: None,
+        id: keep_ids.then_some(node.id),
+    })
+}
+
+/// Returns the argument of the `layout` command restoring the layout of `node`.
+fn layout(node: &Node) -> Option<String> {
+    let layout = match node.layout {
+        NodeLayout::SplitH => "splith",
+        NodeLayout::SplitV => "splitv",
+        NodeLayout::Stacked => "stacking",
+        NodeLayout::Tabbed => "tabbed",
+        _ => return None,
+    };
+    Some(layout.to_owned())
+}
+
+/// Where a restored window is moved to.
+enum Place {
+    /// The workspace, which is opened on the output if it does not exist.
+    Workspace(WorkspaceTarget, String),
+    /// Right after the window with the mark.
+    Mark(String),
+}
+
+/// Launches saved windows (or takes them from the scratchpad) and moves them to their places.
+pub struct Restorer<'a> {
+    sway: &'a mut Connection,
+    apps: HashMap<String, String>,
+    timeout: Duration,
+    events: Option<swayipc::EventStream>,
+    /// The windows which were already moved to their places.
+    claimed: HashSet<i64>,
+    marks: Vec<String>,
+}
+
+impl<'a> Restorer<'a> {
+    pub fn new(
+        sway: &'a mut Connection,
+        apps: HashMap<String, String>,
+        timeout: Duration,
+    ) -> Fallible<Restorer<'a>> {
+        let claimed = tree::windows(&sway.get_tree()?)
+            .iter()
+            .map(|location| location.node.id)
+            .collect();
+        Ok(Restorer {
+            sway,
+            apps,
+            timeout,
+            events: None,
+            claimed,
+            marks: Vec::new(),
+        })
+    }
+
+    /// Restores all workspaces of `session` and removes the marks set meanwhile.
+    pub fn restore(mut self, session: &Session) -> Fallible<()> {
+        for workspace in &session.workspaces {
+            if let Err(err) = self.restore_workspace(workspace) {
+                eprintln!("Cannot restore workspace '{}': {err}", workspace.name);
+            }
+        }
+        let commands: Vec<String> = self
+            .marks
+            .iter()
+            .map(|mark| format!("unmark '{mark}'"))
+            .collect();
+        if commands.is_empty() {
+            return Ok(());
+        }
+        self.sway.run(commands.join("; "))
+    }
+
+    fn restore_workspace(&mut self, workspace: &SavedWorkspace) -> Fallible<()> {
+        let workspace_place = Place::Workspace(
+            WorkspaceTarget::parse(&workspace.name),
+            workspace.output.to_owned(),
+        );
+        self.place_children(
+            &workspace.nodes,
+            workspace.layout.as_deref(),
+            &workspace_place,
+            false,
+        )?;
+        for window in &workspace.floating {
+            let Some(id) = self.launch(window)? else {
+                continue;
+            };
+            self.move_to(id, &workspace_place)?;
+            if let Some((x, y, width, height)) = window.rect {
+                self.sway.run(format!(
+                    "[con_id={id}] floating enable, resize set {width} px {height} px, \
+                     move absolute position {x} px {y} px"
+                ))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Launches the windows of `node`, moves them to `place`, and returns the id of the window or
+    /// the container holding them.
+    fn place(&mut self, node: &SavedNode, place: &Place) -> Fallible<Option<i64>> {
+        match node {
+            SavedNode::Window(window) => {
+                let Some(id) = self.launch(window)? else {
+                    return Ok(None);
+                };
+                self.move_to(id, place)?;
+                Ok(Some(id))
+            }
+            SavedNode::Container { layout, nodes } => {
+                self.place_children(nodes, layout.as_deref(), place, true)
+            }
+        }
+    }
+
+    /// Places the first of `nodes` at `place`, gives its parent (a new container if `split` is
+    /// set) the `layout`, and moves all other nodes after it. Returns the id of the new container
+    /// or, without `split`, of the first node.
+    fn place_children(
+        &mut self,
+        nodes: &[SavedNode],
+        layout: Option<&str>,
+        place: &Place,
+        split: bool,
+    ) -> Fallible<Option<i64>> {
+        let mut nodes = nodes.iter();
+        let first = loop {
+            let Some(node) = nodes.next() else {
+                return Ok(None);
+            };
+            if let Some(first) = self.place(node, place)? {
+                break first;
+            }
+        };
+        let mark = self.mark(first, layout, split)?;
+        // Each node is inserted right after the marked node, so they are placed in reverse.
+        for node in nodes.rev() {
+            self.place(node, &Place::Mark(mark.clone()))?;
+        }
+        if !split {
+            return Ok(Some(first));
+        }
+        let tree = self.sway.get_tree()?;
+        let parent = tree::find(&tree, |node| node.nodes.iter().any(|n| n.id == first));
+        Ok(parent.map(|parent| parent.node.id))
+    }
+
+    /// Moves the window or container `id` to `place`.
+    fn move_to(&mut self, id: i64, place: &Place) -> Fallible<()> {
+        let criteria = format!("[con_id={id}] ");
+        match place {
+            Place::Workspace(target, output) => {
+                move_window_silently(self.sway, &criteria, target, Some(output))
+            }
+            Place::Mark(mark) => self
+                .sway
+                .run(format!("{criteria}move container to mark '{mark}'")),
+        }
+    }
+
+    /// Gives the parent of the node `first` (or a new container around it if `split` is set) the
+    /// `layout` and marks `first` as the node later siblings are moved after.
+    fn mark(&mut self, first: i64, layout: Option<&str>, split: bool) -> Fallible<String> {
+        let mark = format!("{MARK_PREFIX}{}", self.marks.len());
+        let mut command = format!("[con_id={first}] ");
+        if split {
+            command.push_str("split vertical, ");
+        }
+        if let Some(layout) = layout {
+            command.push_str(&format!("layout {layout}, "));
+        }
+        command.push_str(&format!("mark --add '{mark}'"));
+        self.sway.run(command)?;
+        self.marks.push(mark.clone());
+        Ok(mark)
+    }
+
+    /// Launches the app of `window` and returns the id of its new window, `None` if the app has no
+    /// command or no window appeared in time.
+    ///
+    /// A window with an id is taken from the scratchpad instead and made tiled, `None` if it was
+    /// closed meanwhile.
+    fn launch(&mut self, window: &SavedWindow) -> Fallible<Option<i64>> {
+        if let Some(id) = window.id {
+            if tree::find_by_id(&self.sway.get_tree()?, id).is_none() {
+                return Ok(None);
+            }
+            self.sway
+                .run(format!("[con_id={id}] scratchpad show, floating disable"))?;
+            self.claimed.insert(id);
+            return Ok(Some(id));
+        }
+        let Some(command) = self.apps.get(&window.app) else {
+            eprintln!(
+                "Skipping '{}' as {} has no command",
+                window.title, window.app
+            );
+            return Ok(None);
+        };
+        if self.sway.dry_run() {
+            self.sway.run(format!("exec {command}"))?;
+            return Ok(None);
+        }
+        // Subscribe before launching so that the window cannot be missed.
+        if self.events.is_none() {
+            let sway = connection::connect_with_timeout(Some(self.timeout))?;
+            self.events = Some(sway.subscribe([EventType::Window])?);
+        }
+        self.sway.run(format!("exec {command}"))?;
+        match self.wait_for(&window.app) {
+            Ok(id) => Ok(Some(id)),
+            Err(Error::Timeout) => {
+                // The stream must not be used after a timeout.
+                self.events = None;
+                eprintln!("Skipping '{}' as no window appeared in time", window.title);
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Waits for a new window of `app` which is not claimed yet and claims it.
+    fn wait_for(&mut self, app: &str) -> Fallible<i64> {
+        let events = self.events.as_mut().ok_or(Error::Timeout)?;
+        for event in events {
+            let Event::Window(event) = event? else {
+                continue;
+            };
+            let window = event.container;
+            if event.change == WindowChange::New
+                && tree::app(&window) == Some(app)
+                && self.claimed.insert(window.id)
+            {
+                return Ok(window.id);
+            }
+        }
+        Err(Error::Timeout)
+    }
+}
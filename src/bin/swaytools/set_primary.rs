@@ -0,0 +1,56 @@
+//! Choosing the primary output, which sway itself has no notion of.
+//!
+//! The primary output and its fallbacks are kept in the state file. Wherever an output can be
+//! given, e.g., `workspace 3 primary`, `ws focus --output secondary 3`, `workspaces-to-outputs
+//! primary:1-5`, or `swaytools float --output left-of-primary center`, the first connected one of
+//! them is the primary output. Connected outputs are stored by make, model, and serial number, so
+//! the choice survives plugging a monitor into another port.
+
+use swaytools::{
+    error::{Error, Fallible},
+    output_identifier,
+    state::StateFile,
+    Connection, RelativeOutput,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The primary output followed by fallbacks, by name or identifier; prints the current
+    /// primary output if none are given
+    outputs: Vec<String>,
+
+    /// Forget the primary output, so the top left output is the primary one
+    #[arg(long, conflicts_with = "outputs")]
+    clear: bool,
+}
+
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    let state = StateFile::open_default();
+    let outputs = sway.get_outputs()?;
+    if args.outputs.is_empty() && !args.clear {
+        let primary = state.load()?.primary;
+        if let Some(name) = RelativeOutput::Primary.resolve(&outputs, &primary) {
+            println!("{name}");
+        }
+        return Ok(());
+    }
+
+    let mut primary = Vec::new();
+    for output in args.outputs {
+        if RelativeOutput::parse(&output).is_some() {
+            return Err(Error::UnexpectedArgument(output));
+        }
+        // Outputs which are not connected are kept as given, e.g., as a fallback for later.
+        let identifier = outputs
+            .iter()
+            .find(|o| o.name == output)
+            .map_or(output, output_identifier);
+        primary.push(identifier);
+    }
+    if dry_run {
+        println!("Primary outputs: {primary:?}");
+        return Ok(());
+    }
+    state.update(|state| state.primary = primary)
+}
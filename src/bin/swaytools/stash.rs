@@ -0,0 +1,128 @@
+//! Hiding all windows in the scratchpad and putting them back exactly where they were.
+//!
+//! `stash` saves the layout of the focused workspace (or all visible workspaces) like a session
+//! in the state file and moves its windows to the scratchpad. `unstash` puts the windows back into
+//! the saved layout as `session restore` does, but takes them from the scratchpad instead of
+//! launching them, and focuses the window which was focused before. Stashing again before
+//! unstashing adds to the stash.
+
+use crate::session::{self, Restorer, Session};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+use swayipc::NodeType;
+use swaytools::{
+    error::Fallible,
+    state::StateFile,
+    tree::{self, Location},
+    Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Move all windows of the focused workspace to the scratchpad
+    Stash {
+        /// Stash the windows of all visible workspaces
+        #[arg(short, long)]
+        all: bool,
+    },
+    /// Move the stashed windows back to their places
+    Unstash,
+}
+
+/// The name the stash is kept under in the state file.
+const TOOL: &str = "stash";
+
+/// The stashed windows as kept in the state file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Stash {
+    session: Session,
+    /// The window which was focused when stashing.
+    focused: Option<i64>,
+}
+
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    match args.command {
+        Command::Stash { all } => stash(all, &mut sway),
+        Command::Unstash => unstash(&mut sway),
+    }
+}
+
+/// Saves the layout of the focused or all visible workspaces and moves their windows to the
+/// scratchpad.
+fn stash(all: bool, sway: &mut Connection) -> Fallible<()> {
+    let visible: Vec<String> = sway
+        .get_workspaces()?
+        .into_iter()
+        .filter(|ws| if all { ws.visible } else { ws.focused })
+        .map(|ws| ws.name)
+        .collect();
+    let tree = sway.get_tree()?;
+    let workspaces: Vec<Location> = tree::walk(&tree)
+        .into_iter()
+        .filter(|location| location.node.node_type == NodeType::Workspace)
+        .filter(|location| {
+            location
+                .workspace_name()
+                .is_some_and(|name| visible.iter().any(|v| v == name))
+        })
+        .collect();
+
+    let state = StateFile::open_default();
+    let mut stash: Stash = state.load()?.tool(TOOL)?.unwrap_or_default();
+    if stash.focused.is_none() {
+        stash.focused = tree::find(&tree, |node| node.focused)
+            .filter(Location::is_window)
+            .map(|location| location.node.id);
+    }
+    let mut commands = Vec::new();
+    for location in &workspaces {
+        let Some(saved) = session::save_workspace(location, true) else {
+            continue;
+        };
+        stash.session.workspaces.push(saved);
+        commands.extend(
+            tree::windows(location.node)
+                .iter()
+                .map(|window| format!("[con_id={}] move scratchpad", window.node.id)),
+        );
+    }
+    if commands.is_empty() {
+        return Ok(());
+    }
+    sway.run(commands.join("; "))?;
+    if sway.dry_run() {
+        return Ok(());
+    }
+    let stash = serde_json::to_value(&stash)?;
+    state.update(|state| {
+        state.tools.insert(TOOL.to_owned(), stash);
+    })
+}
+
+/// Moves the stashed windows back into their saved layout and forgets the stash.
+fn unstash(sway: &mut Connection) -> Fallible<()> {
+    let state = StateFile::open_default();
+    let Some(stash): Option<Stash> = state.load()?.tool(TOOL)? else {
+        return Ok(());
+    };
+    Restorer::new(sway, HashMap::new(), Duration::ZERO)?.restore(&stash.session)?;
+    // The focused window may have been closed meanwhile.
+    if let Some(id) = stash.focused {
+        if tree::find_by_id(&sway.get_tree()?, id).is_some() {
+            sway.run(format!("[con_id={id}] focus"))?;
+        }
+    }
+    if sway.dry_run() {
+        return Ok(());
+    }
+    state.update(|state| {
+        state.tools.remove(TOOL);
+    })
+}
@@ -0,0 +1,154 @@
+//! Floating windows which stay visible on every workspace of their output.
+//!
+//! The windows made sticky with `stick` are stored as pinned windows in the state file. `watch`
+//! makes them sticky again and moves them back onto their output whenever outputs change, as
+//! sway may drop or misplace sticky windows when their output is reconfigured or disconnected.
+
+use clap::Subcommand;
+use swayipc::{Event, EventType, WindowChange};
+use swaytools::{
+    error::{Error, Fallible},
+    events::{EventStream, StreamEvent},
+    state::StateFile,
+    tree::{self, Location},
+    Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Make the focused (or given) window floating and sticky
+    Stick {
+        #[arg(long)]
+        con_id: Option<i64>,
+    },
+    /// Make the focused (or given) window a normal floating window again
+    Unstick {
+        #[arg(long)]
+        con_id: Option<i64>,
+    },
+    /// List the sticky windows
+    List,
+    /// Keep the sticky windows sticky and on their outputs when outputs change
+    Watch,
+}
+
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    let state = StateFile::open_default();
+    match args.command {
+        Command::Stick { con_id } => {
+            let id = window(con_id, &mut sway)?;
+            sway.run(format!("[con_id={id}] floating enable, sticky enable"))?;
+            if !dry_run {
+                state.update(|state| {
+                    if !state.pinned.contains(&id) {
+                        state.pinned.push(id);
+                    }
+                })?;
+            }
+            Ok(())
+        }
+        Command::Unstick { con_id } => {
+            let id = window(con_id, &mut sway)?;
+            sway.run(format!("[con_id={id}] sticky disable"))?;
+            if !dry_run {
+                state.update(|state| state.pinned.retain(|&pinned| pinned != id))?;
+            }
+            Ok(())
+        }
+        Command::List => {
+            let tree = sway.get_tree()?;
+            for location in tree::windows(&tree) {
+                if location.node.sticky {
+                    println!("{}", crate::picker::describe(&location));
+                }
+            }
+            Ok(())
+        }
+        Command::Watch => watch(&state, &mut sway, dry_run),
+    }
+}
+
+/// Returns the window with the id `con_id` or the focused window.
+fn window(con_id: Option<i64>, sway: &mut Connection) -> Fallible<i64> {
+    let tree = sway.get_tree()?;
+    let location = match con_id {
+        Some(id) => tree::find_by_id(&tree, id),
+        None => tree::find(&tree, |node| node.focused),
+    };
+    location
+        .filter(Location::is_window)
+        .map(|location| location.node.id)
+        .ok_or_else(|| match con_id {
+            Some(id) => Error::WindowNotFound(format!("con_id {id}")),
+            None => Error::WindowNotFound("focus".to_owned()),
+        })
+}
+
+/// Makes the pinned windows sticky again and moves them onto their output.
+fn restore(state: &StateFile, sway: &mut Connection) -> Fallible<()> {
+    let pinned = state.load()?.pinned;
+    let tree = sway.get_tree()?;
+    let commands: Vec<String> = pinned
+        .iter()
+        .filter_map(|&id| tree::find_by_id(&tree, id))
+        .map(|location| {
+            let node = location.node;
+            let mut command = format!("[con_id={}] floating enable, sticky enable", node.id);
+            let outside = location
+                .output
+                .map(|output| &output.rect)
+                .is_some_and(|output| {
+                    node.rect.x < output.x
+                        || node.rect.y < output.y
+                        || node.rect.x + node.rect.width > output.x + output.width
+                        || node.rect.y + node.rect.height > output.y + output.height
+                });
+            if outside {
+                command.push_str(", move position center");
+            }
+            command
+        })
+        .collect();
+    if commands.is_empty() {
+        return Ok(());
+    }
+    sway.run(commands.join("; "))
+}
+
+/// Restores the sticky windows whenever outputs change and forgets closed ones, a dry run
+/// keeps the state file untouched.
+fn watch(state: &StateFile, sway: &mut Connection, dry_run: bool) -> Fallible<()> {
+    let events = EventStream::subscribe(&[EventType::Output, EventType::Window])?;
+    restore(state, sway)?;
+    for event in events {
+        let result = match event {
+            StreamEvent::Event(event) => match *event {
+                Event::Output(_) => restore(state, sway),
+                Event::Window(event) if event.change == WindowChange::Close && !dry_run => {
+                    let id = event.container.id;
+                    state.update(|state| state.pinned.retain(|&pinned| pinned != id))
+                }
+                _ => continue,
+            },
+            // Window ids do not survive a restart of sway.
+            StreamEvent::Reconnected => Connection::new(dry_run).and_then(|connection| {
+                *sway = connection;
+                if dry_run {
+                    return Ok(());
+                }
+                state.update(|state| state.pinned.clear())
+            }),
+        };
+        if let Err(err) = result {
+            eprintln!("Cannot restore the sticky windows: {err}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,147 @@
+//! Jumping to urgent windows, including those whose urgency was already dismissed.
+//!
+//! `swaytools urgent-window watch` records the windows which become urgent in the state file,
+//! `swaytools urgent-window focus` focuses the urgent window which became urgent first (or last)
+//! across all workspaces, or lets the user pick one of the recorded windows, urgent or not anymore.
+
+use crate::{
+    marks,
+    picker::{self, DEFAULT_PICKER},
+};
+use clap::Subcommand;
+use std::time::{SystemTime, UNIX_EPOCH};
+use swayipc::{Event, EventType, WindowChange};
+use swaytools::{
+    error::{Error, Fallible},
+    events::{EventStream, StreamEvent},
+    state::StateFile,
+    tree::{self, Location},
+    Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Record the windows which become urgent
+    Watch,
+    /// Focus the window which became urgent first
+    Focus {
+        /// Focus the window which became urgent last instead
+        #[arg(short, long)]
+        newest: bool,
+
+        /// Pick one of the recorded windows with this dmenu-compatible command instead
+        #[arg(short, long, value_name = "COMMAND", num_args = 0..=1, default_missing_value = DEFAULT_PICKER)]
+        picker: Option<String>,
+    },
+}
+
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    match args.command {
+        Command::Watch => watch(dry_run),
+        Command::Focus { newest, picker } => focus(newest, picker.as_deref(), dry_run),
+    }
+}
+
+/// Records every window which becomes urgent and forgets closed ones, a dry run only prints them.
+fn watch(dry_run: bool) -> Fallible<()> {
+    let state = StateFile::open_default();
+    for event in EventStream::subscribe(&[EventType::Window])? {
+        let StreamEvent::Event(event) = event else {
+            continue;
+        };
+        let Event::Window(event) = *event else {
+            continue;
+        };
+        let id = event.container.id;
+        let result = match event.change {
+            WindowChange::Urgent if event.container.urgent => record(&state, id, true, dry_run),
+            WindowChange::Close => record(&state, id, false, dry_run),
+            _ => continue,
+        };
+        if let Err(err) = result {
+            eprintln!("Cannot record the urgent windows: {err}");
+        }
+    }
+    Ok(())
+}
+
+/// Returns the seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Records the window `id` as urgent or closed in the `state`.
+fn record(state: &StateFile, id: i64, urgent: bool, dry_run: bool) -> Fallible<()> {
+    if dry_run {
+        println!("{} window {id}", if urgent { "Urgent" } else { "Closed" });
+        return Ok(());
+    }
+    state.update(|state| {
+        if urgent {
+            state.push_urgent(id, now());
+        } else {
+            state.urgent.retain(|window| window.id != id);
+        }
+    })
+}
+
+/// Focuses the urgent window which became urgent first (or last with `newest`) or the recorded
+/// window chosen with `picker`.
+///
+/// Urgent windows which were not recorded count as older than all recorded ones.
+fn focus(newest: bool, picker: Option<&str>, dry_run: bool) -> Fallible<()> {
+    let history = StateFile::open_default().load()?.urgent;
+    let mut sway = Connection::new(dry_run)?;
+    let tree = sway.get_tree()?;
+    let recorded: Vec<(Location, u64)> = history
+        .iter()
+        .filter_map(|window| Some((tree::find_by_id(&tree, window.id)?, window.since)))
+        .collect();
+
+    if let Some(picker) = picker {
+        let now = now();
+        let entries: Vec<String> = recorded
+            .iter()
+            .map(|(location, since)| {
+                let urgent = if location.node.urgent {
+                    ", still urgent"
+                } else {
+                    ""
+                };
+                let minutes = now.saturating_sub(*since) / 60;
+                format!("{} ({minutes} min ago{urgent})", picker::describe(location))
+            })
+            .collect();
+        return match picker::pick(picker, &entries)? {
+            Some(index) => marks::focus(&recorded[index].0, &mut sway),
+            None => Ok(()),
+        };
+    }
+
+    let mut urgent: Vec<Location> = tree::windows(&tree)
+        .into_iter()
+        .filter(|location| location.node.urgent)
+        .collect();
+    // Most recent first, like the history.
+    urgent.sort_by_key(|location| {
+        history
+            .iter()
+            .position(|window| window.id == location.node.id)
+            .unwrap_or(usize::MAX)
+    });
+    let location = if newest {
+        urgent.first()
+    } else {
+        urgent.last()
+    };
+    let location = location.ok_or_else(|| Error::WindowNotFound("urgent".to_owned()))?;
+    marks::focus(location, &mut sway)
+}
@@ -0,0 +1,166 @@
+//! Switching the background of an output to the wallpaper of its visible workspace.
+//!
+//! The wallpaper file maps workspace names, numbers, and ranges (as in the mapping syntax) to
+//! images, where a name takes precedence over a number:
+//!
+//! ```toml
+//! default = "~/pictures/default.png"
+//! mode = "fill"
+//!
+//! [workspace]
+//! "1-3" = "~/pictures/work.png"
+//! "4,6" = "~/pictures/chat.png"
+//! music = "~/pictures/music.jpg"
+//! ```
+//!
+//! The backgrounds are set with `output … bg`. The wallpaper of each output is remembered, so an
+//! output whose new workspace has the same wallpaper is left alone instead of flickering.
+
+use clap::ValueHint;
+use serde::Deserialize;
+use std::{collections::HashMap, fs};
+use swayipc::{Event, EventType, Workspace, WorkspaceChange};
+use swaytools::{
+    env,
+    error::Fallible,
+    events::{EventStream, StreamEvent},
+    mapping::parse_workspaces,
+    Connection,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The TOML file with the wallpapers [default: $XDG_CONFIG_HOME/swaytools/wallpaper.toml]
+    #[arg(short, long, value_hint = ValueHint::FilePath)]
+    config: Option<String>,
+}
+
+/// The contents of a wallpaper file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WallpaperFile {
+    /// The wallpaper of workspaces without their own.
+    default: Option<String>,
+    /// How the images are scaled, see `output … bg` in sway-output(5).
+    #[serde(default = "default_mode")]
+    mode: String,
+    #[serde(default)]
+    workspace: HashMap<String, String>,
+}
+
+fn default_mode() -> String {
+    "fill".to_owned()
+}
+
+/// The wallpapers by workspace.
+struct Wallpapers {
+    default: Option<String>,
+    mode: String,
+    by_name: HashMap<String, String>,
+    by_number: Vec<(Vec<i32>, String)>,
+}
+
+impl Wallpapers {
+    /// Loads the wallpapers from the TOML file at `path`.
+    ///
+    /// Keys which are no valid list of numbers and ranges are taken as workspace names.
+    fn load(path: &str) -> Fallible<Wallpapers> {
+        let file: WallpaperFile = toml::from_str(&fs::read_to_string(path)?)?;
+        let mut wallpapers = Wallpapers {
+            default: file.default,
+            mode: file.mode,
+            by_name: HashMap::new(),
+            by_number: Vec::new(),
+        };
+        for (key, image) in file.workspace {
+            if let Ok(numbers) = parse_workspaces(&key, 0, &key) {
+                wallpapers.by_number.push((numbers, image.clone()));
+            }
+            wallpapers.by_name.insert(key, image);
+        }
+        Ok(wallpapers)
+    }
+
+    /// Returns the wallpaper of `workspace`.
+    fn image(&self, workspace: &Workspace) -> Option<&String> {
+        self.by_name
+            .get(&workspace.name)
+            .or_else(|| {
+                self.by_number
+                    .iter()
+                    .find(|(numbers, _)| numbers.contains(&workspace.num))
+                    .map(|(_, image)| image)
+            })
+            .or(self.default.as_ref())
+    }
+}
+
+/// Sets the wallpapers of the visible workspaces on outputs whose background differs from it.
+///
+/// `current` holds the wallpaper set on each output and is updated accordingly.
+fn apply(
+    wallpapers: &Wallpapers,
+    current: &mut HashMap<String, String>,
+    sway: &mut Connection,
+) -> Fallible<()> {
+    let workspaces = sway.get_workspaces()?;
+    // Forget outputs which were disconnected, they start without a wallpaper when they return.
+    current.retain(|output, _| workspaces.iter().any(|ws| ws.output == *output));
+    let mut commands = Vec::new();
+    for workspace in workspaces.iter().filter(|workspace| workspace.visible) {
+        let Some(image) = wallpapers.image(workspace) else {
+            continue;
+        };
+        if current.get(&workspace.output) == Some(image) {
+            continue;
+        }
+        commands.push(format!(
+            "output '{}' bg '{image}' {}",
+            workspace.output, wallpapers.mode
+        ));
+        current.insert(workspace.output.clone(), image.clone());
+    }
+    if commands.is_empty() {
+        return Ok(());
+    }
+    sway.run(commands.join("; "))
+}
+
+/// Sets the wallpapers now and whenever another workspace becomes visible until sway exits.
+pub fn run(args: Args, dry_run: bool) -> Fallible<()> {
+    let path = args.config.unwrap_or_else(|| {
+        env::config_file("wallpaper.toml")
+            .to_string_lossy()
+            .into_owned()
+    });
+    let wallpapers = Wallpapers::load(&path)?;
+    let mut sway = Connection::new(dry_run)?;
+    // Subscribe before the first apply so that no workspace change is missed in between.
+    let events = EventStream::subscribe(&[EventType::Workspace, EventType::Output])?;
+    let mut current = HashMap::new();
+    apply(&wallpapers, &mut current, &mut sway)?;
+
+    for event in events {
+        let result = match event {
+            StreamEvent::Event(event) => match *event {
+                Event::Workspace(event)
+                    if matches!(event.change, WorkspaceChange::Focus | WorkspaceChange::Move) =>
+                {
+                    apply(&wallpapers, &mut current, &mut sway)
+                }
+                Event::Output(_) => apply(&wallpapers, &mut current, &mut sway),
+                _ => continue,
+            },
+            // sway may have been restarted with the backgrounds of its config.
+            StreamEvent::Reconnected => Connection::new(dry_run).and_then(|connection| {
+                sway = connection;
+                current.clear();
+                apply(&wallpapers, &mut current, &mut sway)
+            }),
+        };
+        if let Err(err) = result {
+            eprintln!("Cannot set the wallpapers: {err}");
+        }
+    }
+    Ok(())
+}
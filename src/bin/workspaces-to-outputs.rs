@@ -1,83 +1,158 @@
 use clap::Parser;
-use std::collections::{HashMap, HashSet};
-use swayipc::Connection;
-use swaytools::{make_config, save_config};
+use std::fs;
+use swayipc::{Event, EventType};
+use swaytools::{
+    assign_workspaces, env,
+    error::{ErrorFormat, Fallible},
+    events::{EventStream, StreamEvent},
+    load_config, make_config,
+    mapping_config::MappingConfig,
+    move_workspaces, prune_config, save_config, Connection,
+};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct MappingCli {
-    /// An output workspace mapping in the form "output:number" or "output:from-to", e.g., VGA-1:1-10 or "Dell X2353 0x2342:22"
+    /// An output workspace mapping in the form "output:workspaces" where workspaces is a comma-separated list of numbers and ranges "from-to", e.g., VGA-1:1-10, DP-1:1-3,7,9 or "Dell X2353 0x2342:22"; the output may also be primary, secondary, left-of-primary, or right-of-primary
+    #[arg(required_unless_present_any = ["restore", "prune", "config", "from_sway_config"])]
     mapping: Vec<String>,
+
+    /// Read the mapping from this TOML file instead of mapping arguments.
+    #[arg(short, long, conflicts_with_all = ["mapping", "restore"], value_hint = clap::ValueHint::FilePath)]
+    config: Option<String>,
+
+    /// Use the "workspace N output OUTPUTS" assignments of this sway config file (or the running
+    /// sway's config) as the mapping.
+    #[arg(long, value_name = "PATH", num_args = 0..=1, conflicts_with_all = ["mapping", "restore", "config"], value_hint = clap::ValueHint::FilePath)]
+    from_sway_config: Option<Option<String>>,
+
+    /// Apply the previously saved mapping instead of mapping arguments [env: SWAYTOOLS_MAPPING_FILE].
+    #[arg(short, long, conflicts_with = "mapping")]
+    restore: bool,
+
+    /// Remove outputs which are not connected from the saved mapping, combine with --restore to
+    /// apply the pruned mapping afterwards.
+    #[arg(short, long, conflicts_with = "mapping")]
+    prune: bool,
+
+    /// Also assign the workspaces to their outputs in sway so that workspaces created later are
+    /// opened on their outputs.
+    #[arg(short, long)]
+    assign: bool,
+
+    /// Only assign the workspaces to their outputs without moving existing workspaces.
+    #[arg(long, requires = "assign")]
+    no_move: bool,
+
+    /// Apply the mapping even if it maps an output or a workspace more than once.
+    #[arg(short, long)]
+    force: bool,
+
+    /// Only show commands instead of executing them [env: SWAYTOOLS_DRY_RUN].
+    #[arg(short = 'n', long)]
+    dry_run: bool,
+
+    /// Keep running after applying the mapping and apply it again whenever outputs change.
+    #[arg(short, long)]
+    watch: bool,
+
+    /// How errors are reported [env: SWAYTOOLS_ERRORS].
+    #[arg(long, value_enum)]
+    errors: Option<ErrorFormat>,
 }
 
 fn main() {
     let cli = MappingCli::parse();
-    let mut sway = Connection::new().expect("Cannot connect to sway via IPC.");
-
-    // Create a configuration mapping from the mapping strings on the command line.
-    let config = make_config(cli.mapping, &mut sway);
-    // Save the configuration to a file.
-    save_config(&config);
-    // Actually move the workspaces according to the configuration.
-    move_workspaces(&config, &mut sway)
+    let errors = ErrorFormat::resolve(cli.errors);
+    if let Err(err) = run(cli) {
+        errors.exit(&err);
+    }
 }
 
-/// Move all workspaces in `mappings` to the correct outputs.
-///
-/// `mappings` is a mapping from output (e.g., `VGA-1`) to a list of workspaces
-/// to be shown on this output.
-fn move_workspaces(mappings: &HashMap<String, Vec<i32>>, sway: &mut Connection) {
-    // Take a copy of all outputs to ensure that even on outputs which do not
-    // have workspaces to show anything, a correct workspace is shown.
-    let mut empty_outputs: HashSet<&String> = HashSet::from_iter(mappings.keys());
-    // We want to now which workspace was focused to be able to focus it after
-    // moving the workspaces.
-    let mut focused_ws: Option<i32> = None;
-
-    for ws in sway.get_workspaces().unwrap_or_default() {
-        // Store the focused workspace
-        if ws.focused {
-            focused_ws = Some(ws.num);
+fn run(cli: MappingCli) -> Fallible<()> {
+    let dry_run = env::dry_run(cli.dry_run);
+    if cli.prune {
+        prune(dry_run)?;
+        if !cli.restore {
+            return Ok(());
         }
+    }
+    // Subscribe before the first apply so that no output change is missed in between.
+    let events = if cli.watch {
+        Some(EventStream::subscribe(&[EventType::Output])?)
+    } else {
+        None
+    };
 
-        for (output, workspaces) in mappings.iter() {
-            // Skip output if it should not display the current workspace.
-            if !workspaces.contains(&ws.num) {
-                continue;
-            }
-            // We move a workspace to this output, remove it from the list of
-            // empty outputs.
-            empty_outputs.remove(output);
-            // The workspace is already on the correct output, don't do anything.
-            if &ws.output == output {
-                break;
-            }
+    apply(&cli, dry_run)?;
 
-            // 1. Select the workspace.
-            // 2. Move the workspace to the desired output.
-            sway.run_command(format!(
-                "workspace --no-auto-back-and-forth number {}, move workspace to output '{}'",
-                ws.num, output
-            ))
-            .expect("Cannot move workspace to output.");
+    for event in events.into_iter().flatten() {
+        match event {
+            StreamEvent::Event(event) if matches!(*event, Event::Output(_)) => (),
+            // Outputs may have changed while sway was unreachable
+            StreamEvent::Reconnected => (),
+            StreamEvent::Event(_) => continue,
+        }
+        if let Err(err) = apply(&cli, dry_run) {
+            eprintln!("Cannot re-apply the workspace mapping: {err}");
         }
     }
+    Ok(())
+}
 
-    // Go through all outputs which have no workspace on them.
-    for output in empty_outputs.into_iter() {
-        // Get the first workspace in the assigned list of workspaces for the
-        // output and display this workspace on the output.
-        mappings
-            .get(output)
-            .and_then(|workspaces| workspaces.first())
-            .map(|num| {
-                sway.run_command(&format!("workspace --no-auto-back-and-forth number {num}, move workspace to output '{output}'"))
-            });
+/// Removes outputs which are not connected from the saved configuration, which is left untouched
+/// in dry-run mode.
+fn prune(dry_run: bool) -> Fallible<()> {
+    let mut sway = Connection::new(dry_run)?;
+    let mut config = load_config()?;
+    let pruned = prune_config(&mut config, &mut sway)?;
+    if !dry_run {
+        save_config(&config)?;
+    }
+    for output in pruned {
+        eprintln!("Removing the mapping of disconnected output '{output}'");
     }
+    Ok(())
+}
 
-    // Focus the previously focused workspace.
-    if let Some(ws) = focused_ws {
-        sway.run_command(format!("workspace --no-auto-back-and-forth number {ws}"))
-            .expect("Cannot switch back to focused workspace.");
+/// Applies the mapping of `cli` to the currently connected outputs, saving the resulting
+/// configuration unless it was restored or only shown in dry-run mode.
+fn apply(cli: &MappingCli, dry_run: bool) -> Fallible<()> {
+    // A fresh connection as sway may have been restarted since the last apply.
+    let mut sway = Connection::new(dry_run)?;
+
+    let config = if cli.restore {
+        // Load the saved configuration, skipping outputs which are not connected.
+        let outputs = sway.get_outputs()?;
+        let mut config = load_config()?;
+        config.retain(|output, _| outputs.iter().any(|o| &o.name == output));
+        config
+    } else {
+        // Create a configuration mapping from the mapping file or the mapping strings on the
+        // command line.
+        let config = match (&cli.config, &cli.from_sway_config) {
+            (Some(path), _) => MappingConfig::load(path)?.resolve(cli.force, &mut sway)?,
+            (None, Some(path)) => {
+                let sway_config = match path {
+                    Some(path) => fs::read_to_string(path)?,
+                    None => sway.get_config()?.config,
+                };
+                MappingConfig::from_sway_config(&sway_config).resolve(cli.force, &mut sway)?
+            }
+            (None, None) => make_config(cli.mapping.clone(), cli.force, &mut sway)?,
+        };
+        // Save the configuration to a file, a dry run only previews the commands.
+        if !dry_run {
+            save_config(&config)?;
+        }
+        config
+    };
+    if cli.assign {
+        assign_workspaces(&config, &mut sway)?;
     }
+    if cli.no_move {
+        return Ok(());
+    }
+    // Actually move the workspaces according to the configuration.
+    move_workspaces(&config, &mut sway)
 }
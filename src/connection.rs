@@ -0,0 +1,326 @@
+//! A connection to sway which can show commands instead of executing them.
+
+use crate::{
+    env,
+    error::{Error, Fallible},
+};
+use itertools::Itertools;
+use std::{
+    fs,
+    ops::{Deref, DerefMut},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    process::Command,
+    time::Duration,
+};
+
+/// The default format of commands shown in dry-run mode.
+pub const DEFAULT_DRY_RUN_FORMAT: &str = "SWAY: \u{1b}[1;34m{command}\u{1b}[0m";
+
+/// How long to wait for a reply from sway unless overridden by `$SWAYTOOLS_IPC_TIMEOUT`.
+pub const DEFAULT_IPC_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connects to sway and waits at most [`env::ipc_timeout`] for each reply.
+///
+/// If sway does not reply in time, calls fail with [`Error::Timeout`] instead of blocking forever.
+/// The connection should not be used anymore afterwards as a late reply may still arrive.
+pub fn connect() -> Fallible<swayipc::Connection> {
+    connect_with_timeout(env::ipc_timeout())
+}
+
+/// Connects to sway and waits at most `timeout` for each reply (forever if `None`).
+pub fn connect_with_timeout(timeout: Option<Duration>) -> Fallible<swayipc::Connection> {
+    set_timeout(open()?, timeout)
+}
+
+/// Connects to the socket of sway without any timeout.
+///
+/// If `$SWAYSOCK` refers to a socket of a sway instance which is not running anymore (e.g.,
+/// after sway was restarted), the most recent sway socket in the runtime directory is used.
+pub fn open() -> swayipc::Fallible<swayipc::Connection> {
+    swayipc::Connection::new().or_else(|err| {
+        let stale = env::var("SWAYSOCK").map(PathBuf::from);
+        sockets()
+            .into_iter()
+            .filter(|socket| Some(socket) != stale.as_ref())
+            .find_map(|socket| UnixStream::connect(socket).ok())
+            .map(swayipc::Connection::from)
+            .ok_or(err)
+    })
+}
+
+/// Returns all sway sockets in `$XDG_RUNTIME_DIR`, the most recent one first.
+fn sockets() -> Vec<PathBuf> {
+    let Some(Ok(entries)) = env::var("XDG_RUNTIME_DIR").map(fs::read_dir) else {
+        return Vec::new();
+    };
+    entries
+        .map_while(Result::ok)
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with("sway-ipc.") && name.ends_with(".sock")
+        })
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .sorted_by(|(a, _), (b, _)| b.cmp(a))
+        .map(|(_, path)| path)
+        .collect()
+}
+
+/// The window manager commands are sent to.
+///
+/// Both speak the same protocol but differ slightly in their command dialects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Backend {
+    #[default]
+    Sway,
+    I3,
+}
+
+impl Backend {
+    /// Resolves the backend from the command line value or `$SWAYTOOLS_BACKEND`, otherwise i3 is
+    /// detected by `$I3SOCK` being set without `$SWAYSOCK` (which sway sets both).
+    pub fn resolve(cli: Option<Backend>) -> Backend {
+        cli.or_else(|| match env::var(env::BACKEND).as_deref() {
+            Some("sway") => Some(Backend::Sway),
+            Some("i3") => Some(Backend::I3),
+            _ => None,
+        })
+        .unwrap_or_else(|| match (env::var("SWAYSOCK"), env::var("I3SOCK")) {
+            (None, Some(_)) => Backend::I3,
+            _ => Backend::Sway,
+        })
+    }
+
+    /// Returns the command moving the focused container to the workspace given by `selector`.
+    pub fn move_to_workspace(&self, selector: &str) -> String {
+        match self {
+            Backend::Sway => format!("move to workspace {selector}"),
+            Backend::I3 => format!("move container to workspace {selector}"),
+        }
+    }
+
+    /// Returns the socket of this backend, asking i3 for it if `$I3SOCK` is not set.
+    fn socket(&self) -> Option<PathBuf> {
+        match self {
+            Backend::Sway => env::var("SWAYSOCK").map(PathBuf::from),
+            Backend::I3 => env::var("I3SOCK").map(PathBuf::from).or_else(|| {
+                let output = Command::new("i3").arg("--get-socketpath").output().ok()?;
+                let path = String::from_utf8(output.stdout).ok()?;
+                Some(PathBuf::from(path.trim_end()))
+            }),
+        }
+    }
+}
+
+fn set_timeout(
+    sway: swayipc::Connection,
+    timeout: Option<Duration>,
+) -> Fallible<swayipc::Connection> {
+    let stream = UnixStream::from(sway);
+    stream.set_read_timeout(timeout)?;
+    stream.set_write_timeout(timeout)?;
+    Ok(stream.into())
+}
+
+/// A connection to sway whose commands are only printed instead of executed in dry-run mode.
+///
+/// Queries like `get_workspaces` are passed through to the underlying [`swayipc::Connection`]
+/// even in dry-run mode.
+pub struct Connection {
+    sway: swayipc::Connection,
+    backend: Backend,
+    dry_run: bool,
+    dry_run_format: String,
+}
+
+impl Deref for Connection {
+    type Target = swayipc::Connection;
+
+    fn deref(&self) -> &swayipc::Connection {
+        &self.sway
+    }
+}
+
+impl DerefMut for Connection {
+    fn deref_mut(&mut self) -> &mut swayipc::Connection {
+        &mut self.sway
+    }
+}
+
+impl Connection {
+    /// Connects to sway, see [`connect`] for the timeout.
+    ///
+    /// The format of commands shown in dry-run mode is taken from `$SWAYTOOLS_DRY_RUN_FORMAT`
+    /// where `{command}` is replaced by the command.
+    pub fn new(dry_run: bool) -> Fallible<Connection> {
+        Ok(Connection {
+            sway: connect()?,
+            backend: Backend::resolve(None),
+            dry_run,
+            dry_run_format: env::var(env::DRY_RUN_FORMAT)
+                .unwrap_or_else(|| DEFAULT_DRY_RUN_FORMAT.to_owned()),
+        })
+    }
+
+    /// Sets the format of commands shown in dry-run mode where `{command}` is replaced by the
+    /// command.
+    pub fn with_dry_run_format(mut self, format: &str) -> Connection {
+        self.dry_run_format = format.to_owned();
+        self
+    }
+
+    /// Talks to `backend` using its command dialect, connecting to its socket if it is known.
+    pub fn with_backend(mut self, backend: Backend) -> Fallible<Connection> {
+        if backend != self.backend {
+            if let Some(socket) = backend.socket() {
+                let stream = UnixStream::connect(socket)?;
+                self.sway = set_timeout(stream.into(), env::ipc_timeout())?;
+            }
+            self.backend = backend;
+        }
+        Ok(self)
+    }
+
+    /// Returns the window manager commands are sent to.
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// Waits at most `timeout` for each reply from sway (forever if `None`).
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Fallible<Connection> {
+        self.sway = set_timeout(self.sway, timeout)?;
+        Ok(self)
+    }
+
+    /// Returns whether commands are only shown instead of executed.
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Runs the given command or only prints it in dry-run mode.
+    pub fn run_command<T: AsRef<str>>(
+        &mut self,
+        payload: T,
+    ) -> Fallible<Vec<swayipc::Fallible<()>>> {
+        if self.dry_run {
+            println!(
+                "{}",
+                self.dry_run_format.replace("{command}", payload.as_ref())
+            );
+            Ok(Vec::new())
+        } else {
+            Ok(self.sway.run_command(payload)?)
+        }
+    }
+
+    /// Runs the given command and fails if any part of it failed.
+    pub fn run<T: AsRef<str>>(&mut self, payload: T) -> Fallible<()> {
+        for outcome in self.run_command(payload)? {
+            outcome?;
+        }
+        Ok(())
+    }
+
+    /// Runs the given command and appends the messages of all failed parts to `failures`.
+    ///
+    /// Only fails if the command could not be sent at all.
+    pub fn run_tracked<T: AsRef<str>>(
+        &mut self,
+        payload: T,
+        failures: &mut Vec<String>,
+    ) -> Fallible<()> {
+        for outcome in self.run_command(payload)? {
+            if let Err(err) = outcome {
+                failures.push(err.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn workspace(&mut self, num: Option<i32>, name: Option<&str>) -> Fallible<()> {
+        if let Some(num) = num {
+            if let Some(name) = name {
+                let payload = format!("workspace number {num}:{name}");
+                self.run(payload)
+            } else {
+                self.workspace_num(num)
+            }
+        } else if let Some(name) = name {
+            self.workspace_name(name)
+        } else {
+            Err(Error::NeitherNumNorNameProvided)
+        }
+    }
+
+    pub fn workspace_num(&mut self, num: i32) -> Fallible<()> {
+        self.run(format!("workspace number {num}"))
+    }
+
+    pub fn workspace_name(&mut self, name: &str) -> Fallible<()> {
+        self.run(format!("workspace {name}"))
+    }
+
+    pub fn move_to_workspace(&mut self, num: Option<i32>, name: Option<&str>) -> Fallible<()> {
+        if let Some(num) = num {
+            if let Some(name) = name {
+                let selector = format!("number {num}:{name}");
+                self.run(self.backend.move_to_workspace(&selector))
+            } else {
+                self.move_to_workspace_num(num)
+            }
+        } else if let Some(name) = name {
+            self.move_to_workspace_name(name)
+        } else {
+            Err(Error::NeitherNumNorNameProvided)
+        }
+    }
+
+    pub fn move_to_workspace_num(&mut self, num: i32) -> Fallible<()> {
+        self.run(self.backend.move_to_workspace(&format!("number {num}")))
+    }
+
+    pub fn move_to_workspace_name(&mut self, name: &str) -> Fallible<()> {
+        self.run(self.backend.move_to_workspace(name))
+    }
+
+    pub fn move_workspace_to_output(&mut self, output: &str) -> Fallible<()> {
+        self.run(format!("move workspace to output {output}"))
+    }
+
+    pub fn focus_output(&mut self, name: &str) -> Fallible<()> {
+        self.run(format!("focus output {name}"))
+    }
+
+    pub fn mark_add(&mut self, mark: &str) -> Fallible<()> {
+        self.run(format!("mark --add {mark}"))
+    }
+
+    pub fn mark_remove(&mut self, mark: &str) -> Fallible<()> {
+        self.run(format!("unmark {mark}"))
+    }
+
+    pub fn mark_remove_all(&mut self) -> Fallible<()> {
+        self.run("unmark")
+    }
+
+    pub fn get_workspace_with_mark(
+        &mut self,
+        mark: &str,
+    ) -> Fallible<(i32, String, usize, String)> {
+        let tree = self.sway.get_tree()?;
+        for output in tree.nodes {
+            for workspace in output.nodes {
+                for window in workspace.nodes.iter() {
+                    if window.marks.iter().any(|m| m == mark) {
+                        let ws_num = workspace.num.ok_or(Error::UnexpectedTree)?;
+                        let ws_name = workspace.name.ok_or(Error::UnexpectedTree)?;
+                        let output_name = output.name.ok_or(Error::UnexpectedTree)?;
+                        return Ok((ws_num, ws_name, workspace.nodes.len(), output_name));
+                    }
+                }
+            }
+        }
+        Err(Error::MarkNotFound)
+    }
+}
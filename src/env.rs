@@ -0,0 +1,138 @@
+//! Environment variable overrides shared by all binaries.
+//!
+//! Every setting follows the same precedence: an explicitly passed command line value wins over
+//! the environment variable, which in turn wins over the built-in default.
+
+use crate::connection::DEFAULT_IPC_TIMEOUT;
+use std::{env, path::PathBuf, time::Duration};
+
+/// Overrides the file all state is stored in.
+pub const STATE_FILE: &str = "SWAYTOOLS_STATE_FILE";
+/// Shares the default state files between all sway sessions if set to a truthy value.
+pub const SHARED_STATE: &str = "SWAYTOOLS_SHARED_STATE";
+/// Overrides the file for the output-to-workspace mapping.
+pub const MAPPING_FILE: &str = "SWAYTOOLS_MAPPING_FILE";
+/// Overrides the file where the last active workspace is stored.
+pub const PREVIOUS_FILE: &str = "SWAYTOOLS_PREVIOUS_FILE";
+/// Overrides the directory in which all state files are stored by default.
+pub const RUNTIME_DIR: &str = "SWAYTOOLS_RUNTIME_DIR";
+/// Only show commands instead of executing them if set to a truthy value.
+pub const DRY_RUN: &str = "SWAYTOOLS_DRY_RUN";
+/// Selects the seat whose focus is used, either a seat name or `keyboard:<identifier>`.
+pub const SEAT: &str = "SWAYTOOLS_SEAT";
+/// Selects how errors are reported, either `text` or `json`.
+pub const ERRORS: &str = "SWAYTOOLS_ERRORS";
+/// Overrides the format of commands shown in dry-run mode.
+pub const DRY_RUN_FORMAT: &str = "SWAYTOOLS_DRY_RUN_FORMAT";
+/// Selects the window manager, either `sway` or `i3`.
+pub const BACKEND: &str = "SWAYTOOLS_BACKEND";
+/// Overrides the chain of fallbacks for the output of new workspaces, e.g., `mapping,right,focused`.
+pub const OUTPUT_FALLBACK: &str = "SWAYTOOLS_OUTPUT_FALLBACK";
+/// Overrides how many seconds to wait for a reply from sway, `0` waits forever.
+pub const IPC_TIMEOUT: &str = "SWAYTOOLS_IPC_TIMEOUT";
+
+/// Returns the value of the environment variable `name` if it is set and not empty.
+pub fn var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Returns whether the environment variable `name` is set to a truthy value, i.e., one of `1`,
+/// `true`, `yes`, or `on` (ignoring case).
+pub fn flag(name: &str) -> bool {
+    var(name)
+        .is_some_and(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+}
+
+/// Returns the directory in which state files are stored by default.
+///
+/// This is `$SWAYTOOLS_RUNTIME_DIR` if set, otherwise `$XDG_RUNTIME_DIR` and `/tmp` as a last
+/// resort.
+pub fn runtime_dir() -> String {
+    var(RUNTIME_DIR)
+        .or_else(|| var("XDG_RUNTIME_DIR"))
+        .unwrap_or_else(|| "/tmp".to_owned())
+}
+
+/// Replaces a leading `$XDG_RUNTIME_DIR` in `path` by the actual runtime directory.
+pub fn expand_runtime_dir(path: &str) -> String {
+    match path.strip_prefix("$XDG_RUNTIME_DIR") {
+        Some(rest) => format!("{}{rest}", runtime_dir()),
+        None => path.to_owned(),
+    }
+}
+
+/// Returns the file in the runtime directory with the given `file_name`.
+pub fn runtime_file(file_name: &str) -> PathBuf {
+    [runtime_dir(), file_name.to_owned()].iter().collect()
+}
+
+/// Returns the file in the runtime directory with the given `stem` and `extension` which belongs
+/// to the current sway session.
+///
+/// Each sway instance (identified by `$SWAYSOCK`) gets its own file such that nested or parallel
+/// sessions do not interfere. Setting `$SWAYTOOLS_SHARED_STATE` disables this and uses
+/// `<stem>.<extension>` for all sessions.
+pub fn session_file(stem: &str, extension: &str) -> PathBuf {
+    match var("SWAYSOCK").filter(|_| !flag(SHARED_STATE)) {
+        Some(socket) => runtime_file(&format!("{stem}-{:016x}.{extension}", fnv1a(&socket))),
+        None => runtime_file(&format!("{stem}.{extension}")),
+    }
+}
+
+/// Returns the file with the given `file_name` in the swaytools configuration directory, i.e.,
+/// `$XDG_CONFIG_HOME/swaytools` or `~/.config/swaytools`.
+pub fn config_file(file_name: &str) -> PathBuf {
+    let config = var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_default();
+    config.join("swaytools").join(file_name)
+}
+
+/// Hashes `value` with the 64-bit FNV-1a function, which (unlike the std hashers) is stable
+/// across program runs and Rust versions.
+fn fnv1a(value: &str) -> u64 {
+    value.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Resolves a separate mapping file from the command line value or `$SWAYTOOLS_MAPPING_FILE` (in
+/// this order).
+///
+/// Returns `None` if neither is given, the mapping is then kept in the state file.
+pub fn mapping_file(cli: Option<String>) -> Option<String> {
+    resolve(cli, MAPPING_FILE)
+}
+
+/// Resolves a separate previous workspace file from the command line value or
+/// `$SWAYTOOLS_PREVIOUS_FILE` (in this order).
+///
+/// Returns `None` if neither is given, the previous workspace is then kept in the state file.
+pub fn previous_file(cli: Option<String>) -> Option<String> {
+    resolve(cli, PREVIOUS_FILE)
+}
+
+/// Returns whether commands should only be shown instead of executed, either because `cli` is
+/// set or `$SWAYTOOLS_DRY_RUN` is truthy.
+pub fn dry_run(cli: bool) -> bool {
+    cli || flag(DRY_RUN)
+}
+
+/// Returns how long to wait for a reply from sway, i.e., `$SWAYTOOLS_IPC_TIMEOUT` seconds or
+/// [`DEFAULT_IPC_TIMEOUT`] if it is not set or invalid.
+///
+/// Returns `None` if the timeout is disabled by setting it to `0`.
+pub fn ipc_timeout() -> Option<Duration> {
+    match var(IPC_TIMEOUT).and_then(|secs| secs.parse::<f64>().ok()) {
+        Some(secs) if secs <= 0.0 => None,
+        Some(secs) => Duration::try_from_secs_f64(secs).ok(),
+        None => Some(DEFAULT_IPC_TIMEOUT),
+    }
+}
+
+/// Resolves a path setting and expands a leading `$XDG_RUNTIME_DIR`.
+fn resolve(cli: Option<String>, name: &str) -> Option<String> {
+    cli.or_else(|| var(name))
+        .map(|path| expand_runtime_dir(&path))
+}
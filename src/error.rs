@@ -0,0 +1,170 @@
+//! The error type shared by the library and the binaries.
+//!
+//! Every error maps to one of the stable [`ExitCode`]s so that scripts can tell failures apart.
+//! With `--errors json` (or `$SWAYTOOLS_ERRORS=json`) errors are printed to stdout as a JSON object
+//! of the form `{"error": "ipc_unreachable", "code": 3, "message": "…"}`.
+
+use crate::env;
+use std::process::exit;
+use thiserror::Error as ThisError;
+
+pub type Fallible<T> = Result<T, Error>;
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    SerdeJson(#[from] serde_json::Error),
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+    #[error(transparent)]
+    Sway(swayipc::Error),
+    #[error("sway did not reply in time")]
+    Timeout,
+    #[error("no focused workspace exists")]
+    NoFocusedWorkspace,
+    #[error("could not get workspaces")]
+    NoWorkspaces,
+    #[error("no focused output exists")]
+    NoFocusedOutput,
+    #[error("could not get outputs")]
+    NoOutputs,
+    #[error("you must provide either num or name")]
+    NeitherNumNorNameProvided,
+    #[error("unexpected argument '{0}'")]
+    UnexpectedArgument(String),
+    #[error("previously set mark was not found")]
+    MarkNotFound,
+    #[error("tree does not return expected output")]
+    UnexpectedTree,
+    #[error("no matching seat exists")]
+    SeatNotFound,
+    #[error("no previous workspace stored")]
+    NoPreviousWorkspace,
+    #[error("state file has unsupported schema version {0}")]
+    UnsupportedStateVersion(u32),
+    #[error("workspace '{0}' does not exist")]
+    WorkspaceNotFound(String),
+    #[error("no window with {0} exists")]
+    WindowNotFound(String),
+    #[error("output '{0}' is not connected")]
+    OutputNotFound(String),
+    /// No `{0}` (e.g., `preset`) exists which fulfils `{1}` (e.g., `named 'small'`).
+    #[error("no {0} {1}")]
+    NotFound(&'static str, String),
+    #[error("invalid mapping '{}': {0}", .0.input)]
+    InvalidMapping(#[from] crate::mapping::ParseError),
+    #[error("conflicting mappings (use --force to apply anyway): {}", .0.join("; "))]
+    ConflictingMappings(Vec<String>),
+    /// The value `{1}` of the kind `{0}` (e.g., `pattern`) is invalid because of `{2}`.
+    #[error("`{1}` is an invalid {0}: {2}")]
+    Invalid(&'static str, String, String),
+    #[error("`{0}` failed: {1}")]
+    CommandFailed(String, String),
+    #[error("some commands failed: {}", .0.join("; "))]
+    PartialApply(Vec<String>),
+}
+
+impl From<swayipc::Error> for Error {
+    /// Reports a reply which did not arrive within the connection's timeout as
+    /// [`Error::Timeout`].
+    fn from(err: swayipc::Error) -> Error {
+        match err {
+            swayipc::Error::Io(err)
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                Error::Timeout
+            }
+            err => Error::Sway(err),
+        }
+    }
+}
+
+/// The exit codes of all binaries.
+///
+/// `2` is also used by clap for invalid command line arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    Failure = 1,
+    Usage = 2,
+    IpcUnreachable = 3,
+    WorkspaceNotFound = 4,
+    InvalidMapping = 5,
+    PartialApply = 6,
+    Timeout = 7,
+}
+
+impl ExitCode {
+    /// Returns the machine-readable name of the exit code.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExitCode::Failure => "failure",
+            ExitCode::Usage => "usage",
+            ExitCode::IpcUnreachable => "ipc_unreachable",
+            ExitCode::WorkspaceNotFound => "workspace_not_found",
+            ExitCode::InvalidMapping => "invalid_mapping",
+            ExitCode::PartialApply => "partial_apply",
+            ExitCode::Timeout => "timeout",
+        }
+    }
+}
+
+impl Error {
+    /// Returns the exit code a binary should terminate with because of this error.
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            Error::Sway(swayipc::Error::Io(_) | swayipc::Error::SocketNotFound) => {
+                ExitCode::IpcUnreachable
+            }
+            Error::WorkspaceNotFound(_) => ExitCode::WorkspaceNotFound,
+            Error::InvalidMapping(_) | Error::ConflictingMappings(_) => ExitCode::InvalidMapping,
+            Error::Invalid(..)
+            | Error::UnexpectedArgument(_)
+            | Error::NeitherNumNorNameProvided => ExitCode::Usage,
+            Error::PartialApply(_) => ExitCode::PartialApply,
+            Error::Timeout => ExitCode::Timeout,
+            _ => ExitCode::Failure,
+        }
+    }
+}
+
+/// How errors are reported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// A human-readable message on stderr.
+    #[default]
+    Text,
+    /// A JSON object on stdout.
+    Json,
+}
+
+impl ErrorFormat {
+    /// Resolves the error format from the command line value or `$SWAYTOOLS_ERRORS`.
+    pub fn resolve(cli: Option<ErrorFormat>) -> ErrorFormat {
+        cli.unwrap_or_else(|| match env::var(env::ERRORS).as_deref() {
+            Some("json") => ErrorFormat::Json,
+            _ => ErrorFormat::Text,
+        })
+    }
+
+    /// Reports `err` in this format and terminates the process with the matching exit code.
+    pub fn exit(&self, err: &Error) -> ! {
+        let code = err.exit_code();
+        match self {
+            ErrorFormat::Text => eprintln!("Error: {err}"),
+            ErrorFormat::Json => println!(
+                "{}",
+                serde_json::json!({
+                    "error": code.name(),
+                    "code": code as i32,
+                    "message": err.to_string(),
+                })
+            ),
+        }
+        exit(code as i32)
+    }
+}
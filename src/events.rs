@@ -0,0 +1,92 @@
+//! Event subscriptions which survive a lost connection to sway.
+
+use std::{thread, time::Duration};
+use swayipc::{Error, Event, EventType, Fallible};
+
+/// The initial delay between two reconnection attempts.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+/// The maximal delay between two reconnection attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// An item yielded by an [`EventStream`].
+#[derive(Debug)]
+pub enum StreamEvent {
+    /// An event sent by sway.
+    Event(Box<Event>),
+    /// The connection was lost and has been re-established. Events may have been missed in
+    /// between, so any cached state should be refreshed.
+    Reconnected,
+}
+
+/// A subscription to sway events which transparently reconnects and resubscribes whenever the
+/// connection to sway is lost.
+///
+/// The stream never ends: if sway is unreachable it keeps trying to reconnect with an increasing
+/// delay.
+pub struct EventStream {
+    event_types: Vec<EventType>,
+    stream: Option<swayipc::EventStream>,
+}
+
+impl EventStream {
+    /// Connects to sway and subscribes to the given event types.
+    ///
+    /// Fails if the initial connection or subscription fails so that callers notice a
+    /// misconfiguration right away.
+    pub fn subscribe(event_types: &[EventType]) -> Fallible<Self> {
+        let stream = Self::connect(event_types)?;
+        Ok(EventStream {
+            event_types: event_types.to_vec(),
+            stream: Some(stream),
+        })
+    }
+
+    fn connect(event_types: &[EventType]) -> Fallible<swayipc::EventStream> {
+        crate::connection::open()?.subscribe(event_types)
+    }
+
+    /// Blocks until a new connection and subscription could be established.
+    fn reconnect(&mut self) {
+        let mut backoff = INITIAL_BACKOFF;
+        loop {
+            thread::sleep(backoff);
+            match Self::connect(&self.event_types) {
+                Ok(stream) => {
+                    log::info!("Reconnected to sway.");
+                    self.stream = Some(stream);
+                    return;
+                }
+                Err(err) => {
+                    log::warn!("Cannot reconnect to sway: {err}");
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for EventStream {
+    type Item = StreamEvent;
+
+    fn next(&mut self) -> Option<StreamEvent> {
+        loop {
+            let Some(stream) = self.stream.as_mut() else {
+                self.reconnect();
+                return Some(StreamEvent::Reconnected);
+            };
+            match stream.next() {
+                Some(Ok(event)) => return Some(StreamEvent::Event(Box::new(event))),
+                // Events we cannot decode are skipped, the connection itself is still fine.
+                Some(Err(Error::SerdeJson(err))) => {
+                    log::warn!("Cannot decode sway event: {err}");
+                }
+                Some(Err(Error::UnimplementedEvent(..))) => (),
+                Some(Err(err)) => {
+                    log::warn!("Lost connection to sway: {err}");
+                    self.stream = None;
+                }
+                None => self.stream = None,
+            }
+        }
+    }
+}
@@ -1,131 +1,548 @@
-use serde_json::Result;
-use std::{collections::HashMap, env, fs, path::PathBuf};
-use swayipc::{Connection, Workspace};
+use connection::Backend;
+use error::{Error, ErrorFormat, Fallible};
+use state::StateFile;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+};
+use swayipc::{Output, Workspace};
 
-use clap::Parser;
+pub mod aliases;
+pub mod connection;
+pub mod env;
+pub mod error;
+pub mod events;
+pub mod manager;
+pub mod mapping;
+pub mod mapping_config;
+pub mod pattern;
+pub mod placement;
+pub mod seat;
+pub mod state;
+pub mod sway_config;
+pub mod tracker;
+pub mod tree;
+
+pub use connection::Connection;
+pub use manager::WorkspaceManager;
 
 /// The command line interface for two tools.
-#[derive(Parser, Debug)]
+#[derive(clap::Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct WorkspaceCli {
-    /// The string "number".
-    pub number: String,
+    /// The workspace as number (optionally preceded by the word "number"), name, or "num:name",
+    /// optionally followed by the output to open the workspace on if it does not exist yet
+    /// (which may also be "primary", "secondary", "left-of-primary", or "right-of-primary").
+    #[arg(required = true, num_args = 1..=3, value_name = "WORKSPACE")]
+    pub args: Vec<String>,
+
+    /// Where to open a new workspace if no output is given, tried in order
+    /// [env: SWAYTOOLS_OUTPUT_FALLBACK] [default: mapping,focused].
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub fallback: Vec<OutputFallback>,
+
+    /// The window manager to talk to, detected from the environment by default
+    /// [env: SWAYTOOLS_BACKEND].
+    #[arg(long, value_enum)]
+    pub backend: Option<Backend>,
+
+    /// Only show commands instead of executing them [env: SWAYTOOLS_DRY_RUN].
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+
+    /// How errors are reported [env: SWAYTOOLS_ERRORS].
+    #[arg(long, value_enum)]
+    pub errors: Option<ErrorFormat>,
+}
+
+impl WorkspaceCli {
+    /// Returns the workspace and the output given on the command line.
+    pub fn target(&self) -> Fallible<(WorkspaceTarget, Option<&str>)> {
+        let args = match self.args.first().map(String::as_str) {
+            Some("number") if self.args.len() > 1 => &self.args[1..],
+            _ => &self.args[..],
+        };
+        match args {
+            [workspace] => Ok((WorkspaceTarget::parse(workspace), None)),
+            [workspace, output] => Ok((WorkspaceTarget::parse(workspace), Some(output))),
+            [.., extra] => Err(Error::UnexpectedArgument(extra.to_owned())),
+            [] => Err(Error::NeitherNumNorNameProvided),
+        }
+    }
+}
+
+/// A workspace given by number, name, or both as `num:name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceTarget {
+    /// The number of the workspace, if it has one.
+    pub num: Option<i32>,
+    /// The full name of the workspace, if it is not just the number.
+    pub name: Option<String>,
+}
+
+impl WorkspaceTarget {
+    /// Parses `num`, `num:name`, or any other string as a name.
+    pub fn parse(workspace: &str) -> WorkspaceTarget {
+        let prefix = workspace.split(':').next().unwrap_or_default();
+        match prefix.parse() {
+            Ok(num) if prefix == workspace => WorkspaceTarget {
+                num: Some(num),
+                name: None,
+            },
+            Ok(num) => WorkspaceTarget {
+                num: Some(num),
+                name: Some(workspace.to_owned()),
+            },
+            Err(_) => WorkspaceTarget {
+                num: None,
+                name: Some(workspace.to_owned()),
+            },
+        }
+    }
+
+    /// Returns the argument selecting this workspace in `workspace` and `move to workspace`
+    /// commands, i.e., `number N`, `number N:name`, or the quoted name.
+    pub fn selector(&self) -> String {
+        match (self.num, &self.name) {
+            (Some(_), Some(name)) => format!("number {}", quote(name)),
+            (Some(num), None) => format!("number {num}"),
+            (None, name) => quote(name.as_deref().unwrap_or_default()),
+        }
+    }
+
+    /// Returns the name sway gives the workspace when creating it.
+    pub fn created_name(&self) -> String {
+        match (self.num, &self.name) {
+            (_, Some(name)) => name.to_owned(),
+            (Some(num), None) => num.to_string(),
+            (None, None) => String::new(),
+        }
+    }
+
+    /// Returns whether `workspace` is this workspace, matching by number if there is one.
+    pub fn is(&self, workspace: &Workspace) -> bool {
+        match (self.num, &self.name) {
+            (Some(num), _) => workspace.num == num,
+            (None, name) => name.as_ref() == Some(&workspace.name),
+        }
+    }
+}
+
+/// A step of the chain determining the output a new workspace is opened on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFallback {
+    /// The output the workspace is mapped to.
+    Mapping,
+    /// The output directly left of the focused one.
+    Left,
+    /// The output directly right of the focused one.
+    Right,
+    /// The focused output.
+    Focused,
+}
 
-    /// The workspace to switch to.
-    pub workspace: i32,
+impl OutputFallback {
+    /// Resolves the chain from the command line value or `$SWAYTOOLS_OUTPUT_FALLBACK` (a
+    /// comma-separated list), using the mapping and then the focused output by default.
+    pub fn resolve(cli: Vec<OutputFallback>) -> Vec<OutputFallback> {
+        if !cli.is_empty() {
+            return cli;
+        }
+        env::var(env::OUTPUT_FALLBACK)
+            .and_then(|chain| {
+                chain
+                    .split(',')
+                    .map(|step| clap::ValueEnum::from_str(step.trim(), true).ok())
+                    .collect()
+            })
+            .unwrap_or_else(|| vec![OutputFallback::Mapping, OutputFallback::Focused])
+    }
 
-    /// If the workspace does not exist yet, open it on this output.
-    pub output: Option<String>,
+    /// Returns the output this step selects for `workspace` among the `outputs`.
+    fn output(&self, workspace: &WorkspaceTarget, outputs: &[Output]) -> Option<String> {
+        let focused = outputs.iter().find(|o| o.focused);
+        match self {
+            OutputFallback::Mapping => workspace
+                .num
+                .and_then(get_output_for_workspace)
+                .filter(|name| outputs.iter().any(|o| &o.name == name)),
+            OutputFallback::Left => adjacent_output(outputs, focused?, false),
+            OutputFallback::Right => adjacent_output(outputs, focused?, true),
+            OutputFallback::Focused => focused.map(|o| o.name.to_owned()),
+        }
+    }
 }
 
-/// Initializes the cli interface, connects to the sway ipc, returns the
-/// provided (sanitized) output (for the given workspace) and whether the
-/// provided workspace already exists.
-pub fn initialize_workspace() -> (WorkspaceCli, Connection, Option<String>, bool) {
-    let cli = WorkspaceCli::parse();
+/// Returns the closest active output left or `right` of `focused` which overlaps it vertically.
+fn adjacent_output(outputs: &[Output], focused: &Output, right: bool) -> Option<String> {
+    let rect = &focused.rect;
+    outputs
+        .iter()
+        .filter(|o| o.active && o.name != focused.name)
+        .filter(|o| o.rect.y < rect.y + rect.height && rect.y < o.rect.y + o.rect.height)
+        .filter_map(|o| {
+            let distance = if right {
+                o.rect.x - (rect.x + rect.width)
+            } else {
+                rect.x - (o.rect.x + o.rect.width)
+            };
+            (distance >= 0).then_some((distance, &o.name))
+        })
+        .min()
+        .map(|(_, name)| name.to_owned())
+}
 
-    let mut sway = Connection::new().expect("Cannot connect to sway via IPC.");
+/// Connects to the sway ipc, returns the workspace and the provided (sanitized)
+/// output (for the given workspace) and whether the workspace already exists.
+///
+/// Without a connected output on the command line the output is determined by
+/// the [`OutputFallback`] chain.
+pub fn initialize_workspace(
+    cli: &WorkspaceCli,
+) -> Fallible<(Connection, WorkspaceTarget, Option<String>, bool)> {
+    let mut sway =
+        Connection::new(env::dry_run(cli.dry_run))?.with_backend(Backend::resolve(cli.backend))?;
+    let (workspace, output) = cli.target()?;
 
-    let output = cli
-        .output
-        .as_ref()
+    let output = output
         // If we are given an output then we sanitize it.
         .and_then(|output| output_if_exists(output.to_string(), &mut sway))
-        // If we are not given an output or the sanitization threw it away we get the output for the provided workspace.
-        .or_else(|| get_output_for_workspace(cli.workspace));
+        // If we are not given an output or the sanitization threw it away we go through the fallbacks.
+        .or_else(|| {
+            let outputs = sway.get_outputs().unwrap_or_default();
+            OutputFallback::resolve(cli.fallback.clone())
+                .iter()
+                .find_map(|fallback| fallback.output(&workspace, &outputs))
+        });
 
     // We check whether the provided workspace exists.
-    let workspace_exists = workspace_exists(cli.workspace, &mut sway);
+    let workspace_exists = sway
+        .get_workspaces()
+        .unwrap_or_default()
+        .iter()
+        .any(|ws| workspace.is(ws));
 
-    (cli, sway, output, workspace_exists)
+    Ok((sway, workspace, output, workspace_exists))
+}
+
+/// An output given relative to the primary output, which sway itself has no notion of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelativeOutput {
+    /// The first connected output of the primary output and its fallbacks.
+    Primary,
+    /// The active output closest to the primary one.
+    Secondary,
+    /// The output directly left of the primary one.
+    LeftOfPrimary,
+    /// The output directly right of the primary one.
+    RightOfPrimary,
+}
+
+impl RelativeOutput {
+    /// Parses `primary`, `secondary`, `left-of-primary`, or `right-of-primary`.
+    pub fn parse(output: &str) -> Option<RelativeOutput> {
+        match output {
+            "primary" => Some(RelativeOutput::Primary),
+            "secondary" => Some(RelativeOutput::Secondary),
+            "left-of-primary" => Some(RelativeOutput::LeftOfPrimary),
+            "right-of-primary" => Some(RelativeOutput::RightOfPrimary),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the output among `outputs` this target selects, where `primary` lists
+    /// the primary output and its fallbacks by name or identifier.
+    ///
+    /// Without any of them connected, the top left active output is the primary one.
+    pub fn resolve(self, outputs: &[Output], primary: &[String]) -> Option<String> {
+        let active: Vec<&Output> = outputs.iter().filter(|o| o.active).collect();
+        let primary = primary
+            .iter()
+            .find_map(|name| {
+                active
+                    .iter()
+                    .find(|o| &o.name == name || &output_identifier(o) == name)
+            })
+            .or_else(|| active.iter().min_by_key(|o| (o.rect.x, o.rect.y)))?;
+        match self {
+            RelativeOutput::Primary => Some(primary.name.to_owned()),
+            RelativeOutput::Secondary => active
+                .iter()
+                .filter(|o| o.name != primary.name)
+                .min_by_key(|o| {
+                    (o.rect.x - primary.rect.x).abs() + (o.rect.y - primary.rect.y).abs()
+                })
+                .map(|o| o.name.to_owned()),
+            RelativeOutput::LeftOfPrimary => adjacent_output(outputs, primary, false),
+            RelativeOutput::RightOfPrimary => adjacent_output(outputs, primary, true),
+        }
+    }
 }
 
 /// Returns the provided (optional) output if it is indeed connected.
 ///
 /// Goes through the list of outputs and checks whether the provided output exists, i.e.,
 /// checks whether the provided output is either the name (like `VGA-1`, `HDMI-A-3`, …) or a
-/// combination of make, model, and serial number. If so the name is returned.
+/// combination of make, model, and serial number. If so the name is returned. A
+/// [`RelativeOutput`] like `primary` is resolved with the primary output from the state file.
 pub fn output_if_exists(output: String, sway: &mut Connection) -> Option<String> {
+    if let Some(relative) = RelativeOutput::parse(&output) {
+        let primary = StateFile::open_default()
+            .load()
+            .map(|state| state.primary)
+            .unwrap_or_default();
+        return relative.resolve(&sway.get_outputs().unwrap_or_default(), &primary);
+    }
     for sway_output in sway.get_outputs().unwrap_or_default() {
         if output == sway_output.name {
             return Some(output);
         }
-        if output
-            == format!(
-                "{} {} {}",
-                sway_output.make, sway_output.model, sway_output.serial
-            )
-        {
+        if output == output_identifier(&sway_output) {
             return Some(sway_output.name);
         }
     }
     None
 }
 
-/// Returns the path to the configuration file.
-pub fn get_config_path() -> PathBuf {
-    [
-        env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string()),
-        "sway-workspaces-outputs.json".to_string(),
-    ]
-    .iter()
-    .collect()
+/// Returns the identifier of `output` which stays the same across ports, i.e., its make, model,
+/// and serial number separated by spaces.
+pub fn output_identifier(output: &Output) -> String {
+    format!("{} {} {}", output.make, output.model, output.serial)
+}
+
+/// Puts `argument` in single quotes for a sway command, e.g., `it's` becomes `'it'\''s'`.
+///
+/// The quoting is also understood by the shell sway runs `exec` commands with.
+pub fn quote(argument: &str) -> String {
+    format!("'{}'", argument.replace('\'', r"'\''"))
 }
 
-/// Loads the configuration from the configuration file.
-pub fn load_config() -> Result<HashMap<String, Vec<i32>>> {
-    let config_path = get_config_path();
-    let json = fs::read_to_string(config_path).unwrap_or_default();
-    serde_json::from_str(&json)
+/// Returns the `exec` command launching the program and arguments `args`.
+///
+/// sway hands the command to a shell, so each argument is quoted, see [`quote`].
+pub fn exec_command(args: &[String]) -> String {
+    let quoted: Vec<String> = args.iter().map(|arg| quote(arg)).collect();
+    format!("exec {}", quoted.join(" "))
 }
 
-/// Stores the configuration in the configuration file.
-pub fn save_config(config: &HashMap<String, Vec<i32>>) -> bool {
-    let config_path = get_config_path();
-    if let Ok(json) = serde_json::to_string(&config) {
-        return fs::write(config_path, json).is_ok();
+/// Loads the configuration from `$SWAYTOOLS_MAPPING_FILE` if set or the state file otherwise.
+pub fn load_config() -> Fallible<HashMap<String, Vec<i32>>> {
+    match env::mapping_file(None) {
+        Some(config_path) => {
+            let json = fs::read_to_string(config_path).unwrap_or_default();
+            Ok(serde_json::from_str(&json)?)
+        }
+        None => Ok(StateFile::open_default().load()?.mapping),
+    }
+}
+
+/// Stores the configuration in `$SWAYTOOLS_MAPPING_FILE` if set or the state file otherwise.
+pub fn save_config(config: &HashMap<String, Vec<i32>>) -> Fallible<()> {
+    match env::mapping_file(None) {
+        Some(config_path) => Ok(fs::write(config_path, serde_json::to_string(&config)?)?),
+        None => StateFile::open_default().update(|state| state.mapping = config.to_owned()),
     }
-    false
 }
 
 /// Create a configuration from a list of mapping strings.
-pub fn make_config(mappings: Vec<String>, sway: &mut Connection) -> HashMap<String, Vec<i32>> {
+///
+/// Mappings for outputs which are not connected are skipped. Unless `force` is
+/// set, mappings which assign an output or a workspace more than once are
+/// rejected with [`Error::ConflictingMappings`].
+pub fn make_config(
+    mappings: Vec<String>,
+    force: bool,
+    sway: &mut Connection,
+) -> Fallible<HashMap<String, Vec<i32>>> {
+    let mappings = mappings
+        .iter()
+        .map(|mapping| mapping::parse_mapping(mapping))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mappings = resolve_outputs(mappings, sway);
+    check_conflicts(&mappings, force)?;
+
     let mut config = HashMap::new();
+    for (output, workspaces) in mappings {
+        if let Some(output) = output_if_exists(output, sway) {
+            config.insert(output, workspaces);
+        }
+    }
 
+    Ok(config)
+}
+
+/// Replaces the outputs of `mappings` by the names of the connected outputs they
+/// refer to, see [`output_if_exists`], so that, e.g., `primary` and its name
+/// conflict. Outputs which are not connected are kept as given.
+pub fn resolve_outputs(
+    mappings: Vec<(String, Vec<i32>)>,
+    sway: &mut Connection,
+) -> Vec<(String, Vec<i32>)> {
     mappings
+        .into_iter()
+        .map(|(output, workspaces)| {
+            let name = output_if_exists(output.clone(), sway).unwrap_or(output);
+            (name, workspaces)
+        })
+        .collect()
+}
+
+/// Removes the entries of outputs which are not connected from `config` and
+/// returns their names.
+pub fn prune_config(
+    config: &mut HashMap<String, Vec<i32>>,
+    sway: &mut Connection,
+) -> Fallible<Vec<String>> {
+    let outputs = sway.get_outputs()?;
+    let mut pruned: Vec<String> = config
+        .keys()
+        .filter(|output| !outputs.iter().any(|o| &&o.name == output))
+        .cloned()
+        .collect();
+    pruned.sort();
+    for output in &pruned {
+        config.remove(output);
+    }
+    Ok(pruned)
+}
+
+/// Fails with [`Error::ConflictingMappings`] if the parsed `mappings` conflict
+/// and `force` is not set.
+pub fn check_conflicts(mappings: &[(String, Vec<i32>)], force: bool) -> Fallible<()> {
+    let conflicts = mapping::conflicts(mappings);
+    if force || conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::ConflictingMappings(conflicts))
+    }
+}
+
+/// Assigns all workspaces in `mappings` to their outputs with `workspace <num>
+/// output <output>` commands, such that sway opens them there when they are
+/// created later.
+///
+/// Failed assignments are reported like in [`move_workspaces`].
+pub fn assign_workspaces(
+    mappings: &HashMap<String, Vec<i32>>,
+    sway: &mut Connection,
+) -> Fallible<()> {
+    let commands: Vec<String> = mappings
         .iter()
-        .flat_map(|mapping| add_mapping(mapping, &mut config, sway))
-        .for_each(drop);
+        .flat_map(|(output, workspaces)| {
+            workspaces
+                .iter()
+                .map(move |num| format!("workspace {num} output '{output}'"))
+        })
+        .collect();
 
-    config
+    let mut failures = Vec::new();
+    if !commands.is_empty() {
+        sway.run_tracked(commands.join("; "), &mut failures)?;
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::PartialApply(failures))
+    }
 }
 
-/// Add one mapping from the provided `mapping` string to the `config` set.
-fn add_mapping(
-    mapping: &str,
-    config: &mut HashMap<String, Vec<i32>>,
+/// Move all workspaces in `mappings` to the correct outputs.
+///
+/// `mappings` is a mapping from output (e.g., `VGA-1`) to a list of workspaces
+/// to be shown on this output.
+///
+/// Workspaces with windows are moved by criteria without focusing them and all
+/// commands are sent at once to avoid visibly switching through workspaces.
+/// Afterwards the workspaces which were visible before are shown again and the
+/// previously focused workspace is focused.
+///
+/// All moves are attempted even if some of them fail, in which case
+/// [`Error::PartialApply`] lists the failures.
+pub fn move_workspaces(
+    mappings: &HashMap<String, Vec<i32>>,
     sway: &mut Connection,
-) -> Option<()> {
-    let (output_str, workspace_str) = mapping.split_at(mapping.rfind(':')?);
-    let output = output_if_exists(output_str.to_owned(), sway)?;
-    if let Some(index) = workspace_str[1..].find('-') {
-        let (left_str, right_str) = workspace_str[1..].split_at(index);
-        let left = left_str.parse().ok()?;
-        let right = right_str[1..].parse().ok()?;
-        config.insert(
-            output,
-            (if left <= right {
-                left..=right
+) -> Fallible<()> {
+    let mut commands = Vec::new();
+    // Take a copy of all outputs to ensure that even on outputs which do not
+    // have workspaces to show anything, a correct workspace is shown.
+    let mut empty_outputs: HashSet<&String> = HashSet::from_iter(mappings.keys());
+    // We want to now which workspace was focused to be able to focus it after
+    // moving the workspaces.
+    // Workspaces are remembered by name as named workspaces have no number.
+    let mut focused_ws: Option<String> = None;
+    // The workspaces visible on the outputs before moving, restored afterwards.
+    let mut visible_ws: Vec<String> = Vec::new();
+
+    for ws in sway.get_workspaces()? {
+        // Store the focused and visible workspaces
+        if ws.focused {
+            focused_ws = Some(ws.name.clone());
+        } else if ws.visible {
+            visible_ws.push(ws.name.clone());
+        }
+
+        for (output, workspaces) in mappings.iter() {
+            // Skip output if it should not display the current workspace.
+            if !workspaces.contains(&ws.num) {
+                continue;
+            }
+            // We move a workspace to this output, remove it from the list of
+            // empty outputs.
+            empty_outputs.remove(output);
+            // The workspace is already on the correct output, don't do anything.
+            if &ws.output == output {
+                break;
+            }
+
+            if ws.focus.is_empty() {
+                // An empty workspace cannot be matched by criteria, so select
+                // it and move it to the desired output.
+                commands.push(format!(
+                    "workspace --no-auto-back-and-forth number {}, move workspace to output '{}'",
+                    ws.num, output
+                ));
             } else {
-                right..=left
-            })
-            .collect(),
-        );
+                // Move the workspace of its windows without focusing it.
+                commands.push(format!(
+                    "[workspace=\"^{}$\"] move workspace to output '{}'",
+                    regex::escape(&ws.name),
+                    output
+                ));
+            }
+        }
+    }
+
+    // Go through all outputs which have no workspace on them.
+    for output in empty_outputs.into_iter() {
+        // Get the first workspace in the assigned list of workspaces for the
+        // output and display this workspace on the output.
+        if let Some(num) = mappings
+            .get(output)
+            .and_then(|workspaces| workspaces.first())
+        {
+            commands.push(format!(
+                "workspace --no-auto-back-and-forth number {num}, move workspace to output '{output}'"
+            ));
+        }
+    }
+
+    // Show the previously visible workspaces again, wherever they ended up,
+    // and focus the previously focused workspace last.
+    for ws in visible_ws.into_iter().chain(focused_ws) {
+        commands.push(format!("workspace --no-auto-back-and-forth {}", quote(&ws)));
+    }
+
+    let mut failures = Vec::new();
+    if !commands.is_empty() {
+        sway.run_tracked(commands.join("; "), &mut failures)?;
+    }
+
+    if failures.is_empty() {
+        Ok(())
     } else {
-        let number = workspace_str[1..].parse().ok()?;
-        config.insert(output, vec![number]);
-    };
-    Some(())
+        Err(Error::PartialApply(failures))
+    }
 }
 
 /// Returns whether a workspace with the given number exists.
@@ -150,6 +567,9 @@ pub fn get_output_for_workspace(workspace_num: i32) -> Option<String> {
 }
 
 /// Return the currently focused workspace.
+///
+/// On multi-seat setups this is the workspace of the most recently active seat, see
+/// [`seat::focused_workspace_for_seat`] for the workspace of a specific seat.
 pub fn get_focused_workspace(sway: &mut Connection) -> Option<Workspace> {
     sway.get_workspaces()
         .unwrap_or_default()
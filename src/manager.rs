@@ -0,0 +1,480 @@
+//! The mapping-aware workspace operations behind the `ws` binary.
+//!
+//! [`WorkspaceManager`] lets other tools (bars, launchers, …) focus workspaces, move containers,
+//! and apply the output-to-workspace mapping the same way `ws` does, without shelling out to it.
+//!
+//! ```no_run
+//! use swaytools::WorkspaceManager;
+//!
+//! let mut manager = WorkspaceManager::new(false)?;
+//! // Focuses workspace 3 on the output it is mapped to.
+//! manager.focus(Some(3), None, true)?;
+//! # Ok::<(), swaytools::error::Error>(())
+//! ```
+
+use crate::{
+    error::{Error, Fallible},
+    move_workspaces, output_if_exists, placement, prune_config,
+    seat::{self, SeatSelector},
+    state::{StateFile, WorkspaceRef},
+    Connection, WorkspaceTarget,
+};
+use std::{collections::HashMap, fs, path::PathBuf};
+use swayipc::{Output, Workspace};
+
+/// Focuses and moves to workspaces while respecting the output-to-workspace mapping.
+///
+/// Workspaces and outputs are queried lazily and cached until they are reset.
+pub struct WorkspaceManager {
+    connection: Connection,
+    workspaces: Option<Vec<Workspace>>,
+    outputs: Option<Vec<Output>>,
+    mapping_file: Option<PathBuf>,
+    previous_file: Option<PathBuf>,
+    state: StateFile,
+    mapping: HashMap<String, Vec<i32>>,
+    seat: Option<SeatSelector>,
+    /// The names of the output and workspace focused by `seat`.
+    seat_focus: Option<(Option<String>, Option<String>)>,
+}
+
+impl WorkspaceManager {
+    /// Connects to sway and uses the default state file for the mapping and the previously
+    /// focused workspace.
+    ///
+    /// If `dry_run` is set, commands are printed instead of executed.
+    pub fn new(dry_run: bool) -> Fallible<WorkspaceManager> {
+        Ok(WorkspaceManager {
+            connection: Connection::new(dry_run)?,
+            workspaces: None,
+            outputs: None,
+            mapping_file: None,
+            previous_file: None,
+            state: StateFile::open_default(),
+            mapping: HashMap::new(),
+            seat: None,
+            seat_focus: None,
+        })
+    }
+
+    /// Uses the given `state` file instead of the default one.
+    pub fn with_state_file(mut self, state: StateFile) -> WorkspaceManager {
+        self.state = state;
+        self
+    }
+
+    /// Stores the mapping in a separate file instead of the state file.
+    pub fn with_mapping_file<P: Into<PathBuf>>(mut self, path: Option<P>) -> WorkspaceManager {
+        self.mapping_file = path.map(Into::into);
+        self
+    }
+
+    /// Stores the previously focused workspace in a separate file instead of the state file.
+    pub fn with_previous_file<P: Into<PathBuf>>(mut self, path: Option<P>) -> WorkspaceManager {
+        self.previous_file = path.map(Into::into);
+        self
+    }
+
+    /// Uses the focus of the selected seat instead of the global focus.
+    pub fn with_seat(mut self, seat: Option<SeatSelector>) -> WorkspaceManager {
+        self.seat = seat;
+        self.seat_focus = None;
+        self
+    }
+
+    /// Returns the connection commands are sent over.
+    pub fn connection(&mut self) -> &mut Connection {
+        &mut self.connection
+    }
+
+    /// Connects to sway again, e.g., after sway has been restarted, and resets all caches.
+    pub fn reconnect(&mut self) -> Fallible<()> {
+        self.connection = Connection::new(self.connection.dry_run())?;
+        self.reset_workspaces();
+        self.reset_outputs();
+        Ok(())
+    }
+
+    // ########################################################################
+
+    /// Focuses the workspace given by `num` and/or `name`.
+    ///
+    /// A numbered workspace which does not exist yet is created on the output it is mapped to.
+    /// If the workspace is already focused and `auto_back_and_forth` is not set, nothing happens;
+    /// otherwise sway's `workspace_auto_back_and_forth` setting applies.
+    pub fn focus(
+        &mut self,
+        num: Option<i32>,
+        name: Option<&str>,
+        auto_back_and_forth: bool,
+    ) -> Fallible<()> {
+        self.focus_on(num, name, None, auto_back_and_forth)
+    }
+
+    /// Focuses the workspace given by `num` and/or `name` like [`WorkspaceManager::focus`], but
+    /// creates a workspace which does not exist yet on `output` (anything [`output_if_exists`]
+    /// accepts, e.g., `primary`) instead of the mapped output if it is connected.
+    pub fn focus_on(
+        &mut self,
+        num: Option<i32>,
+        name: Option<&str>,
+        output: Option<&str>,
+        auto_back_and_forth: bool,
+    ) -> Fallible<()> {
+        self.update_workspaces()?;
+
+        let target = self.workspace_by_num_or_name(num, name);
+        let focused = self.focused_workspace().ok_or(Error::NoFocusedWorkspace)?;
+
+        // If the target workspace already exists
+        if let Some(target) = target {
+            // If the target is already focused and auto-back-and-forth is not wanted, abort here
+            if target.num == focused.num && target.name == focused.name && !auto_back_and_forth {
+                return Ok(());
+            }
+            // Just focus the target workspace. This will either focus it (if not focused yet) or
+            // go to previously focused workspace if auto-back-and-forth is enabled.
+            return self.connection.workspace(num, name);
+        }
+
+        // Store name of the previously focused workspace as the following calls need the manager
+        // mutably
+        let focused_name = focused.name.to_owned();
+
+        // Find out on which output the workspace should be shown
+        let output_str = match (output, num) {
+            (Some(output), _) => output_if_exists(output.to_owned(), &mut self.connection),
+            (None, Some(number)) => {
+                self.load_mapping()?;
+                self.mapping
+                    .iter()
+                    .find(|(_, ws)| ws.contains(&number))
+                    .map(|(output, _)| output.to_owned())
+            }
+            // If workspace is not given by a number, just select the workspace
+            (None, None) => {
+                return self
+                    .connection
+                    .workspace_name(name.ok_or(Error::NeitherNumNorNameProvided)?);
+            }
+        };
+        self.update_outputs()?;
+        let Some(output_str) = output_str else {
+            // We could not find the desired output, just select it
+            return self.connection.workspace(num, name);
+        };
+        let focused_output = self.focused_output().ok_or(Error::NoFocusedOutput)?;
+        if focused_output.name == output_str {
+            // We are on the correct output already, just select workspace
+            return self.connection.workspace(num, name);
+        }
+        // 1. focus the desired output
+        self.connection.focus_output(&output_str)?;
+        // 2. select the desired workspace
+        self.connection.workspace(num, name)?;
+        // 3. select the initially focused workspace
+        self.connection.workspace_name(&focused_name)?;
+        // 4. select the desired workspace
+        self.connection.workspace(num, name)
+    }
+
+    /// Shows the workspaces of group `num` on all mapped outputs, like switching a virtual desktop.
+    ///
+    /// The group is the position of `num` among the sorted workspaces mapped to its output, e.g.,
+    /// with `A:1-10`, `B:11-20` both `1` and `11` show `1` on A and `11` on B. A workspace number
+    /// which is not mapped is taken as the position itself. The focused output keeps the focus.
+    pub fn focus_synced(&mut self, num: i32) -> Fallible<()> {
+        self.load_mapping()?;
+        self.update_outputs()?;
+        let sorted = |workspaces: &Vec<i32>| {
+            let mut workspaces = workspaces.to_owned();
+            workspaces.sort();
+            workspaces
+        };
+        let group = self
+            .mapping
+            .values()
+            .find_map(|workspaces| sorted(workspaces).iter().position(|&ws| ws == num))
+            .unwrap_or_else(|| usize::try_from(num - 1).unwrap_or_default());
+        let focused = self
+            .focused_output()
+            .ok_or(Error::NoFocusedOutput)?
+            .name
+            .to_owned();
+
+        let mut targets: Vec<(String, i32)> = self
+            .mapping
+            .iter()
+            .filter_map(|(output, workspaces)| {
+                let output = self.output_by_name_or_identifier(Some(output), Some(output))?;
+                Some((output.name.to_owned(), *sorted(workspaces).get(group)?))
+            })
+            .collect();
+        // The focused output comes last so that it keeps the focus.
+        targets.sort_by_key(|(output, _)| (*output == focused, output.to_owned()));
+        let commands: Vec<String> = targets
+            .iter()
+            .map(|(output, ws)| {
+                format!("focus output '{output}'; workspace --no-auto-back-and-forth number {ws}")
+            })
+            .collect();
+        if commands.is_empty() {
+            return self.connection.workspace_num(num);
+        }
+        self.reset_workspaces();
+        self.connection.run(commands.join("; "))
+    }
+
+    /// Moves the focused container to the workspace given by `num` and/or `name`.
+    ///
+    /// A numbered workspace which does not exist yet is opened on the output it is mapped to
+    /// without changing the visible workspaces, see [`placement::move_window`]. If the workspace
+    /// is already focused and `auto_back_and_forth` is not set, nothing happens.
+    pub fn move_container(
+        &mut self,
+        num: Option<i32>,
+        name: Option<&str>,
+        auto_back_and_forth: bool,
+    ) -> Fallible<()> {
+        if !auto_back_and_forth {
+            self.update_workspaces()?;
+            let target = self.workspace_by_num_or_name(num, name);
+            let focused = self.focused_workspace().ok_or(Error::NoFocusedWorkspace)?;
+            if target.is_some_and(|t| t.num == focused.num && t.name == focused.name) {
+                return Ok(());
+            }
+        }
+
+        let workspace = match (num, name) {
+            (Some(num), Some(name)) => WorkspaceTarget {
+                num: Some(num),
+                name: Some(format!("{num}:{name}")),
+            },
+            (num, name) => WorkspaceTarget {
+                num,
+                name: name.map(str::to_owned),
+            },
+        };
+        if workspace.num.is_none() && workspace.name.is_none() {
+            return Err(Error::NeitherNumNorNameProvided);
+        }
+        // Without a mapping the workspace is simply opened on the focused output
+        let output = match num {
+            Some(number) if self.load_mapping().is_ok() => self
+                .mapping
+                .iter()
+                .find(|(_, ws)| ws.contains(&number))
+                .map(|(output, _)| output.to_owned()),
+            _ => None,
+        };
+        self.reset_workspaces();
+        placement::move_window(
+            &mut self.connection,
+            "",
+            &workspace,
+            output.as_deref(),
+            false,
+        )
+    }
+
+    /// Sets the mapping of the given outputs on top of the current [`WorkspaceManager::mapping`]
+    /// and stores the result.
+    ///
+    /// Outputs are given by name, as `make model serial`, or relative to the primary output (see
+    /// [`output_if_exists`]); outputs which are not connected are skipped.
+    pub fn map_outputs<I: IntoIterator<Item = (String, Vec<i32>)>>(
+        &mut self,
+        maps: I,
+    ) -> Fallible<()> {
+        for (output_str, workspaces) in maps {
+            if let Some(name) = output_if_exists(output_str, &mut self.connection) {
+                self.mapping.insert(name, workspaces);
+            }
+        }
+        self.save_mapping()
+    }
+
+    /// Removes the outputs which are not connected from the current [`WorkspaceManager::mapping`]
+    /// and returns their names.
+    pub fn prune_mapping(&mut self) -> Fallible<Vec<String>> {
+        prune_config(&mut self.mapping, &mut self.connection)
+    }
+
+    /// Loads the stored mapping and moves all workspaces to their mapped outputs.
+    ///
+    /// See [`move_workspaces`] for how failed moves are reported.
+    pub fn apply_mapping(&mut self) -> Fallible<()> {
+        self.load_mapping()?;
+        self.reset_workspaces();
+        move_workspaces(&self.mapping, &mut self.connection)
+    }
+
+    // ########################################################################
+
+    /// Returns the name and number of the previously focused workspace.
+    pub fn previous_workspace(&self) -> Fallible<(String, i32)> {
+        if let Some(previous_file) = &self.previous_file {
+            let json = fs::read_to_string(previous_file)?;
+            let result = serde_json::from_str(&json)?;
+            return Ok(result);
+        }
+        let previous = self.state.load()?.previous;
+        previous
+            .map(|ws| (ws.name, ws.num))
+            .ok_or(Error::NoPreviousWorkspace)
+    }
+
+    /// Records the workspace with the given `name` and `num` as previously focused, except in a
+    /// dry run.
+    pub fn save_previous_workspace(&self, name: String, num: i32) -> Fallible<()> {
+        if self.connection.dry_run() {
+            return Ok(());
+        }
+        if let Some(previous_file) = &self.previous_file {
+            let data = serde_json::to_string(&(name, num))?;
+            fs::write(previous_file, data)?;
+            return Ok(());
+        }
+        self.state
+            .update(|state| state.push_previous(WorkspaceRef { name, num }))
+    }
+
+    /// Returns the mapping as last loaded or changed.
+    pub fn mapping(&self) -> &HashMap<String, Vec<i32>> {
+        &self.mapping
+    }
+
+    /// Loads the mapping from the mapping file or the state file.
+    pub fn load_mapping(&mut self) -> Fallible<()> {
+        if let Some(mapping_file) = &self.mapping_file {
+            let json = fs::read_to_string(mapping_file)?;
+            self.mapping = serde_json::from_str(&json)?;
+        } else {
+            self.mapping = self.state.load()?.mapping;
+        }
+        Ok(())
+    }
+
+    /// Stores the mapping in the mapping file or the state file, except in a dry run.
+    pub fn save_mapping(&mut self) -> Fallible<()> {
+        if self.connection.dry_run() {
+            return Ok(());
+        }
+        if let Some(mapping_file) = &self.mapping_file {
+            let data = serde_json::to_string(&self.mapping)?;
+            fs::write(mapping_file, data)?;
+            return Ok(());
+        }
+        let mapping = self.mapping.to_owned();
+        self.state.update(|state| state.mapping = mapping)
+    }
+
+    // ########################################################################
+
+    pub fn workspace_by_num(&self, num: i32) -> Option<&Workspace> {
+        self.workspaces()
+            .and_then(|wss| wss.iter().find(|ws| ws.num == num))
+    }
+
+    pub fn workspace_by_name(&self, name: &str) -> Option<&Workspace> {
+        self.workspaces()
+            .and_then(|wss| wss.iter().find(|ws| ws.name == name))
+    }
+
+    pub fn workspace_by_num_or_name(
+        &self,
+        num: Option<i32>,
+        name: Option<&str>,
+    ) -> Option<&Workspace> {
+        self.workspaces().and_then(|wss| {
+            wss.iter().find(|ws| {
+                name.is_some_and(|name| name == ws.name) || num.is_some_and(|num| num == ws.num)
+            })
+        })
+    }
+
+    /// Returns the cached workspaces, see [`WorkspaceManager::update_workspaces`].
+    pub fn workspaces(&self) -> Option<&Vec<Workspace>> {
+        self.workspaces.as_ref()
+    }
+
+    /// Returns the workspace focused by the selected seat or globally.
+    pub fn focused_workspace(&self) -> Option<&Workspace> {
+        match &self.seat_focus {
+            Some((_, workspace)) => {
+                let name = workspace.as_deref()?;
+                self.workspace_by_name(name)
+            }
+            None => self.workspaces()?.iter().find(|ws| ws.focused),
+        }
+    }
+
+    /// Queries the workspaces unless they are cached already.
+    pub fn update_workspaces(&mut self) -> Fallible<()> {
+        if self.workspaces.is_none() {
+            self.workspaces = Some(self.connection.get_workspaces()?);
+        }
+        self.update_seat_focus()
+    }
+
+    pub fn reset_workspaces(&mut self) {
+        self.workspaces = None;
+        self.seat_focus = None;
+    }
+
+    // ########################################################################
+
+    pub fn output_by_name_or_identifier(
+        &self,
+        name: Option<&str>,
+        identifier: Option<&str>,
+    ) -> Option<&Output> {
+        self.outputs().and_then(|os| {
+            os.iter().find(|o| {
+                name.is_some_and(|name| name == o.name)
+                    || identifier.is_some_and(|identifier| {
+                        identifier == format!("{} {} {}", o.make, o.model, o.serial)
+                    })
+            })
+        })
+    }
+
+    /// Returns the cached outputs, see [`WorkspaceManager::update_outputs`].
+    pub fn outputs(&self) -> Option<&Vec<Output>> {
+        self.outputs.as_ref()
+    }
+
+    /// Returns the output focused by the selected seat or globally.
+    pub fn focused_output(&self) -> Option<&Output> {
+        match &self.seat_focus {
+            Some((output, _)) => {
+                let name = output.as_deref()?;
+                self.output_by_name_or_identifier(Some(name), None)
+            }
+            None => self.outputs()?.iter().find(|o| o.focused),
+        }
+    }
+
+    /// Queries the outputs unless they are cached already.
+    pub fn update_outputs(&mut self) -> Fallible<()> {
+        if self.outputs.is_none() {
+            self.outputs = Some(self.connection.get_outputs()?);
+        }
+        self.update_seat_focus()
+    }
+
+    pub fn reset_outputs(&mut self) {
+        self.outputs = None;
+        self.seat_focus = None;
+    }
+
+    // ########################################################################
+
+    /// Looks up the focus of the selected seat (if any).
+    fn update_seat_focus(&mut self) -> Fallible<()> {
+        if let (Some(seat), None) = (&self.seat, &self.seat_focus) {
+            self.seat_focus = Some(seat::focus_for_seat(&mut self.connection, seat)?);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,352 @@
+//! Parsers for the output-to-workspace mapping syntax `OUTPUT:WORKSPACES`.
+//!
+//! `WORKSPACES` is a comma-separated list of workspace numbers and inclusive ranges `from-to`,
+//! e.g., `DP-1:1-3,7,9`. Errors point at the offending part of the input and suggest a
+//! correction where one can be guessed, so that misuse in `exec` lines of the sway config can be
+//! diagnosed from the logs.
+
+use std::{collections::HashMap, fmt};
+
+/// A mapping string which could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The complete input.
+    pub input: String,
+    /// The byte offset of the offending part in `input`.
+    pub offset: usize,
+    /// The length of the offending part in bytes.
+    pub len: usize,
+    /// What is wrong with the offending part.
+    pub message: String,
+    /// A corrected version of the complete input, if one could be guessed.
+    pub suggestion: Option<String>,
+}
+
+impl ParseError {
+    fn new(input: &str, offset: usize, len: usize, message: String) -> ParseError {
+        ParseError {
+            input: input.to_owned(),
+            offset,
+            len,
+            message,
+            suggestion: None,
+        }
+    }
+
+    /// Suggests replacing the offending part by `replacement`.
+    fn replace_with(mut self, replacement: &str) -> ParseError {
+        self.suggestion = Some(format!(
+            "{}{replacement}{}",
+            &self.input[..self.offset],
+            &self.input[self.offset + self.len..]
+        ));
+        self
+    }
+
+    /// Returns the (1-based) character position of the offending part.
+    pub fn position(&self) -> usize {
+        self.input[..self.offset].chars().count() + 1
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.input[self.offset..self.offset + self.len]
+            .chars()
+            .count()
+            .max(1);
+        write!(
+            f,
+            "{} at position {}\n    {}\n    {}{}",
+            self.message,
+            self.position(),
+            self.input,
+            " ".repeat(self.position() - 1),
+            "^".repeat(width)
+        )?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\n  did you mean '{suggestion}'?")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses a complete mapping `OUTPUT:WORKSPACES`.
+///
+/// The returned workspaces are sorted and free of duplicates.
+pub fn parse_mapping(input: &str) -> Result<(String, Vec<i32>), ParseError> {
+    let (output, offset, workspaces) = split_mapping(input)?;
+    Ok((
+        output.to_owned(),
+        parse_workspaces(input, offset, workspaces)?,
+    ))
+}
+
+/// Describes all conflicts between the parsed `mappings`, i.e., outputs given more than once and
+/// workspaces mapped to more than one output.
+pub fn conflicts(mappings: &[(String, Vec<i32>)]) -> Vec<String> {
+    let mut conflicts = Vec::new();
+    for (index, (output, workspaces)) in mappings.iter().enumerate() {
+        for (other, other_workspaces) in &mappings[index + 1..] {
+            if output == other {
+                conflicts.push(format!("output '{output}' is mapped more than once"));
+                continue;
+            }
+            let shared: Vec<String> = workspaces
+                .iter()
+                .filter(|num| other_workspaces.contains(num))
+                .map(i32::to_string)
+                .collect();
+            match shared.len() {
+                0 => (),
+                1 => conflicts.push(format!(
+                    "workspace {} is mapped to both '{output}' and '{other}'",
+                    shared[0]
+                )),
+                _ => conflicts.push(format!(
+                    "workspaces {} are mapped to both '{output}' and '{other}'",
+                    shared.join(",")
+                )),
+            }
+        }
+    }
+    conflicts
+}
+
+/// Returns the `stored` mapping with the new `mappings` applied, i.e., each output given in
+/// `mappings` replaces its stored entry.
+///
+/// The stored entries come first, sorted by output, so the [`conflicts`] of the result name the
+/// new mappings last.
+pub fn merge(
+    stored: &HashMap<String, Vec<i32>>,
+    mappings: &[(String, Vec<i32>)],
+) -> Vec<(String, Vec<i32>)> {
+    let mut merged: Vec<(String, Vec<i32>)> = stored
+        .iter()
+        .filter(|(output, _)| !mappings.iter().any(|(other, _)| other == *output))
+        .map(|(output, workspaces)| (output.to_owned(), workspaces.to_owned()))
+        .collect();
+    merged.sort();
+    merged.extend(mappings.iter().cloned());
+    merged
+}
+
+/// Splits a mapping at the last colon into the output, the offset of the workspaces in `input`,
+/// and the workspaces.
+pub fn split_mapping(input: &str) -> Result<(&str, usize, &str), ParseError> {
+    let Some(index) = input.rfind(':') else {
+        let err = ParseError::new(
+            input,
+            0,
+            input.len(),
+            "missing ':' between output and workspaces".to_owned(),
+        );
+        return Err(match input.rfind('=') {
+            Some(index) => ParseError {
+                offset: index,
+                len: 1,
+                ..err
+            }
+            .replace_with(":"),
+            None => err,
+        });
+    };
+    let (output, workspaces) = (&input[..index], &input[index + 1..]);
+    if output.trim().is_empty() {
+        return Err(ParseError::new(
+            input,
+            0,
+            index,
+            "missing output name".to_owned(),
+        ));
+    }
+    if workspaces.trim().is_empty() {
+        return Err(ParseError::new(
+            input,
+            index + 1,
+            workspaces.len(),
+            "missing workspaces after ':'".to_owned(),
+        ));
+    }
+    Ok((output, index + 1, workspaces))
+}
+
+/// Parses the comma-separated `list` of workspace numbers and ranges found at `offset` in
+/// `input`.
+///
+/// The returned workspaces are sorted and free of duplicates.
+pub fn parse_workspaces(input: &str, offset: usize, list: &str) -> Result<Vec<i32>, ParseError> {
+    let mut workspaces = Vec::new();
+    let mut segment_offset = offset;
+    for segment in list.split(',') {
+        if segment.trim().is_empty() {
+            let mut err = ParseError::new(
+                input,
+                segment_offset,
+                segment.len(),
+                "empty workspace".to_owned(),
+            );
+            let cleaned = list
+                .split(',')
+                .filter(|segment| !segment.trim().is_empty())
+                .collect::<Vec<_>>()
+                .join(",");
+            if !cleaned.is_empty() {
+                err.suggestion = Some(format!(
+                    "{}{cleaned}{}",
+                    &input[..offset],
+                    &input[offset + list.len()..]
+                ));
+            }
+            return Err(err);
+        }
+        workspaces.extend(parse_segment(input, segment_offset, segment)?);
+        segment_offset += segment.len() + 1;
+    }
+    workspaces.sort();
+    workspaces.dedup();
+    Ok(workspaces)
+}
+
+/// Parses the single workspace number or range `from-to` found at `offset` in `input`.
+pub fn parse_segment(input: &str, offset: usize, segment: &str) -> Result<Vec<i32>, ParseError> {
+    if let Some(index) = segment.find("..") {
+        let err = ParseError::new(
+            input,
+            offset + index,
+            2,
+            "ranges are written as 'from-to'".to_owned(),
+        );
+        return Err(err.replace_with("-"));
+    }
+    let Some((left, right)) = segment.split_once('-') else {
+        return Ok(vec![parse_number(input, offset, segment, "")?]);
+    };
+    if left.trim().is_empty() {
+        return Err(ParseError::new(
+            input,
+            offset,
+            segment.len(),
+            format!("missing start of range '{segment}'"),
+        ));
+    }
+    let left = parse_number(input, offset, left, " in range")?;
+    let right = parse_number(
+        input,
+        offset + segment.len() - right.len(),
+        right,
+        " in range",
+    )?;
+    Ok(if left <= right {
+        (left..=right).collect()
+    } else {
+        (right..=left).collect()
+    })
+}
+
+/// Parses the workspace `number` found at `offset` in `input`.
+fn parse_number(
+    input: &str,
+    offset: usize,
+    number: &str,
+    context: &str,
+) -> Result<i32, ParseError> {
+    number.parse().map_err(|_| {
+        let err = ParseError::new(
+            input,
+            offset,
+            number.len(),
+            format!("invalid number '{number}'{context}"),
+        );
+        match number.trim().parse::<i32>() {
+            Ok(trimmed) => err.replace_with(&trimmed.to_string()),
+            Err(_) => err,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check_conflicts, error::Error};
+
+    fn maps(mappings: &[&str]) -> Vec<(String, Vec<i32>)> {
+        mappings
+            .iter()
+            .map(|mapping| parse_mapping(mapping).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn overlapping_ranges_and_duplicates_are_merged() {
+        assert_eq!(
+            parse_mapping("DP-1:5-3,1-4,4,9").unwrap(),
+            ("DP-1".to_owned(), vec![1, 2, 3, 4, 5, 9])
+        );
+    }
+
+    #[test]
+    fn output_may_contain_colons() {
+        assert_eq!(
+            parse_mapping("Dell X2353 0x2342:22").unwrap(),
+            ("Dell X2353 0x2342".to_owned(), vec![22])
+        );
+    }
+
+    #[test]
+    fn invalid_number_in_range_is_pointed_at() {
+        let err = parse_mapping("VGA-1:3-x").unwrap_err();
+        assert_eq!((err.offset, err.len, err.position()), (8, 1, 9));
+        assert_eq!(
+            err.to_string(),
+            "invalid number 'x' in range at position 9\n    VGA-1:3-x\n            ^"
+        );
+    }
+
+    #[test]
+    fn corrections_are_suggested() {
+        let err = parse_mapping("VGA-1:1..3").unwrap_err();
+        assert_eq!(err.suggestion.as_deref(), Some("VGA-1:1-3"));
+        let err = parse_mapping("VGA-1=1").unwrap_err();
+        assert_eq!(err.suggestion.as_deref(), Some("VGA-1:1"));
+        let err = parse_mapping("VGA-1:1,,2").unwrap_err();
+        assert_eq!(err.suggestion.as_deref(), Some("VGA-1:1,2"));
+    }
+
+    #[test]
+    fn conflicts_name_outputs_and_shared_workspaces() {
+        assert_eq!(
+            conflicts(&maps(&["A:1-3", "B:3-5", "A:9"])),
+            [
+                "workspace 3 is mapped to both 'A' and 'B'",
+                "output 'A' is mapped more than once",
+            ]
+        );
+        assert!(conflicts(&maps(&["A:1-3", "B:4-5"])).is_empty());
+    }
+
+    #[test]
+    fn conflicts_are_forced() {
+        let conflicting = maps(&["A:1-3", "B:2-3"]);
+        assert!(matches!(
+            check_conflicts(&conflicting, false),
+            Err(Error::ConflictingMappings(conflicts)) if conflicts.len() == 1
+        ));
+        assert!(check_conflicts(&conflicting, true).is_ok());
+    }
+
+    #[test]
+    fn new_maps_conflicting_with_stored_ones_are_detected() {
+        let stored = HashMap::from([("A".to_owned(), vec![5]), ("C".to_owned(), vec![7])]);
+        let merged = merge(&stored, &maps(&["B:5"]));
+        assert_eq!(
+            conflicts(&merged),
+            ["workspace 5 is mapped to both 'A' and 'B'"]
+        );
+        // A new map for an output replaces its stored one.
+        assert!(conflicts(&merge(&stored, &maps(&["A:6", "B:5"]))).is_empty());
+    }
+}
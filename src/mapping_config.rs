@@ -0,0 +1,120 @@
+//! TOML files describing the output-to-workspace mapping.
+//!
+//! Each output gets a section named by the output (its name or `make model serial`) with the
+//! workspaces in the mapping syntax and optional fallback outputs, which receive the workspaces if
+//! the output is not connected:
+//!
+//! ```toml
+//! # The external monitor at the desk
+//! [output."Dell Inc. DELL U2415 7MT0186417KS"]
+//! workspaces = "1-5,9"
+//! fallback = ["HDMI-A-1", "eDP-1"]
+//!
+//! [output.eDP-1]
+//! workspaces = "6-8"
+//! ```
+
+use crate::{
+    check_conflicts,
+    error::Fallible,
+    mapping::{self, ParseError},
+    output_if_exists, sway_config, Connection, WorkspaceTarget,
+};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+};
+
+/// The contents of a mapping file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MappingConfig {
+    #[serde(default)]
+    pub output: BTreeMap<String, OutputSection>,
+}
+
+/// The section of one output.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputSection {
+    /// The workspaces as comma-separated numbers and ranges, e.g., `1-3,7,9`.
+    pub workspaces: String,
+    /// The outputs to use in this order if the output is not connected.
+    #[serde(default)]
+    pub fallback: Vec<String>,
+}
+
+impl MappingConfig {
+    /// Loads the mapping file at `path`.
+    pub fn load(path: &str) -> Fallible<MappingConfig> {
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Creates the mapping from the `workspace <num> output <outputs…>` assignments of the sway
+    /// `config`, where all but the first output are fallbacks.
+    ///
+    /// Workspaces without a number are skipped as the mapping only assigns numbers.
+    pub fn from_sway_config(config: &str) -> MappingConfig {
+        let mut mapping = MappingConfig::default();
+        for (workspace, outputs) in sway_config::workspace_outputs(config) {
+            let Some(num) = WorkspaceTarget::parse(&workspace).num else {
+                continue;
+            };
+            let section = mapping
+                .output
+                .entry(outputs[0].to_owned())
+                .or_insert_with(|| OutputSection {
+                    workspaces: String::new(),
+                    fallback: outputs[1..].to_vec(),
+                });
+            if !section.workspaces.is_empty() {
+                section.workspaces.push(',');
+            }
+            section.workspaces.push_str(&num.to_string());
+        }
+        mapping
+    }
+
+    /// Returns the workspaces of each section by output, see [`mapping::parse_workspaces`].
+    pub fn mappings(&self) -> Result<Vec<(String, Vec<i32>)>, ParseError> {
+        self.output
+            .iter()
+            .map(|(output, section)| {
+                let workspaces = &section.workspaces;
+                Ok((
+                    output.to_owned(),
+                    mapping::parse_workspaces(workspaces, 0, workspaces)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Creates a configuration for the connected outputs, where the workspaces of a section go to
+    /// its first connected output or fallback.
+    ///
+    /// Unless `force` is set, sections which map a workspace more than once are rejected.
+    pub fn resolve(
+        &self,
+        force: bool,
+        sway: &mut Connection,
+    ) -> Fallible<HashMap<String, Vec<i32>>> {
+        let mappings = self.mappings()?;
+        check_conflicts(&mappings, force)?;
+
+        let mut config: HashMap<String, Vec<i32>> = HashMap::new();
+        for ((output, workspaces), section) in mappings.into_iter().zip(self.output.values()) {
+            let connected = std::iter::once(&output)
+                .chain(&section.fallback)
+                .find_map(|output| output_if_exists(output.to_owned(), sway));
+            if let Some(connected) = connected {
+                config.entry(connected).or_default().extend(workspaces);
+            }
+        }
+        for workspaces in config.values_mut() {
+            workspaces.sort();
+            workspaces.dedup();
+        }
+        Ok(config)
+    }
+}
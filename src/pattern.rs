@@ -0,0 +1,37 @@
+//! Patterns matching identifiers, names, or app ids.
+//!
+//! A pattern is a glob (a value without wildcards matches only itself) or a regular expression
+//! enclosed in slashes like `/^1:1:AT_/`.
+
+use crate::error::{Error, Fallible};
+use regex::Regex;
+
+/// A glob pattern or a regular expression enclosed in slashes.
+pub enum Pattern {
+    Glob(glob::Pattern),
+    Regex(Regex),
+}
+
+impl Pattern {
+    pub fn parse(entry: &str) -> Fallible<Pattern> {
+        let invalid = |err: String| Error::Invalid("pattern", entry.to_owned(), err);
+        match entry
+            .strip_prefix('/')
+            .and_then(|entry| entry.strip_suffix('/'))
+        {
+            Some(regex) => Ok(Pattern::Regex(
+                Regex::new(regex).map_err(|err| invalid(err.to_string()))?,
+            )),
+            None => Ok(Pattern::Glob(
+                glob::Pattern::new(entry).map_err(|err| invalid(err.to_string()))?,
+            )),
+        }
+    }
+
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            Pattern::Glob(pattern) => pattern.matches(value),
+            Pattern::Regex(regex) => regex.is_match(value),
+        }
+    }
+}
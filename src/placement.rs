@@ -0,0 +1,370 @@
+//! Moving windows to workspaces which are opened on a given output.
+//!
+//! Sway creates a missing workspace on the focused output, so a new workspace has to be moved to
+//! its output afterwards. Focusing it for that would destroy the workspace the window came from
+//! if it became empty, and focusing that workspace again would then recreate it on the wrong
+//! output. Instead the workspace is moved by criteria once sway reported its creation, and only
+//! workspaces which still exist are focused again.
+
+use crate::{
+    connection::{self, Backend},
+    error::{Error, Fallible},
+    quote, Connection, WorkspaceTarget,
+};
+use swayipc::{Event, EventType, Workspace, WorkspaceChange};
+
+/// The operations of a connection to sway needed to place workspaces.
+pub trait WorkspaceIpc {
+    /// Returns all workspaces.
+    fn workspaces(&mut self) -> Fallible<Vec<Workspace>>;
+
+    /// Runs `command` and fails if any part of it failed.
+    fn run(&mut self, command: &str) -> Fallible<()>;
+
+    /// Returns the window manager whose command dialect is used.
+    fn backend(&self) -> Backend {
+        Backend::Sway
+    }
+
+    /// Runs `command` and waits until sway reports that the workspace `name` was created.
+    fn run_and_wait_for_workspace(&mut self, command: &str, name: &str) -> Fallible<()>;
+}
+
+impl WorkspaceIpc for Connection {
+    fn backend(&self) -> Backend {
+        Connection::backend(self)
+    }
+
+    fn workspaces(&mut self) -> Fallible<Vec<Workspace>> {
+        Ok(self.get_workspaces()?)
+    }
+
+    fn run(&mut self, command: &str) -> Fallible<()> {
+        Connection::run(self, command)
+    }
+
+    /// Waits at most [`crate::env::ipc_timeout`] for each event.
+    fn run_and_wait_for_workspace(&mut self, command: &str, name: &str) -> Fallible<()> {
+        if self.dry_run() {
+            return Connection::run(self, command);
+        }
+        // Subscribe before running the command so that the event cannot be missed.
+        let events = connection::connect()?.subscribe([EventType::Workspace])?;
+        Connection::run(self, command)?;
+        for event in events {
+            if let Event::Workspace(event) = event? {
+                let created = event.current.and_then(|current| current.name);
+                if event.change == WorkspaceChange::Init && created.as_deref() == Some(name) {
+                    return Ok(());
+                }
+            }
+        }
+        Err(Error::Timeout)
+    }
+}
+
+/// Moves the window selected by `criteria` (the focused one if empty) to `workspace`.
+///
+/// If the workspace does not exist yet, it is opened on `output` while the workspace previously
+/// visible on that output and the previously focused workspace are shown again. With `follow` the
+/// moved window's workspace is focused instead.
+pub fn move_window<S: WorkspaceIpc>(
+    sway: &mut S,
+    criteria: &str,
+    workspace: &WorkspaceTarget,
+    output: Option<&str>,
+    follow: bool,
+) -> Fallible<()> {
+    let before = sway.workspaces()?;
+    let move_window = format!(
+        "{criteria}{}",
+        sway.backend().move_to_workspace(&workspace.selector())
+    );
+    let focus_target = format!(
+        "workspace --no-auto-back-and-forth {}",
+        workspace.selector()
+    );
+
+    let Some(output) = placement(&before, workspace, output) else {
+        if follow {
+            return sway.run(&format!("{move_window}; {focus_target}"));
+        }
+        return sway.run(&move_window);
+    };
+
+    let name = workspace.created_name();
+    sway.run_and_wait_for_workspace(&move_window, &name)?;
+    // The new workspace contains the window, so criteria select it without focusing it.
+    sway.run(&move_workspace(&name, output))?;
+    if follow {
+        return sway.run(&focus_target);
+    }
+
+    let after = sway.workspaces()?;
+    restore(sway, &before, &after)
+}
+
+/// Moves the window selected by `criteria` (the focused one if empty) to `workspace` like
+/// [`move_window`] without changing the visible or focused workspaces.
+///
+/// A new workspace is moved to `output` by criteria instead of being assigned to it, so that it
+/// is not tied to the output for the rest of the session.
+pub fn move_window_silently<S: WorkspaceIpc>(
+    sway: &mut S,
+    criteria: &str,
+    workspace: &WorkspaceTarget,
+    output: Option<&str>,
+) -> Fallible<()> {
+    move_window(sway, criteria, workspace, output, false)
+}
+
+/// Returns `output` if `workspace` has to be placed there, i.e., if it does not exist yet and would
+/// be created on another output.
+///
+/// An existing workspace stays where it is and a new one is created on the focused output.
+fn placement<'a>(
+    before: &[Workspace],
+    workspace: &WorkspaceTarget,
+    output: Option<&'a str>,
+) -> Option<&'a str> {
+    let focused = before.iter().find(|ws| ws.focused);
+    output.filter(|output| {
+        !before.iter().any(|ws| workspace.is(ws))
+            && focused.is_some_and(|focused| &focused.output != output)
+    })
+}
+
+/// Returns the command moving the workspace `name` containing windows to `output`.
+fn move_workspace(name: &str, output: &str) -> String {
+    format!(
+        "[workspace=\"^{}$\"] move workspace to output '{output}'",
+        regex::escape(name)
+    )
+}
+
+/// Shows the workspaces which were visible `before` but are not `after` again and focuses the
+/// previously focused workspace.
+///
+/// Empty workspaces which are neither visible nor focused have been destroyed in the meantime and
+/// must not be recreated by focusing them, for a destroyed focused workspace its output is
+/// focused instead.
+fn restore<S: WorkspaceIpc>(
+    sway: &mut S,
+    before: &[Workspace],
+    after: &[Workspace],
+) -> Fallible<()> {
+    let find = |name: &str| after.iter().find(|ws| ws.name == name);
+    let mut commands: Vec<String> = before
+        .iter()
+        .filter(|ws| ws.visible && !ws.focused)
+        .filter(|ws| find(&ws.name).is_some_and(|ws| !ws.visible))
+        .map(|ws| format!("workspace --no-auto-back-and-forth {}", quote(&ws.name)))
+        .collect();
+    if let Some(focused) = before.iter().find(|ws| ws.focused) {
+        match find(&focused.name) {
+            // Showing other workspaces may have moved the focus away.
+            Some(ws) if ws.focused && commands.is_empty() => (),
+            Some(_) => commands.push(format!(
+                "workspace --no-auto-back-and-forth {}",
+                quote(&focused.name)
+            )),
+            None => commands.push(format!("focus output '{}'", focused.output)),
+        }
+    }
+    if commands.is_empty() {
+        return Ok(());
+    }
+    sway.run(&commands.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Replies with the given workspace snapshots in order and records all commands.
+    struct MockSway {
+        backend: Backend,
+        snapshots: Vec<Vec<Workspace>>,
+        commands: Vec<String>,
+        waited_for: Vec<String>,
+    }
+
+    impl MockSway {
+        fn new(snapshots: Vec<Vec<Workspace>>) -> MockSway {
+            MockSway {
+                backend: Backend::Sway,
+                snapshots,
+                commands: Vec::new(),
+                waited_for: Vec::new(),
+            }
+        }
+    }
+
+    impl WorkspaceIpc for MockSway {
+        fn backend(&self) -> Backend {
+            self.backend
+        }
+
+        fn workspaces(&mut self) -> Fallible<Vec<Workspace>> {
+            Ok(self.snapshots.remove(0))
+        }
+
+        fn run(&mut self, command: &str) -> Fallible<()> {
+            self.commands.push(command.to_owned());
+            Ok(())
+        }
+
+        fn run_and_wait_for_workspace(&mut self, command: &str, name: &str) -> Fallible<()> {
+            self.commands.push(command.to_owned());
+            self.waited_for.push(name.to_owned());
+            Ok(())
+        }
+    }
+
+    fn ws(name: &str, output: &str, visible: bool, focused: bool) -> Workspace {
+        serde_json::from_value(json!({
+            "id": 0,
+            "num": name.parse::<i32>().unwrap_or(-1),
+            "name": name,
+            "visible": visible,
+            "focused": focused,
+            "urgent": false,
+            "rect": {"x": 0, "y": 0, "width": 0, "height": 0},
+            "output": output,
+        }))
+        .unwrap()
+    }
+
+    fn target(workspace: &str) -> WorkspaceTarget {
+        WorkspaceTarget::parse(workspace)
+    }
+
+    #[test]
+    fn existing_workspace_is_not_placed() {
+        let mut sway = MockSway::new(vec![vec![
+            ws("1", "OUT1", true, true),
+            ws("2", "OUT2", true, false),
+        ]]);
+        move_window(&mut sway, "", &target("2"), Some("OUT1"), false).unwrap();
+        assert_eq!(sway.commands, ["move to workspace number 2"]);
+        assert!(sway.waited_for.is_empty());
+    }
+
+    #[test]
+    fn new_workspace_on_focused_output_is_not_placed() {
+        let mut sway = MockSway::new(vec![vec![ws("1", "OUT1", true, true)]]);
+        move_window(&mut sway, "", &target("3"), Some("OUT1"), true).unwrap();
+        assert_eq!(
+            sway.commands,
+            ["move to workspace number 3; workspace --no-auto-back-and-forth number 3"]
+        );
+    }
+
+    #[test]
+    fn new_workspace_is_placed_and_visibility_restored() {
+        let mut sway = MockSway::new(vec![
+            vec![ws("1", "OUT1", true, true), ws("2", "OUT2", true, false)],
+            vec![
+                ws("1", "OUT1", true, true),
+                ws("2", "OUT2", false, false),
+                ws("3", "OUT2", true, false),
+            ],
+        ]);
+        move_window(&mut sway, "[con_id=7] ", &target("3"), Some("OUT2"), false).unwrap();
+        assert_eq!(sway.waited_for, ["3"]);
+        assert_eq!(
+            sway.commands,
+            [
+                "[con_id=7] move to workspace number 3",
+                "[workspace=\"^3$\"] move workspace to output 'OUT2'",
+                "workspace --no-auto-back-and-forth '2'; workspace --no-auto-back-and-forth '1'",
+            ]
+        );
+    }
+
+    /// The situation of the former FIXME: the focused workspace 2 on OUT2 only contains the
+    /// window, which is moved to the new workspace 3 mapped to the empty workspace 1 on OUT1.
+    #[test]
+    fn destroyed_workspaces_are_not_recreated() {
+        let mut sway = MockSway::new(vec![
+            vec![ws("1", "OUT1", true, false), ws("2", "OUT2", true, true)],
+            // Moving workspace 3 to OUT1 focused it and destroyed the now empty workspaces.
+            vec![ws("3", "OUT1", true, true), ws("4", "OUT2", true, false)],
+        ]);
+        move_window(&mut sway, "", &target("3"), Some("OUT1"), false).unwrap();
+        assert_eq!(
+            sway.commands,
+            [
+                "move to workspace number 3",
+                "[workspace=\"^3$\"] move workspace to output 'OUT1'",
+                "focus output 'OUT2'",
+            ]
+        );
+    }
+
+    #[test]
+    fn silent_move_places_without_assigning() {
+        let mut sway = MockSway::new(vec![
+            vec![ws("1", "OUT1", true, true), ws("2", "OUT2", true, false)],
+            vec![
+                ws("1", "OUT1", true, false),
+                ws("2", "OUT2", false, false),
+                ws("3", "OUT2", true, true),
+            ],
+        ]);
+        move_window_silently(&mut sway, "", &target("3"), Some("OUT2")).unwrap();
+        assert_eq!(sway.waited_for, ["3"]);
+        assert_eq!(
+            sway.commands,
+            [
+                "move to workspace number 3",
+                "[workspace=\"^3$\"] move workspace to output 'OUT2'",
+                "workspace --no-auto-back-and-forth '2'; workspace --no-auto-back-and-forth '1'",
+            ]
+        );
+    }
+
+    #[test]
+    fn quotes_in_names_are_escaped() {
+        let mut sway = MockSway::new(vec![
+            vec![ws("1", "OUT1", true, true), ws("it's", "OUT2", true, false)],
+            vec![
+                ws("1", "OUT1", true, true),
+                ws("it's", "OUT2", false, false),
+                ws("3", "OUT2", true, false),
+            ],
+        ]);
+        move_window(&mut sway, "", &target("3"), Some("OUT2"), false).unwrap();
+        assert_eq!(
+            sway.commands[2],
+            r"workspace --no-auto-back-and-forth 'it'\''s'; workspace --no-auto-back-and-forth '1'"
+        );
+    }
+
+    #[test]
+    fn i3_dialect_moves_containers() {
+        let mut sway = MockSway::new(vec![vec![ws("1", "OUT1", true, true)]]);
+        sway.backend = Backend::I3;
+        move_window(&mut sway, "", &target("2"), None, false).unwrap();
+        assert_eq!(sway.commands, ["move container to workspace number 2"]);
+    }
+
+    #[test]
+    fn follow_focuses_the_placed_workspace() {
+        let mut sway = MockSway::new(vec![vec![
+            ws("1", "OUT1", true, true),
+            ws("2", "OUT2", true, false),
+        ]]);
+        move_window(&mut sway, "", &target("5:mail"), Some("OUT2"), true).unwrap();
+        assert_eq!(sway.waited_for, ["5:mail"]);
+        assert_eq!(
+            sway.commands,
+            [
+                "move to workspace number '5:mail'",
+                "[workspace=\"^5:mail$\"] move workspace to output 'OUT2'",
+                "workspace --no-auto-back-and-forth number '5:mail'",
+            ]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Seat-aware lookups of the focused workspace and output.
+//!
+//! sway reports a single `focused` flag for workspaces and outputs which belongs to the most
+//! recently active seat. On multi-seat setups every seat has its own focus, which is resolved
+//! here from the seat's focused node in the tree.
+
+use crate::{
+    env,
+    error::{Error, Fallible},
+    tree, Connection,
+};
+use std::{convert::Infallible, str::FromStr};
+use swayipc::{Output, Seat, Workspace};
+
+/// Selects the seat whose focus is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeatSelector {
+    /// The seat with the given name (as reported by `get_seats`).
+    Name(String),
+    /// The seat the keyboard with the given identifier is attached to.
+    Keyboard(String),
+}
+
+impl FromStr for SeatSelector {
+    type Err = Infallible;
+
+    /// Parses `keyboard:<identifier>` as [`SeatSelector::Keyboard`] and anything else as a seat
+    /// name.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.strip_prefix("keyboard:") {
+            Some(identifier) => SeatSelector::Keyboard(identifier.to_owned()),
+            None => SeatSelector::Name(s.to_owned()),
+        })
+    }
+}
+
+impl SeatSelector {
+    /// Resolves the seat selector from the command line value or `$SWAYTOOLS_SEAT`.
+    ///
+    /// Returns `None` if neither is given, the global focus is then used.
+    pub fn resolve(cli: Option<SeatSelector>) -> Option<SeatSelector> {
+        cli.or_else(|| env::var(env::SEAT).and_then(|seat| seat.parse().ok()))
+    }
+
+    /// Returns whether `seat` is selected.
+    pub fn matches(&self, seat: &Seat) -> bool {
+        match self {
+            SeatSelector::Name(name) => &seat.name == name,
+            SeatSelector::Keyboard(identifier) => seat
+                .devices
+                .iter()
+                .any(|device| device.input_type == "keyboard" && &device.identifier == identifier),
+        }
+    }
+}
+
+/// Returns the selected seat.
+pub fn find_seat(sway: &mut Connection, selector: &SeatSelector) -> Fallible<Seat> {
+    sway.get_seats()?
+        .into_iter()
+        .find(|seat| selector.matches(seat))
+        .ok_or(Error::SeatNotFound)
+}
+
+/// Returns the names of the output and workspace focused by the selected seat.
+pub fn focus_for_seat(
+    sway: &mut Connection,
+    selector: &SeatSelector,
+) -> Fallible<(Option<String>, Option<String>)> {
+    let seat = find_seat(sway, selector)?;
+    let tree = sway.get_tree()?;
+    Ok(tree::find_by_id(&tree, seat.focus)
+        .map(|location| {
+            (
+                location.output_name().map(str::to_owned),
+                location.workspace_name().map(str::to_owned),
+            )
+        })
+        .unwrap_or_default())
+}
+
+/// Returns the workspace focused by the selected seat.
+pub fn focused_workspace_for_seat(
+    sway: &mut Connection,
+    selector: &SeatSelector,
+) -> Fallible<Option<Workspace>> {
+    let (_, workspace) = focus_for_seat(sway, selector)?;
+    Ok(workspace.and_then(|name| {
+        sway.get_workspaces()
+            .unwrap_or_default()
+            .into_iter()
+            .find(|workspace| workspace.name == name)
+    }))
+}
+
+/// Returns the output focused by the selected seat.
+pub fn focused_output_for_seat(
+    sway: &mut Connection,
+    selector: &SeatSelector,
+) -> Fallible<Option<Output>> {
+    let (output, _) = focus_for_seat(sway, selector)?;
+    Ok(output.and_then(|name| {
+        sway.get_outputs()
+            .unwrap_or_default()
+            .into_iter()
+            .find(|output| output.name == name)
+    }))
+}
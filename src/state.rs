@@ -0,0 +1,271 @@
+//! The session state shared by all binaries.
+//!
+//! All state lives in a single versioned JSON file (`swaytools-state-<session>.json` in the runtime
+//! directory by default, see [`env::session_file`]). Every access takes a lock on a sidecar
+//! `.lock` file so that concurrently running tools do not overwrite each other's changes. If the
+//! state file does not exist yet, the state is imported from the files used by earlier versions
+//! (`ws.json`, `ws-prev.json`, and `sway-workspaces-outputs.json`).
+
+use crate::{
+    env,
+    error::{Error, Fallible},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+/// The current version of the state file schema.
+pub const STATE_VERSION: u32 = 1;
+
+/// The maximal number of entries kept in the workspace and window histories.
+pub const MAX_HISTORY: usize = 32;
+
+/// A workspace identified by its name and number.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceRef {
+    pub name: String,
+    pub num: i32,
+}
+
+/// The position and size of a floating window in absolute coordinates.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FloatingGeometry {
+    pub id: i64,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// A window sent to the scratchpad together with where it came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MinimizedWindow {
+    pub id: i64,
+    pub workspace: String,
+    pub floating: bool,
+}
+
+/// A window which demanded attention.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UrgentWindow {
+    pub id: i64,
+    /// When the window became urgent in seconds since the Unix epoch.
+    pub since: u64,
+}
+
+/// The split layout of a container and the sizes of its children before it was made tabbed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSplit {
+    /// `splith` or `splitv`.
+    pub layout: String,
+    /// The ids of the children and their shares of the container.
+    pub sizes: Vec<(i64, f64)>,
+}
+
+/// The content of the state file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct State {
+    /// The schema version this state was written with.
+    pub version: u32,
+    /// The output-to-workspace mapping.
+    #[serde(default)]
+    pub mapping: HashMap<String, Vec<i32>>,
+    /// The previously focused workspace.
+    #[serde(default)]
+    pub previous: Option<WorkspaceRef>,
+    /// The previously focused workspaces, most recent first.
+    #[serde(default)]
+    pub history: Vec<WorkspaceRef>,
+    /// The ids of pinned windows.
+    #[serde(default)]
+    pub pinned: Vec<i64>,
+    /// The ids of the recently focused windows, most recent first.
+    #[serde(default)]
+    pub windows: Vec<i64>,
+    /// The outputs powered off to leave only one output on, by make, model, and serial number.
+    #[serde(default)]
+    pub powered_off: Vec<String>,
+    /// The workspaces moved away from the internal output while it was disabled.
+    #[serde(default)]
+    pub evacuated: Vec<i32>,
+    /// The floating windows by the sorted identifiers of the outputs connected at the time.
+    #[serde(default)]
+    pub floating: HashMap<String, Vec<FloatingGeometry>>,
+    /// The minimized windows, most recently minimized first.
+    #[serde(default)]
+    pub minimized: Vec<MinimizedWindow>,
+    /// The workspaces on which the golden ratio daemon resizes the focused window.
+    #[serde(default)]
+    pub golden_ratio: Vec<String>,
+    /// The split layouts of the containers made tabbed by `layout-toggle`, by container id.
+    #[serde(default)]
+    pub split_layouts: HashMap<i64, SavedSplit>,
+    /// The windows which became urgent, most recent first, even if their urgency was dismissed.
+    #[serde(default)]
+    pub urgent: Vec<UrgentWindow>,
+    /// The primary output followed by its fallbacks, by name or make, model, and serial number.
+    #[serde(default)]
+    pub primary: Vec<String>,
+    /// The data of single tools by tool name, which is up to each tool, see [`State::tool`].
+    #[serde(default)]
+    pub tools: HashMap<String, serde_json::Value>,
+    /// The fields written by newer versions, kept so that older tools do not drop them.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl State {
+    /// Records `workspace` as the previously focused workspace.
+    pub fn push_previous(&mut self, workspace: WorkspaceRef) {
+        self.history.retain(|ws| ws != &workspace);
+        self.history.insert(0, workspace.clone());
+        self.history.truncate(MAX_HISTORY);
+        self.previous = Some(workspace);
+    }
+
+    /// Records the window with the given `id` as the most recently focused window.
+    pub fn push_window(&mut self, id: i64) {
+        self.windows.retain(|&window| window != id);
+        self.windows.insert(0, id);
+        self.windows.truncate(MAX_HISTORY);
+    }
+
+    /// Records the window with the given `id` as the most recently urgent window.
+    pub fn push_urgent(&mut self, id: i64, since: u64) {
+        self.urgent.retain(|window| window.id != id);
+        self.urgent.insert(0, UrgentWindow { id, since });
+        self.urgent.truncate(MAX_HISTORY);
+    }
+
+    /// Returns the data stored under the tool `name`, `None` if there is none.
+    pub fn tool<T: DeserializeOwned>(&self, name: &str) -> Fallible<Option<T>> {
+        Ok(self.tools.get(name).map(T::deserialize).transpose()?)
+    }
+
+    /// Imports the state from the files used by earlier versions.
+    fn import_legacy() -> State {
+        let mut state = State {
+            version: STATE_VERSION,
+            ..Default::default()
+        };
+        for file_name in ["ws.json", "sway-workspaces-outputs.json"] {
+            if let Some(mapping) = read_json(&env::runtime_file(file_name)) {
+                state.mapping = mapping;
+                break;
+            }
+        }
+        if let Some((name, num)) = read_json(&env::runtime_file("ws-prev.json")) {
+            state.push_previous(WorkspaceRef { name, num });
+        }
+        state
+    }
+
+    /// Brings a state read from disk up to the current schema version.
+    fn migrate(mut self) -> Fallible<State> {
+        if self.version > STATE_VERSION {
+            return Err(Error::UnsupportedStateVersion(self.version));
+        }
+        self.version = STATE_VERSION;
+        Ok(self)
+    }
+}
+
+/// Reads and deserializes the JSON file at `path`, ignoring all errors.
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Option<T> {
+    let json = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// The file the [`State`] is stored in.
+pub struct StateFile {
+    path: PathBuf,
+}
+
+impl StateFile {
+    /// Uses the state file at the given `path`.
+    pub fn new<P: Into<PathBuf>>(path: P) -> StateFile {
+        StateFile { path: path.into() }
+    }
+
+    /// Uses `$SWAYTOOLS_STATE_FILE` or the state file of the current sway session in the runtime
+    /// directory.
+    pub fn open_default() -> StateFile {
+        StateFile::new(
+            env::var(env::STATE_FILE)
+                .map(|path| env::expand_runtime_dir(&path).into())
+                .unwrap_or_else(|| env::session_file("swaytools-state", "json")),
+        )
+    }
+
+    /// Returns the path of the state file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Locks the state file, exclusively if `exclusive` is set.
+    ///
+    /// The lock is released when the returned file is dropped.
+    fn lock(&self, exclusive: bool) -> Fallible<File> {
+        let mut lock_path = self.path.to_owned().into_os_string();
+        lock_path.push(".lock");
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(lock_path)?;
+        if exclusive {
+            file.lock()?;
+        } else {
+            file.lock_shared()?;
+        }
+        Ok(file)
+    }
+
+    /// Reads the state without holding a lock.
+    fn read(&self) -> Fallible<State> {
+        match fs::read_to_string(&self.path) {
+            Ok(json) => serde_json::from_str::<State>(&json)?.migrate(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(State::import_legacy()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Loads the current state.
+    pub fn load(&self) -> Fallible<State> {
+        let _lock = self.lock(false)?;
+        self.read()
+    }
+
+    /// Applies `change` to the current state and stores the result.
+    ///
+    /// The state file is locked for the whole operation and replaced atomically.
+    pub fn update<T, F: FnOnce(&mut State) -> T>(&self, change: F) -> Fallible<T> {
+        let _lock = self.lock(true)?;
+        let mut state = self.read()?;
+        let result = change(&mut state);
+
+        let mut tmp_path = self.path.to_owned().into_os_string();
+        tmp_path.push(".tmp");
+        fs::write(&tmp_path, serde_json::to_string(&state)?)?;
+        fs::rename(&tmp_path, &self.path)?;
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_fields_are_kept() {
+        let json = r#"{"version":1,"pinned":[3],"future":{"a":[1,2]}}"#;
+        let state: State = serde_json::from_str(json).unwrap();
+        assert_eq!(state.pinned, vec![3]);
+        let written = serde_json::to_value(&state).unwrap();
+        assert_eq!(written["future"], serde_json::json!({"a": [1, 2]}));
+    }
+}
@@ -0,0 +1,52 @@
+//! Helpers for reading the sway config as returned by `get_config`.
+//!
+//! Only the commands of the config itself are considered, files included by it are not.
+
+/// Returns the words of every line of `config`, joining lines continued by a trailing backslash.
+pub fn lines(config: &str) -> Vec<Vec<String>> {
+    config
+        .replace("\\\n", " ")
+        .lines()
+        .map(|line| split_words(line.trim()))
+        .collect()
+}
+
+/// Splits `line` at whitespace, keeping quoted words together (without the quotes).
+pub fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '"' | '\'') => quote = Some(c),
+            (None, c) if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            (None, c) => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Returns the workspaces assigned to outputs by top-level `workspace <name> output <outputs…>`
+/// commands together with their outputs in order of preference.
+pub fn workspace_outputs(config: &str) -> Vec<(String, Vec<String>)> {
+    lines(config)
+        .into_iter()
+        .filter_map(|words| match words.as_slice() {
+            [command, workspace, keyword, outputs @ ..]
+                if command == "workspace" && keyword == "output" && !outputs.is_empty() =>
+            {
+                Some((workspace.to_owned(), outputs.to_vec()))
+            }
+            _ => None,
+        })
+        .collect()
+}
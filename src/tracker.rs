@@ -0,0 +1,227 @@
+//! An always-current view of sway's workspaces, outputs, and focus for long-running daemons.
+//!
+//! The [`StateTracker`] subscribes to workspace, output, and window events and applies them to
+//! its cached state. Only events which cannot be applied incrementally (like a newly created
+//! workspace or an output change) cause a query to sway.
+
+use crate::{
+    connection,
+    error::Fallible,
+    events::{EventStream, StreamEvent},
+    tree,
+};
+use swayipc::{
+    Connection, Event, EventType, Node, Output, WindowChange, Workspace, WorkspaceChange,
+};
+
+/// The event types the tracker needs to keep its state current.
+const TRACKED_EVENTS: [EventType; 3] = [EventType::Workspace, EventType::Output, EventType::Window];
+
+/// Keeps track of workspaces, outputs, and the focused window by listening to events.
+pub struct StateTracker {
+    connection: Connection,
+    events: EventStream,
+    workspaces: Vec<Workspace>,
+    outputs: Vec<Output>,
+    focused_window: Option<Node>,
+}
+
+impl StateTracker {
+    /// Connects to sway, subscribes to the tracked events and `extra_events`, and queries the
+    /// initial state.
+    ///
+    /// Events of the `extra_events` types are passed through by [`StateTracker::next_event`] so
+    /// that daemons only need a single subscription.
+    pub fn new(extra_events: &[EventType]) -> Fallible<StateTracker> {
+        let mut event_types = TRACKED_EVENTS.to_vec();
+        event_types.extend(
+            extra_events
+                .iter()
+                .filter(|event_type| !TRACKED_EVENTS.contains(event_type)),
+        );
+        let mut tracker = StateTracker {
+            connection: connection::connect()?,
+            events: EventStream::subscribe(&event_types)?,
+            workspaces: Vec::new(),
+            outputs: Vec::new(),
+            focused_window: None,
+        };
+        tracker.refresh()?;
+        Ok(tracker)
+    }
+
+    /// Queries the complete state from sway again.
+    pub fn refresh(&mut self) -> Fallible<()> {
+        self.workspaces = self.connection.get_workspaces()?;
+        self.outputs = self.connection.get_outputs()?;
+        let tree = self.connection.get_tree()?;
+        self.focused_window = tree::windows(&tree)
+            .into_iter()
+            .find(|location| location.node.focused)
+            .map(|location| location.node.to_owned());
+        Ok(())
+    }
+
+    /// Waits for the next event, applies it to the tracked state, and returns it.
+    ///
+    /// After a [`StreamEvent::Reconnected`] the state has already been queried again.
+    pub fn next_event(&mut self) -> Option<StreamEvent> {
+        let event = self.events.next()?;
+        let result = match &event {
+            StreamEvent::Event(event) => self.apply(event),
+            StreamEvent::Reconnected => connection::connect().and_then(|connection| {
+                self.connection = connection;
+                self.refresh()
+            }),
+        };
+        if let Err(err) = result {
+            log::warn!("Cannot update the tracked state: {err}");
+        }
+        Some(event)
+    }
+
+    /// Applies a single event to the tracked state.
+    pub fn apply(&mut self, event: &Event) -> Fallible<()> {
+        match event {
+            Event::Workspace(ev) => match ev.change {
+                WorkspaceChange::Focus => {
+                    if let Some(current) = &ev.current {
+                        self.focus_workspace(current.id);
+                    }
+                }
+                WorkspaceChange::Empty => {
+                    if let Some(current) = &ev.current {
+                        self.workspaces.retain(|ws| ws.id != current.id);
+                    }
+                }
+                WorkspaceChange::Rename => {
+                    if let Some(current) = &ev.current {
+                        if let Some(ws) = self.workspaces.iter_mut().find(|ws| ws.id == current.id)
+                        {
+                            ws.name = current.name.to_owned().unwrap_or_default();
+                            ws.num = current.num.unwrap_or(-1);
+                        }
+                    }
+                }
+                WorkspaceChange::Urgent => {
+                    if let Some(current) = &ev.current {
+                        if let Some(ws) = self.workspaces.iter_mut().find(|ws| ws.id == current.id)
+                        {
+                            ws.urgent = current.urgent;
+                        }
+                    }
+                }
+                // New or moved workspaces need their output, which is not part of the event
+                _ => self.workspaces = self.connection.get_workspaces()?,
+            },
+            Event::Output(_) => {
+                self.outputs = self.connection.get_outputs()?;
+                self.workspaces = self.connection.get_workspaces()?;
+            }
+            Event::Window(ev) => match ev.change {
+                WindowChange::Focus => self.focused_window = Some(ev.container.to_owned()),
+                WindowChange::Close => {
+                    if self.focused_window_id() == Some(ev.container.id) {
+                        self.focused_window = None;
+                    }
+                }
+                _ => {
+                    if self.focused_window_id() == Some(ev.container.id) {
+                        self.focused_window = Some(ev.container.to_owned());
+                    }
+                }
+            },
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Marks the workspace with the given `id` as focused and visible.
+    fn focus_workspace(&mut self, id: i64) {
+        let Some(output) = self
+            .workspaces
+            .iter()
+            .find(|ws| ws.id == id)
+            .map(|ws| ws.output.to_owned())
+        else {
+            return;
+        };
+        for ws in self.workspaces.iter_mut() {
+            ws.focused = ws.id == id;
+            if ws.output == output {
+                ws.visible = ws.id == id;
+            }
+        }
+        for o in self.outputs.iter_mut() {
+            o.focused = o.name == output;
+            if o.focused {
+                o.current_workspace = self
+                    .workspaces
+                    .iter()
+                    .find(|ws| ws.id == id)
+                    .map(|ws| ws.name.to_owned());
+            }
+        }
+    }
+
+    pub fn workspaces(&self) -> &Vec<Workspace> {
+        &self.workspaces
+    }
+
+    pub fn workspace_by_num(&self, num: i32) -> Option<&Workspace> {
+        self.workspaces.iter().find(|ws| ws.num == num)
+    }
+
+    pub fn workspace_by_name(&self, name: &str) -> Option<&Workspace> {
+        self.workspaces.iter().find(|ws| ws.name == name)
+    }
+
+    pub fn workspace_by_num_or_name(
+        &self,
+        num: Option<i32>,
+        name: Option<&str>,
+    ) -> Option<&Workspace> {
+        self.workspaces.iter().find(|ws| {
+            name.is_some_and(|name| name == ws.name) || num.is_some_and(|num| num == ws.num)
+        })
+    }
+
+    pub fn focused_workspace(&self) -> Option<&Workspace> {
+        self.workspaces.iter().find(|ws| ws.focused)
+    }
+
+    pub fn visible_workspace_for_output(&self, output: &str) -> Option<&Workspace> {
+        self.workspaces
+            .iter()
+            .find(|ws| ws.output == output && ws.visible)
+    }
+
+    pub fn outputs(&self) -> &Vec<Output> {
+        &self.outputs
+    }
+
+    pub fn output_by_name_or_identifier(
+        &self,
+        name: Option<&str>,
+        identifier: Option<&str>,
+    ) -> Option<&Output> {
+        self.outputs.iter().find(|o| {
+            name.is_some_and(|name| name == o.name)
+                || identifier.is_some_and(|identifier| {
+                    identifier == format!("{} {} {}", o.make, o.model, o.serial)
+                })
+        })
+    }
+
+    pub fn focused_output(&self) -> Option<&Output> {
+        self.outputs.iter().find(|o| o.focused)
+    }
+
+    pub fn focused_window(&self) -> Option<&Node> {
+        self.focused_window.as_ref()
+    }
+
+    pub fn focused_window_id(&self) -> Option<i64> {
+        self.focused_window.as_ref().map(|node| node.id)
+    }
+}
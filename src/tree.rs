@@ -0,0 +1,103 @@
+//! Helpers for walking the layout tree returned by `get_tree`.
+
+use swayipc::{Node, NodeType};
+
+/// A node of the tree together with the output and workspace it is located on.
+#[derive(Debug, Clone, Copy)]
+pub struct Location<'a> {
+    pub node: &'a Node,
+    pub output: Option<&'a Node>,
+    pub workspace: Option<&'a Node>,
+}
+
+impl<'a> Location<'a> {
+    /// Returns the name of the output the node is located on.
+    pub fn output_name(&self) -> Option<&'a str> {
+        self.output.and_then(|output| output.name.as_deref())
+    }
+
+    /// Returns the name of the workspace the node is located on.
+    pub fn workspace_name(&self) -> Option<&'a str> {
+        self.workspace
+            .and_then(|workspace| workspace.name.as_deref())
+    }
+
+    /// Returns whether the node is a window, i.e., has no children and is not an output or
+    /// workspace.
+    pub fn is_window(&self) -> bool {
+        matches!(self.node.node_type, NodeType::Con | NodeType::FloatingCon)
+            && self.node.nodes.is_empty()
+            && self.node.floating_nodes.is_empty()
+    }
+}
+
+/// Returns the app id of a Wayland window or the class of an Xwayland window.
+pub fn app(node: &Node) -> Option<&str> {
+    node.app_id.as_deref().or_else(|| {
+        node.window_properties
+            .as_ref()
+            .and_then(|properties| properties.class.as_deref())
+    })
+}
+
+/// Returns all nodes of `tree` in depth-first order (tiling children before floating children)
+/// together with their locations.
+pub fn walk(tree: &Node) -> Vec<Location<'_>> {
+    let mut locations = Vec::new();
+    collect(tree, None, None, &mut locations);
+    locations
+}
+
+fn collect<'a>(
+    node: &'a Node,
+    mut output: Option<&'a Node>,
+    mut workspace: Option<&'a Node>,
+    locations: &mut Vec<Location<'a>>,
+) {
+    match node.node_type {
+        NodeType::Output => output = Some(node),
+        NodeType::Workspace => workspace = Some(node),
+        _ => (),
+    }
+    locations.push(Location {
+        node,
+        output,
+        workspace,
+    });
+    for child in node.nodes.iter().chain(node.floating_nodes.iter()) {
+        collect(child, output, workspace, locations);
+    }
+}
+
+/// Returns the first node of `tree` matching `predicate`.
+pub fn find<P: Fn(&Node) -> bool>(tree: &Node, predicate: P) -> Option<Location<'_>> {
+    walk(tree)
+        .into_iter()
+        .find(|location| predicate(location.node))
+}
+
+/// Returns the node with the given `id`.
+pub fn find_by_id(tree: &Node, id: i64) -> Option<Location<'_>> {
+    find(tree, |node| node.id == id)
+}
+
+/// Returns all windows of `tree`.
+pub fn windows(tree: &Node) -> Vec<Location<'_>> {
+    walk(tree).into_iter().filter(Location::is_window).collect()
+}
+
+/// Returns the nodes from the root of `tree` down to the node with the given `id`, which is the
+/// last one, or an empty list if there is no such node.
+pub fn path(tree: &Node, id: i64) -> Vec<&Node> {
+    if tree.id == id {
+        return vec![tree];
+    }
+    for child in tree.nodes.iter().chain(tree.floating_nodes.iter()) {
+        let mut path = path(child, id);
+        if !path.is_empty() {
+            path.insert(0, tree);
+            return path;
+        }
+    }
+    Vec::new()
+}